@@ -0,0 +1,255 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::IValue;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Tree of calls made during one invocation of `MarineCore::call_async`.
+#[derive(Debug, Clone)]
+pub struct CallTrace {
+    /// Top-level calls, usually there is only one: the call of the facade function.
+    pub spans: Vec<CallSpan>,
+}
+
+/// One call of a module export, either from the host or from another module via IT imports.
+#[derive(Debug, Clone)]
+pub struct CallSpan {
+    pub module_name: String,
+    pub function_name: String,
+
+    /// Approximate size of the arguments in bytes.
+    pub arguments_size: usize,
+
+    /// Approximate size of the results in bytes, 0 if the call failed.
+    pub results_size: usize,
+
+    /// Time from the trace start to the call start.
+    pub start: Duration,
+    pub duration: Duration,
+
+    /// How much the module memory grew during the call, in bytes.
+    pub memory_growth: usize,
+
+    /// Error message if the call failed.
+    pub error: Option<String>,
+
+    /// Calls made from this one into other modules.
+    pub children: Vec<CallSpan>,
+}
+
+/// Records call spans when tracing is enabled, it's shared by all modules of a `MarineCore`.
+#[derive(Clone, Default)]
+pub(crate) struct CallTracer {
+    state: Arc<Mutex<TracerState>>,
+}
+
+#[derive(Default)]
+struct TracerState {
+    enabled: bool,
+    trace_start: Option<Instant>,
+    /// Spans of the calls being executed now, the last one is the innermost.
+    stack: Vec<(Instant, CallSpan)>,
+    finished: Vec<CallSpan>,
+}
+
+/// Memory size of the module before the call, needed to compute the span memory growth.
+pub(crate) struct SpanGuard {
+    memory_size: usize,
+}
+
+impl CallTracer {
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        let mut state = self.state.lock();
+        state.enabled = enabled;
+        state.stack.clear();
+        state.finished.clear();
+        state.trace_start = None;
+    }
+
+    /// Starts a new trace, spans of the previous one are discarded.
+    pub(crate) fn start_trace(&self) {
+        let mut state = self.state.lock();
+        if !state.enabled {
+            return;
+        }
+
+        state.stack.clear();
+        state.finished.clear();
+        state.trace_start = Some(Instant::now());
+    }
+
+    /// Returns spans recorded since the last `start_trace`.
+    pub(crate) fn trace(&self) -> Option<CallTrace> {
+        let state = self.state.lock();
+        if !state.enabled || state.finished.is_empty() {
+            return None;
+        }
+
+        Some(CallTrace {
+            spans: state.finished.clone(),
+        })
+    }
+
+    /// Opens a span for a call, returns None if tracing is disabled.
+    pub(crate) fn enter(
+        &self,
+        module_name: &str,
+        function_name: &str,
+        arguments: &[IValue],
+        memory_size: impl FnOnce() -> usize,
+    ) -> Option<SpanGuard> {
+        let mut state = self.state.lock();
+        if !state.enabled {
+            return None;
+        }
+
+        let memory_size = memory_size();
+        let now = Instant::now();
+        let trace_start = *state.trace_start.get_or_insert(now);
+        let span = CallSpan {
+            module_name: module_name.to_string(),
+            function_name: function_name.to_string(),
+            arguments_size: ivalues_size(arguments),
+            results_size: 0,
+            start: now - trace_start,
+            duration: Duration::ZERO,
+            memory_growth: 0,
+            error: None,
+            children: Vec::new(),
+        };
+        state.stack.push((now, span));
+
+        Some(SpanGuard { memory_size })
+    }
+
+    /// Closes the innermost span and attaches it to its parent.
+    pub(crate) fn exit<E: std::fmt::Display>(
+        &self,
+        guard: SpanGuard,
+        result: Result<&[IValue], &E>,
+        memory_size: usize,
+    ) {
+        let mut state = self.state.lock();
+        // tracing could be reset while the call was executed
+        let (start, mut span) = match state.stack.pop() {
+            Some(entry) => entry,
+            None => return,
+        };
+
+        span.duration = start.elapsed();
+        span.memory_growth = memory_size.saturating_sub(guard.memory_size);
+        match result {
+            Ok(results) => span.results_size = ivalues_size(results),
+            Err(error) => span.error = Some(error.to_string()),
+        }
+
+        match state.stack.last_mut() {
+            Some((_, parent)) => parent.children.push(span),
+            None => state.finished.push(span),
+        }
+    }
+}
+
+impl CallTrace {
+    /// Serializes the trace in the Chrome trace event format,
+    /// it could be opened by chrome://tracing or https://ui.perfetto.dev.
+    pub fn to_chrome_trace(&self) -> String {
+        let mut events = Vec::new();
+        for span in &self.spans {
+            collect_chrome_events(span, &mut events);
+        }
+
+        let trace = ChromeTrace {
+            trace_events: events,
+        };
+        // serialization of these structures can't fail
+        serde_json::to_string(&trace).unwrap_or_default()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChromeTrace<'s> {
+    trace_events: Vec<ChromeTraceEvent<'s>>,
+}
+
+#[derive(Serialize)]
+struct ChromeTraceEvent<'s> {
+    name: String,
+    cat: &'s str,
+    /// "X" is a complete event, i.e. one with a duration.
+    ph: &'static str,
+    /// Timestamps and durations are in microseconds.
+    ts: f64,
+    dur: f64,
+    pid: u32,
+    tid: u32,
+    args: ChromeTraceEventArgs<'s>,
+}
+
+#[derive(Serialize)]
+struct ChromeTraceEventArgs<'s> {
+    arguments_size: usize,
+    results_size: usize,
+    memory_growth: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'s str>,
+}
+
+fn collect_chrome_events<'s>(span: &'s CallSpan, events: &mut Vec<ChromeTraceEvent<'s>>) {
+    events.push(ChromeTraceEvent {
+        name: format!("{}::{}", span.module_name, span.function_name),
+        cat: &span.module_name,
+        ph: "X",
+        ts: span.start.as_secs_f64() * 1_000_000.0,
+        dur: span.duration.as_secs_f64() * 1_000_000.0,
+        pid: 1,
+        tid: 1,
+        args: ChromeTraceEventArgs {
+            arguments_size: span.arguments_size,
+            results_size: span.results_size,
+            memory_growth: span.memory_growth,
+            error: span.error.as_deref(),
+        },
+    });
+
+    for child in &span.children {
+        collect_chrome_events(child, events);
+    }
+}
+
+fn ivalues_size(values: &[IValue]) -> usize {
+    values.iter().map(ivalue_size).sum()
+}
+
+fn ivalue_size(value: &IValue) -> usize {
+    match value {
+        IValue::Boolean(_) | IValue::S8(_) | IValue::U8(_) => 1,
+        IValue::S16(_) | IValue::U16(_) => 2,
+        IValue::S32(_) | IValue::U32(_) | IValue::F32(_) | IValue::I32(_) => 4,
+        IValue::S64(_) | IValue::U64(_) | IValue::F64(_) | IValue::I64(_) => 8,
+        IValue::String(string) => string.len(),
+        IValue::ByteArray(bytes) => bytes.len(),
+        IValue::Array(values) => ivalues_size(values),
+        IValue::Record(fields) => fields.iter().map(ivalue_size).sum(),
+    }
+}
@@ -21,6 +21,8 @@ use crate::HostImportError;
 use marine_wasm_backend_traits::WasiParameters;
 use marine_wasm_backend_traits::WasmBackend;
 
+use futures::future::BoxFuture;
+
 use std::path::PathBuf;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -34,6 +36,19 @@ pub type HostExportedFunc<WB> = Box<
         + 'static,
 >;
 
+/// A host import that doesn't block the executor while it's running.
+/// The context is available only until the future is created, so everything needed
+/// from the module memory should be read before that.
+pub type AsyncHostExportedFunc<WB> = Box<
+    dyn for<'c> Fn(
+            &mut <WB as WasmBackend>::ImportCallContext<'c>,
+            Vec<IValue>,
+        ) -> BoxFuture<'static, Option<IValue>>
+        + Sync
+        + Send
+        + 'static,
+>;
+
 pub type RawImportCreator<WB> = Arc<
     dyn Fn(<WB as WasmBackend>::ContextMut<'_>) -> <WB as WasmBackend>::HostFunction + Send + Sync,
 >;
@@ -42,6 +57,10 @@ pub struct HostImportDescriptor<WB: WasmBackend> {
     /// This closure will be invoked for corresponding import.
     pub host_exported_func: HostExportedFunc<WB>,
 
+    /// If Some, this closure is invoked instead of `host_exported_func`,
+    /// and the call waits for the returned future without blocking the executor.
+    pub async_host_exported_func: Option<AsyncHostExportedFunc<WB>>,
+
     /// Type of the closure arguments.
     pub argument_types: Vec<IType>,
 
@@ -53,6 +72,51 @@ pub struct HostImportDescriptor<WB: WasmBackend> {
     pub error_handler: ErrorHandler,
 }
 
+impl<WB: WasmBackend> HostImportDescriptor<WB> {
+    /// Creates a descriptor of a host import that returns its result right away,
+    /// errors while lifting arguments are handled by the default handler.
+    pub fn new<F>(func: F, argument_types: Vec<IType>, output_type: Option<IType>) -> Self
+    where
+        F: for<'c> Fn(
+                &mut <WB as WasmBackend>::ImportCallContext<'c>,
+                Vec<IValue>,
+            ) -> Option<IValue>
+            + Sync
+            + Send
+            + 'static,
+    {
+        Self {
+            host_exported_func: Box::new(func),
+            async_host_exported_func: None,
+            argument_types,
+            output_type,
+            error_handler: None,
+        }
+    }
+
+    /// Creates a descriptor of a host import that doesn't block the executor while it's running,
+    /// errors while lifting arguments are handled by the default handler.
+    pub fn new_async<F>(func: F, argument_types: Vec<IType>, output_type: Option<IType>) -> Self
+    where
+        F: for<'c> Fn(
+                &mut <WB as WasmBackend>::ImportCallContext<'c>,
+                Vec<IValue>,
+            ) -> BoxFuture<'static, Option<IValue>>
+            + Sync
+            + Send
+            + 'static,
+    {
+        Self {
+            // never called, because the async closure takes precedence
+            host_exported_func: Box::new(|_, _| None),
+            async_host_exported_func: Some(Box::new(func)),
+            argument_types,
+            output_type,
+            error_handler: None,
+        }
+    }
+}
+
 #[derive(Hash, Ord, PartialOrd, Eq, PartialEq)]
 pub enum HostAPIVersion {
     V0,
@@ -82,6 +146,9 @@ pub struct MModuleConfig<WB: WasmBackend> {
 
     /// WASI parameters: env variables, mapped dirs, and args
     pub wasi_parameters: WasiParameters,
+
+    /// Maximum size of the module memory in bytes, it's applied in addition to the total limit.
+    pub max_memory: Option<u64>,
 }
 
 impl<WB: WasmBackend> Default for MModuleConfig<WB> {
@@ -91,6 +158,7 @@ impl<WB: WasmBackend> Default for MModuleConfig<WB> {
             raw_imports: HashMap::new(),
             host_imports: HashMap::new(),
             wasi_parameters: WasiParameters::default(),
+            max_memory: None,
         }
     }
 }
@@ -108,11 +176,17 @@ impl<WB: WasmBackend> MModuleConfig<WB> {
         self.wasi_parameters.mapped_dirs = mapped_dirs;
         self
     }
+
+    pub fn with_max_memory(mut self, max_memory: u64) -> Self {
+        self.max_memory = Some(max_memory);
+        self
+    }
 }
 
 pub struct MarineCoreConfig<WB: WasmBackend> {
     pub(crate) total_memory_limit: u64,
     pub(crate) wasm_backend: WB,
+    pub(crate) snapshots: bool,
 }
 
 pub const INFINITE_MEMORY_LIMIT: u64 = u64::MAX;
@@ -122,6 +196,14 @@ impl<WB: WasmBackend> MarineCoreConfig<WB> {
         Self {
             total_memory_limit: total_memory_limit.unwrap_or(INFINITE_MEMORY_LIMIT),
             wasm_backend,
+            snapshots: false,
         }
     }
+
+    /// Prepare loaded modules for `MarineCore::snapshot`, it's disabled by default,
+    /// because it requires rewriting of each module before compilation.
+    pub fn with_snapshots(mut self) -> Self {
+        self.snapshots = true;
+        self
+    }
 }
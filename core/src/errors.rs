@@ -16,6 +16,7 @@
 
 use crate::HostImportError;
 use crate::misc::PrepareError;
+use crate::snapshot::SnapshotError;
 
 use marine_it_interfaces::MITInterfacesError;
 use marine_it_parser::ITParserError;
@@ -75,6 +76,55 @@ pub enum MError {
 
     #[error("Wasm backend error: {0}")]
     WasmBackendError(#[from] WasmBackendError),
+
+    /// A module can't be replaced, because the new one isn't compatible with modules importing it.
+    #[error("module '{module_name}' can't be replaced: {reason}")]
+    IncompatibleModuleReplacement {
+        module_name: String,
+        reason: ReplacementIncompatibility,
+    },
+
+    /// A call consumed all the fuel it was given.
+    #[error("call exhausted its fuel limit of {0} units")]
+    FuelExhausted(u64),
+
+    /// A requested range of bytes lies outside of a module linear memory.
+    #[error("range of {len} bytes at offset {offset} is out of module '{module_name}' memory of {memory_size} bytes")]
+    MemoryOutOfBounds {
+        module_name: String,
+        offset: usize,
+        len: usize,
+        memory_size: usize,
+    },
+
+    /// A module doesn't have an in-memory directory mapped to the guest path.
+    #[error("module '{module_name}' doesn't have an in-memory directory mapped to '{guest_path}'")]
+    NoSuchMemoryDir {
+        module_name: String,
+        guest_path: String,
+    },
+
+    /// Errors related to making or restoring snapshots.
+    #[error(transparent)]
+    SnapshotError(#[from] SnapshotError),
+}
+
+/// Describes why a new module can't replace a loaded one.
+#[derive(Debug, ThisError)]
+pub enum ReplacementIncompatibility {
+    /// The new module doesn't export a function imported by a dependent module.
+    #[error("function '{function_name}' imported by module '{dependent_module}' is missing")]
+    MissingFunction {
+        dependent_module: String,
+        function_name: String,
+    },
+
+    /// The new module exports a function with a signature differing from the imported one.
+    #[error("function '{function_name}' doesn't match the signature imported by module '{dependent_module}'")]
+    SignatureMismatch {
+        dependent_module: String,
+        function_name: String,
+    },
 }
 
 impl From<MITInterfacesError> for MError {
@@ -67,6 +67,7 @@ async fn call_host_import<'args, WB: WasmBackend>(
 ) -> anyhow::Result<Vec<WValue>> {
     let HostImportDescriptor {
         host_exported_func,
+        async_host_exported_func,
         argument_types,
         error_handler,
         ..
@@ -84,7 +85,10 @@ async fn call_host_import<'args, WB: WasmBackend>(
         argument_types,
     );
     let output = match inputs {
-        Ok(ivalues) => host_exported_func(&mut caller, ivalues),
+        Ok(ivalues) => match async_host_exported_func {
+            Some(func) => func(&mut caller, ivalues).await,
+            None => host_exported_func(&mut caller, ivalues),
+        },
         Err(e) => {
             log::error!("error occurred while lifting values in host import: {}", e);
             error_handler
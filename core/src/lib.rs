@@ -34,12 +34,15 @@ mod host_imports;
 mod misc;
 mod module;
 mod memory_statistic;
+mod snapshot;
+mod call_tracer;
 
 pub use crate::marine_core::MModuleInterface;
 pub use config::MarineCoreConfig;
 pub use config::INFINITE_MEMORY_LIMIT;
 pub use config::HostAPIVersion;
 pub use errors::MError;
+pub use errors::ReplacementIncompatibility;
 pub use host_imports::HostImportError;
 pub use module::IValue;
 pub use module::IRecordType;
@@ -51,6 +54,22 @@ pub use module::from_interface_values;
 pub use module::to_interface_value;
 pub use memory_statistic::ModuleMemoryStat;
 pub use memory_statistic::MemoryStats;
+pub use snapshot::MarineCoreSnapshot;
+pub use snapshot::ModuleSnapshot;
+pub use snapshot::WasiSnapshot;
+pub use snapshot::GlobalValue;
+pub use snapshot::SnapshotError;
+pub use snapshot::SnapshotResult;
+pub use snapshot::SNAPSHOT_FORMAT_VERSION;
+pub use call_tracer::CallTrace;
+pub use call_tracer::CallSpan;
+
+pub use marine_wasm_backend_traits::CapturedOutput;
+pub use marine_wasm_backend_traits::DirUsage;
+pub use marine_wasm_backend_traits::MappedDirPolicy;
+pub use marine_wasm_backend_traits::MemoryDir;
+pub use marine_wasm_backend_traits::MemoryFile;
+pub use marine_wasm_backend_traits::MemoryNode;
 
 pub use wasmer_it::IRecordFieldType;
 pub mod ne_vec {
@@ -62,6 +81,7 @@ pub(crate) type MResult<T> = std::result::Result<T, MError>;
 pub mod generic {
     pub use crate::config::MModuleConfig;
     pub use crate::config::HostExportedFunc;
+    pub use crate::config::AsyncHostExportedFunc;
     pub use crate::config::HostImportDescriptor;
     pub use crate::marine_core::MarineCore;
 }
@@ -72,6 +92,7 @@ pub mod wasmtime {
 
     pub type MModuleConfig = crate::config::MModuleConfig<WasmBackend>;
     pub type HostExportedFunc = crate::config::HostExportedFunc<WasmBackend>;
+    pub type AsyncHostExportedFunc = crate::config::AsyncHostExportedFunc<WasmBackend>;
     pub type HostImportDescriptor = crate::config::HostImportDescriptor<WasmBackend>;
     pub type MarineCore = crate::marine_core::MarineCore<WasmBackend>;
 }
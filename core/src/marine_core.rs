@@ -18,13 +18,22 @@ use super::generic::*;
 use crate::config::MarineCoreConfig;
 use crate::module::MModule;
 use crate::module::MRecordTypes;
+use crate::snapshot::MarineCoreSnapshot;
+use crate::snapshot::SnapshotError;
+use crate::call_tracer::CallTrace;
+use crate::call_tracer::CallTracer;
 use crate::{IRecordType, IValue, MemoryStats, MError, MFunctionSignature, ModuleMemoryStat, MResult};
 
 use marine_wasm_backend_traits::AsContextMut;
+use marine_wasm_backend_traits::MemoryDir;
+use marine_wasm_backend_traits::RuntimeError;
 use marine_wasm_backend_traits::Store;
 use marine_wasm_backend_traits::WasiState;
 use marine_wasm_backend_traits::WasmBackend;
+use marine_wasm_backend_traits::WasmBackendError;
 
+use wasmer_it::errors::InstructionError;
+use wasmer_it::errors::InstructionErrorKind;
 use serde::Serialize;
 
 use std::collections::hash_map::Entry;
@@ -53,21 +62,34 @@ pub struct MModuleInterface<'a> {
 pub struct MarineCore<WB: WasmBackend> {
     // set of modules registered inside Marine
     modules: HashMap<String, MModule<WB>>,
+    // names of loaded modules in the order of loading, used to make snapshots
+    load_order: Vec<String>,
     // Wasm backend may have state in the future
     #[allow(unused)]
     wasm_backend: WB,
     /// Container for all objects created by a Wasm backend.
     store: RefCell<<WB as WasmBackend>::Store>,
+    /// Records calls between modules when tracing is enabled.
+    tracer: CallTracer,
+    /// If set, loaded modules could be snapshotted.
+    snapshots: bool,
 }
 
 impl<WB: WasmBackend> MarineCore<WB> {
     pub fn new(config: MarineCoreConfig<WB>) -> MResult<Self> {
         let mut store = <WB as WasmBackend>::Store::new(&config.wasm_backend);
         store.set_total_memory_limit(config.total_memory_limit);
+        // calls without a fuel limit, including module initialization, shouldn't run out of fuel
+        if store.consumed_fuel().is_some() {
+            store.set_fuel(None)?;
+        }
         Ok(Self {
             modules: HashMap::new(),
+            load_order: Vec::new(),
             wasm_backend: config.wasm_backend,
             store: RefCell::new(store),
+            tracer: CallTracer::default(),
+            snapshots: config.snapshots,
         })
     }
 
@@ -79,6 +101,8 @@ impl<WB: WasmBackend> MarineCore<WB> {
         arguments: &[IValue],
     ) -> MResult<Vec<IValue>> {
         let module_name = module_name.as_ref();
+        self.tracer.start_trace();
+
         let store = &mut self.store;
         let module = self
             .modules
@@ -95,6 +119,47 @@ impl<WB: WasmBackend> MarineCore<WB> {
             .await
     }
 
+    /// Enable or disable recording of call traces, see `last_call_trace`.
+    /// Tracing is disabled by default, because it slows down calls between modules.
+    pub fn set_call_tracing(&mut self, enabled: bool) {
+        self.tracer.set_enabled(enabled)
+    }
+
+    /// Return the tree of calls made by the last `call_async` if tracing is enabled.
+    pub fn last_call_trace(&self) -> Option<CallTrace> {
+        self.tracer.trace()
+    }
+
+    /// Invoke a function like `call_async`, but stop the execution with `MError::FuelExhausted`
+    /// once it consumes more than `fuel` units. Returns the result with the consumed fuel.
+    /// Fuel metering must be supported and enabled by the Wasm backend.
+    pub async fn call_with_fuel_async(
+        &mut self,
+        module_name: impl AsRef<str>,
+        func_name: impl AsRef<str>,
+        arguments: &[IValue],
+        fuel: u64,
+    ) -> MResult<(Vec<IValue>, u64)> {
+        let store = self.store.get_mut();
+        store.set_fuel(Some(fuel))?;
+        let consumed_before = store.consumed_fuel().unwrap_or_default();
+
+        let result = self.call_async(module_name, func_name, arguments).await;
+
+        let store = self.store.get_mut();
+        let consumed = store
+            .consumed_fuel()
+            .unwrap_or_default()
+            .saturating_sub(consumed_before);
+        store.set_fuel(None)?;
+
+        match result {
+            Ok(result) => Ok((result, consumed)),
+            Err(e) if is_out_of_fuel(&e) => Err(MError::FuelExhausted(fuel)),
+            Err(e) => Err(e),
+        }
+    }
+
     /// Load a new module inside Marine.
     pub async fn load_module(
         &mut self,
@@ -115,13 +180,20 @@ impl<WB: WasmBackend> MarineCore<WB> {
             &name,
             self.store.get_mut(),
             wasm_bytes,
+            self.snapshots,
             config,
             &self.modules,
+            &self.tracer,
         )
         .await?;
 
+        self.insert_module(name, module)
+    }
+
+    fn insert_module(&mut self, name: String, module: MModule<WB>) -> MResult<()> {
         match self.modules.entry(name) {
             Entry::Vacant(entry) => {
+                self.load_order.push(entry.key().clone());
                 entry.insert(module);
                 Ok(())
             }
@@ -129,9 +201,120 @@ impl<WB: WasmBackend> MarineCore<WB> {
         }
     }
 
+    /// Make a snapshot of all loaded modules: their linear memories, globals and WASI parameters.
+    /// The snapshot doesn't contain Wasm bytes, they have to be provided again on restoring.
+    /// Modules could be snapshotted only if snapshots are enabled in `MarineCoreConfig`
+    /// or if they were restored from a snapshot.
+    pub fn snapshot(&mut self) -> MResult<MarineCoreSnapshot> {
+        let store = self.store.get_mut();
+        let modules = self
+            .load_order
+            .iter()
+            .map(|name| {
+                let module = self
+                    .modules
+                    .get(name)
+                    .ok_or_else(|| MError::NoSuchModule(name.clone()))?;
+                module.snapshot(&mut store.as_context_mut(), name)
+            })
+            .collect::<MResult<Vec<_>>>()?;
+
+        Ok(MarineCoreSnapshot { modules })
+    }
+
+    /// Restore modules from a snapshot made by `snapshot`. Modules are loaded in the same order
+    /// as they were loaded originally, so this instance must be empty.
+    /// `modules` maps module names to their Wasm bytes and configs, WASI parameters from configs
+    /// are ignored in favor of the ones saved in the snapshot.
+    pub async fn restore(
+        &mut self,
+        snapshot: &MarineCoreSnapshot,
+        mut modules: HashMap<String, (Vec<u8>, MModuleConfig<WB>)>,
+    ) -> MResult<()> {
+        if !self.modules.is_empty() {
+            return Err(SnapshotError::NonEmptyMarine.into());
+        }
+
+        for module_snapshot in &snapshot.modules {
+            let (wasm_bytes, config) = modules
+                .remove(&module_snapshot.name)
+                .ok_or_else(|| SnapshotError::MissingModule(module_snapshot.name.clone()))?;
+
+            let module = MModule::restore(
+                self.store.get_mut(),
+                &wasm_bytes,
+                config,
+                &self.modules,
+                module_snapshot,
+                &self.tracer,
+            )
+            .await?;
+
+            self.insert_module(module_snapshot.name.clone(), module)?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace a loaded module with a new one, keeping its name and position in the load order.
+    /// Modules importing functions from the replaced module are relinked to the new one,
+    /// so the new module must export all these functions with the same signatures,
+    /// otherwise `MError::IncompatibleModuleReplacement` is returned and nothing is changed.
+    /// The old module state isn't transferred, the new one is initialized from scratch.
+    pub async fn replace_module(
+        &mut self,
+        name: impl AsRef<str>,
+        wasm_bytes: &[u8],
+        config: MModuleConfig<WB>,
+    ) -> MResult<()> {
+        let name = name.as_ref();
+        if !self.modules.contains_key(name) {
+            return Err(MError::NoSuchModule(name.to_string()));
+        }
+
+        // compatibility is checked before instantiation, because it runs _start and _initialize
+        let new_interface =
+            MModule::<WB>::parse_interface(self.store.get_mut(), wasm_bytes, self.snapshots)?;
+        let dependents = self
+            .modules
+            .iter()
+            .filter(|(dependent_name, _)| dependent_name.as_str() != name);
+
+        for (dependent_name, dependent) in dependents.clone() {
+            dependent
+                .check_imports_compatibility(dependent_name, name, &new_interface)
+                .map_err(|reason| MError::IncompatibleModuleReplacement {
+                    module_name: name.to_string(),
+                    reason,
+                })?;
+        }
+
+        let new_module = MModule::new(
+            name,
+            self.store.get_mut(),
+            wasm_bytes,
+            self.snapshots,
+            config,
+            &self.modules,
+            &self.tracer,
+        )
+        .await?;
+
+        for (dependent_name, dependent) in self.modules.iter() {
+            if dependent_name != name {
+                dependent.relink_imports(name, &new_module)?;
+            }
+        }
+
+        self.modules.insert(name.to_string(), new_module);
+
+        Ok(())
+    }
+
     /// Unload previously loaded module.
     pub fn unload_module(&mut self, name: impl AsRef<str>) -> MResult<()> {
         // TODO: clean up all reference from adaptors after adding support of lazy linking
+        self.load_order.retain(|loaded| loaded != name.as_ref());
         self.modules
             .remove(name.as_ref())
             .map(|_| ())
@@ -142,9 +325,32 @@ impl<WB: WasmBackend> MarineCore<WB> {
         &'s mut self,
         module_name: impl AsRef<str>,
     ) -> Option<Box<dyn WasiState + 's>> {
+        let store = self.store.get_mut();
         self.modules
             .get_mut(module_name.as_ref())
-            .map(|module| module.get_wasi_state())
+            .map(|module| module.get_wasi_state(store))
+    }
+
+    /// Returns an in-memory directory mapped to the guest path of a module.
+    /// The directory shares content with the module, so it could be read or exported after calls.
+    pub fn module_memory_dir(
+        &self,
+        module_name: impl AsRef<str>,
+        guest_path: impl AsRef<str>,
+    ) -> MResult<MemoryDir> {
+        let module_name = module_name.as_ref();
+        let guest_path = guest_path.as_ref();
+        let module = self
+            .modules
+            .get(module_name)
+            .ok_or_else(|| MError::NoSuchModule(module_name.to_string()))?;
+
+        module
+            .memory_dir(guest_path)
+            .ok_or_else(|| MError::NoSuchMemoryDir {
+                module_name: module_name.to_string(),
+                guest_path: guest_path.to_string(),
+            })
     }
 
     /// Return function signatures of all loaded info Marine modules with their names.
@@ -195,6 +401,39 @@ impl<WB: WasmBackend> MarineCore<WB> {
         MemoryStats::new(records, allocation_stats)
     }
 
+    /// Returns the linear memory size of a module with given name in bytes.
+    pub fn module_memory_size(&self, module_name: impl AsRef<str>) -> MResult<usize> {
+        let module_name = module_name.as_ref();
+        let module = self
+            .modules
+            .get(module_name)
+            .ok_or_else(|| MError::NoSuchModule(module_name.to_string()))?;
+
+        Ok(module.memory_size(&mut self.store.borrow_mut().as_context_mut()))
+    }
+
+    /// Returns `len` bytes of a module linear memory starting from `offset`,
+    /// the memory isn't changed. Fails with `MError::MemoryOutOfBounds` if the range doesn't fit.
+    pub fn read_module_memory(
+        &self,
+        module_name: impl AsRef<str>,
+        offset: usize,
+        len: usize,
+    ) -> MResult<Vec<u8>> {
+        let module_name = module_name.as_ref();
+        let module = self
+            .modules
+            .get(module_name)
+            .ok_or_else(|| MError::NoSuchModule(module_name.to_string()))?;
+
+        module.read_memory(
+            &mut self.store.borrow_mut().as_context_mut(),
+            module_name,
+            offset,
+            len,
+        )
+    }
+
     pub fn clear_allocation_stats(&mut self) {
         self.store.borrow_mut().clear_allocation_stats()
     }
@@ -210,3 +449,46 @@ impl<WB: WasmBackend> MarineCore<WB> {
         }
     }
 }
+
+/// Checks whether a call failed because some module in its call chain exhausted the fuel.
+/// Errors of calls between modules are nested into interpreter errors, so they're unwrapped.
+fn is_out_of_fuel(error: &MError) -> bool {
+    match error {
+        MError::WasmBackendError(WasmBackendError::RuntimeError(e)) => {
+            runtime_error_is_out_of_fuel(e)
+        }
+        MError::ITInstructionError(e) => instruction_error_is_out_of_fuel(e),
+        _ => false,
+    }
+}
+
+fn runtime_error_is_out_of_fuel(error: &RuntimeError) -> bool {
+    match error {
+        RuntimeError::OutOfFuel => true,
+        RuntimeError::Trap(e) | RuntimeError::Other(e) => anyhow_is_out_of_fuel(e),
+        _ => false,
+    }
+}
+
+fn instruction_error_is_out_of_fuel(error: &InstructionError) -> bool {
+    match &error.error_kind {
+        InstructionErrorKind::LocalOrImportCall { reason, .. } => anyhow_is_out_of_fuel(reason),
+        _ => false,
+    }
+}
+
+fn anyhow_is_out_of_fuel(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<RuntimeError>() {
+            return runtime_error_is_out_of_fuel(e);
+        }
+        if let Some(e) = cause.downcast_ref::<InstructionError>() {
+            return instruction_error_is_out_of_fuel(e);
+        }
+        if let Some(e) = cause.downcast_ref::<MError>() {
+            return is_out_of_fuel(e);
+        }
+
+        false
+    })
+}
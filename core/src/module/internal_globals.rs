@@ -0,0 +1,304 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Wasmtime and JS don't give access to globals which aren't exported, so to save them in
+//! snapshots every internal mutable global (e.g. the stack pointer) is exported before
+//! the module is compiled.
+
+use marine_wasm_backend_traits::ModuleCreationError;
+
+use anyhow::anyhow;
+use wasmparser::ExportSectionReader;
+use wasmparser::ExternalKind;
+use wasmparser::GlobalSectionReader;
+use wasmparser::ImportSectionReader;
+use wasmparser::TypeRef;
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+/// Prefix of the names internal mutable globals are exported with, it's followed by the index.
+pub(crate) const INTERNAL_GLOBAL_PREFIX: &str = "__marine_global_";
+
+const WASM_HEADER_SIZE: usize = 8;
+const GLOBAL_SECTION_ID: u8 = 6;
+const EXPORT_SECTION_ID: u8 = 7;
+const IMPORT_SECTION_ID: u8 = 2;
+const CUSTOM_SECTION_ID: u8 = 0;
+/// Ids of sections that must follow the export section.
+const SECTIONS_AFTER_EXPORT: [u8; 5] = [8, 9, 10, 11, 12];
+const GLOBAL_EXTERNAL_KIND: u8 = 3;
+
+struct Section<'a> {
+    id: u8,
+    /// Offset of the section content in the module.
+    offset: usize,
+    content: &'a [u8],
+}
+
+/// Returns the module with all mutable globals exported, or the same bytes
+/// if there are no internal mutable globals.
+pub(crate) fn export_internal_globals(
+    wasm_bytes: &[u8],
+) -> Result<Cow<'_, [u8]>, ModuleCreationError> {
+    let invalid = |reason: String| {
+        ModuleCreationError::FailedToCompileWasm(anyhow!("invalid Wasm module: {}", reason))
+    };
+
+    let sections =
+        split_sections(wasm_bytes).ok_or_else(|| invalid("malformed sections".into()))?;
+    let internal_globals =
+        internal_mutable_globals(&sections).map_err(|e| invalid(e.to_string()))?;
+    if internal_globals.is_empty() {
+        return Ok(Cow::Borrowed(wasm_bytes));
+    }
+
+    let mut new_exports = Vec::new();
+    for index in &internal_globals {
+        let name = format!("{INTERNAL_GLOBAL_PREFIX}{index}");
+        write_leb(&mut new_exports, name.len() as u64);
+        new_exports.extend_from_slice(name.as_bytes());
+        new_exports.push(GLOBAL_EXTERNAL_KIND);
+        write_leb(&mut new_exports, *index as u64);
+    }
+
+    let mut result = Vec::with_capacity(wasm_bytes.len() + new_exports.len() + 16);
+    result.extend_from_slice(&wasm_bytes[..WASM_HEADER_SIZE]);
+
+    let mut exports_written = false;
+    for section in &sections {
+        let export_section_is_missing = !exports_written
+            && section.id != CUSTOM_SECTION_ID
+            && SECTIONS_AFTER_EXPORT.contains(&section.id);
+        if export_section_is_missing {
+            write_export_section(&mut result, 0, &[], &new_exports, internal_globals.len());
+            exports_written = true;
+        }
+
+        if section.id == EXPORT_SECTION_ID {
+            let (count, count_size) =
+                read_leb(section.content).ok_or_else(|| invalid("malformed exports".into()))?;
+            let entries = &section.content[count_size..];
+            write_export_section(
+                &mut result,
+                count,
+                entries,
+                &new_exports,
+                internal_globals.len(),
+            );
+            exports_written = true;
+            continue;
+        }
+
+        result.push(section.id);
+        write_leb(&mut result, section.content.len() as u64);
+        result.extend_from_slice(section.content);
+    }
+
+    if !exports_written {
+        write_export_section(&mut result, 0, &[], &new_exports, internal_globals.len());
+    }
+
+    Ok(Cow::Owned(result))
+}
+
+/// Returns indices of mutable globals defined in the module, but not exported from it.
+fn internal_mutable_globals(sections: &[Section<'_>]) -> wasmparser::Result<Vec<u32>> {
+    let mut imported_globals = 0;
+    let mut mutable_globals = Vec::new();
+    let mut exported_globals = HashSet::new();
+
+    for section in sections {
+        match section.id {
+            IMPORT_SECTION_ID => {
+                for import in ImportSectionReader::new(section.content, section.offset)? {
+                    if let TypeRef::Global(_) = import?.ty {
+                        imported_globals += 1;
+                    }
+                }
+            }
+            GLOBAL_SECTION_ID => {
+                let globals = GlobalSectionReader::new(section.content, section.offset)?;
+                for (index, global) in globals.into_iter().enumerate() {
+                    if global?.ty.mutable {
+                        mutable_globals.push(index as u32);
+                    }
+                }
+            }
+            EXPORT_SECTION_ID => {
+                for export in ExportSectionReader::new(section.content, section.offset)? {
+                    let export = export?;
+                    if export.kind == ExternalKind::Global {
+                        exported_globals.insert(export.index);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // imported globals go first in the global index space
+    Ok(mutable_globals
+        .into_iter()
+        .map(|index| index + imported_globals)
+        .filter(|index| !exported_globals.contains(index))
+        .collect())
+}
+
+fn write_export_section(
+    result: &mut Vec<u8>,
+    old_count: u64,
+    old_entries: &[u8],
+    new_entries: &[u8],
+    new_count: usize,
+) {
+    let mut content = Vec::with_capacity(old_entries.len() + new_entries.len() + 5);
+    write_leb(&mut content, old_count + new_count as u64);
+    content.extend_from_slice(old_entries);
+    content.extend_from_slice(new_entries);
+
+    result.push(EXPORT_SECTION_ID);
+    write_leb(result, content.len() as u64);
+    result.extend_from_slice(&content);
+}
+
+fn split_sections(wasm_bytes: &[u8]) -> Option<Vec<Section<'_>>> {
+    let mut sections = Vec::new();
+    let mut offset = WASM_HEADER_SIZE;
+    if wasm_bytes.len() < WASM_HEADER_SIZE {
+        return None;
+    }
+
+    while offset < wasm_bytes.len() {
+        let id = wasm_bytes[offset];
+        let (size, size_len) = read_leb(&wasm_bytes[offset + 1..])?;
+        let start = offset + 1 + size_len;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+        let content = wasm_bytes.get(start..end)?;
+        sections.push(Section {
+            id,
+            offset: start,
+            content,
+        });
+        offset = end;
+    }
+
+    Some(sections)
+}
+
+/// Reads an unsigned LEB128 number, returns it with the number of bytes it takes.
+fn read_leb(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut result = 0u64;
+    for (position, byte) in bytes.iter().take(10).enumerate() {
+        result |= ((byte & 0x7f) as u64) << (7 * position);
+        if byte & 0x80 == 0 {
+            return Some((result, position + 1));
+        }
+    }
+
+    None
+}
+
+fn write_leb(result: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            result.push(byte);
+            return;
+        }
+        result.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::export_internal_globals;
+
+    use wasmparser::ExternalKind;
+    use wasmparser::Parser;
+    use wasmparser::Payload;
+
+    const HEADER: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+    // two mutable i32 globals and an immutable one, the first mutable one is exported as "sp"
+    const GLOBAL_SECTION: [u8; 18] = [
+        0x06, 0x10, 0x03, 0x7f, 0x01, 0x41, 0x00, 0x0b, 0x7f, 0x00, 0x41, 0x00, 0x0b, 0x7f, 0x01,
+        0x41, 0x00, 0x0b,
+    ];
+
+    fn global_exports(wasm_bytes: &[u8]) -> Vec<(String, u32)> {
+        Parser::new(0)
+            .parse_all(wasm_bytes)
+            .filter_map(|payload| match payload.unwrap() {
+                Payload::ExportSection(reader) => Some(reader),
+                _ => None,
+            })
+            .flat_map(|reader| reader.into_iter().map(|export| export.unwrap()))
+            .filter(|export| export.kind == ExternalKind::Global)
+            .map(|export| (export.name.to_string(), export.index))
+            .collect()
+    }
+
+    fn module(with_exports: bool) -> Vec<u8> {
+        let mut module = HEADER.to_vec();
+        module.extend_from_slice(&GLOBAL_SECTION);
+        if with_exports {
+            module.extend_from_slice(&[0x07, 0x06, 0x01, 0x02, b's', b'p', 0x03, 0x00]);
+        }
+        // a custom section after exports must be kept
+        module.extend_from_slice(&[0x00, 0x03, 0x02, b'i', b't']);
+        module
+    }
+
+    #[test]
+    fn exports_internal_mutable_globals() {
+        let module = module(true);
+        let exported = export_internal_globals(&module).unwrap();
+
+        let exports = global_exports(&exported);
+        assert_eq!(
+            exports,
+            [
+                (String::from("sp"), 0),
+                (String::from("__marine_global_2"), 2)
+            ]
+        );
+        assert!(exported.ends_with(&[0x00, 0x03, 0x02, b'i', b't']));
+    }
+
+    #[test]
+    fn adds_missing_export_section() {
+        let module = module(false);
+        let exported = export_internal_globals(&module).unwrap();
+
+        let exports = global_exports(&exported);
+        assert_eq!(
+            exports,
+            [
+                (String::from("__marine_global_0"), 0),
+                (String::from("__marine_global_2"), 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_modules_without_internal_globals() {
+        let module = [HEADER.as_slice(), &[0x00, 0x03, 0x02, b'i', b't']].concat();
+        let exported = export_internal_globals(&module).unwrap();
+        assert_eq!(exported.as_ref(), module.as_slice());
+    }
+}
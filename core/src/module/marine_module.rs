@@ -22,16 +22,23 @@ use super::IRecordType;
 use super::IFunctionArg;
 use super::IValue;
 use super::WValue;
+use super::internal_globals::export_internal_globals;
 use crate::generic::HostImportDescriptor;
 use crate::MResult;
 use crate::generic::MModuleConfig;
 use crate::config::HostAPIVersion;
 use crate::config::RawImportCreator;
+use crate::snapshot::wasm_hash;
+use crate::snapshot::ModuleSnapshot;
+use crate::snapshot::SnapshotError;
+use crate::ReplacementIncompatibility;
+use crate::call_tracer::CallTracer;
 
 use marine_wasm_backend_traits::prelude::*;
 
 use marine_it_interfaces::MITInterfaces;
 use marine_it_parser::extract_it_from_module;
+use marine_module_interface::it_interface::IModuleInterface;
 use marine_utils::SharedString;
 use wasmer_it::interpreter::Interpreter;
 
@@ -43,9 +50,14 @@ use std::convert::TryInto;
 use std::mem::MaybeUninit;
 use std::sync::Arc;
 use std::borrow::BorrowMut;
+use std::borrow::Cow;
 
 const START_FUNC: &str = "_start";
 const INITIALIZE_FUNC: &str = "_initialize";
+/// Size of a chunk used to copy linear memory from and to snapshots.
+const MEMORY_CHUNK_SIZE: usize = 1024 * 1024;
+/// Size of a Wasm page in bytes.
+const WASM_PAGE_SIZE: usize = 64 * 1024;
 
 type ITInterpreter<WB> = Interpreter<
     ITInstance<WB>,
@@ -67,6 +79,11 @@ pub(super) struct ITModuleFunc<WB: WasmBackend> {
 pub(super) struct Callable<WB: WasmBackend> {
     pub(super) it_instance: Arc<ITInstance<WB>>,
     pub(super) it_module_func: ITModuleFunc<WB>,
+    /// Memory limit of the module this function belongs to.
+    pub(super) memory_limit: Option<ModuleMemoryLimit>,
+    pub(super) module_name: Arc<str>,
+    pub(super) function_name: Arc<str>,
+    pub(super) tracer: CallTracer,
 }
 
 impl<WB: WasmBackend> Callable<WB> {
@@ -77,14 +94,28 @@ impl<WB: WasmBackend> Callable<WB> {
     ) -> MResult<Vec<IValue>> {
         use wasmer_it::interpreter::stack::Stackable;
 
+        let span = self
+            .tracer
+            .enter(&self.module_name, &self.function_name, args, || {
+                self.it_instance.memory_size(store)
+            });
+
+        // the function could be called from another module, so the caller limit is restored after
+        let previous_limit = store.set_module_memory_limit(self.memory_limit.clone());
         let result = self
             .it_module_func
             .interpreter
             .run(args, Arc::make_mut(&mut self.it_instance), store)
-            .await?
-            .as_slice()
-            .to_owned();
-        Ok(result)
+            .await
+            .map(|stack| stack.as_slice().to_owned());
+        store.set_module_memory_limit(previous_limit);
+
+        if let Some(span) = span {
+            let memory_size = self.it_instance.memory_size(store);
+            self.tracer.exit(span, result.as_deref(), memory_size);
+        }
+
+        Ok(result?)
     }
 }
 
@@ -93,22 +124,151 @@ type ExportFunctions<WB> = HashMap<SharedString, Arc<Callable<WB>>>;
 pub(crate) struct MModule<WB: WasmBackend> {
     wasm_instance: Box<<WB as WasmBackend>::Instance>,
 
+    /// IT functions of the module, including functions imported from other modules.
+    it_instance: Arc<ITInstance<WB>>,
+
     export_funcs: ExportFunctions<WB>,
 
     // TODO: save refs instead copying of a record types HashMap.
     /// Record types used in exported functions as arguments or return values.
     export_record_types: MRecordTypes,
+
+    /// SHA-256 of the Wasm bytes this module was created from,
+    /// it's present only if the module could be snapshotted.
+    wasm_hash: Option<[u8; 32]>,
+
+    /// WASI parameters the module was instantiated with, they are needed to make a snapshot.
+    wasi_parameters: WasiParameters,
+}
+
+/// A compiled module with the hash of its original bytes, if it could be snapshotted.
+struct CompiledModule<WB: WasmBackend> {
+    wasm_module: <WB as WasmBackend>::Module,
+    wasm_hash: Option<[u8; 32]>,
 }
 
 impl<WB: WasmBackend> MModule<WB> {
+    /// Creates a module and runs its `_initialize` and `_start`, the module
+    /// could be snapshotted only if `snapshots` is set.
     pub(crate) async fn new(
         name: &str,
         store: &mut <WB as WasmBackend>::Store,
         wasm_bytes: &[u8],
+        snapshots: bool,
         config: MModuleConfig<WB>,
         modules: &HashMap<String, MModule<WB>>,
+        tracer: &CallTracer,
+    ) -> MResult<Self> {
+        let compiled = Self::compile(store, wasm_bytes, snapshots)?;
+        let memory_limit = module_memory_limit(name, &config);
+        let previous_limit = store.as_context_mut().set_module_memory_limit(memory_limit);
+        let result =
+            Self::instantiate_and_start(name, store, compiled, config, modules, tracer).await;
+        store
+            .as_context_mut()
+            .set_module_memory_limit(previous_limit);
+
+        result
+    }
+
+    async fn instantiate_and_start(
+        name: &str,
+        store: &mut <WB as WasmBackend>::Store,
+        compiled: CompiledModule<WB>,
+        config: MModuleConfig<WB>,
+        modules: &HashMap<String, MModule<WB>>,
+        tracer: &CallTracer,
+    ) -> MResult<Self> {
+        let module = Self::instantiate(name, store, compiled, config, modules, tracer).await?;
+
+        // backend is not expected to call _start or _initialize
+        // call _initialize to populate the WASI state of the module
+        #[rustfmt::skip]
+        if let Ok(initialize_func) = module.wasm_instance.get_function(store, INITIALIZE_FUNC) {
+            initialize_func.call_async(store, &[]).await?;
+        }
+        // call _start to call module's main function
+        #[rustfmt::skip]
+        if let Ok(start_func) = module.wasm_instance.get_function(store, START_FUNC) {
+            start_func.call_async(store, &[]).await?;
+        }
+
+        Ok(module)
+    }
+
+    /// Creates a module from the snapshot: instead of calling `_initialize` and `_start`
+    /// its memory and globals are set to the values saved in the snapshot.
+    pub(crate) async fn restore(
+        store: &mut <WB as WasmBackend>::Store,
+        wasm_bytes: &[u8],
+        mut config: MModuleConfig<WB>,
+        modules: &HashMap<String, MModule<WB>>,
+        snapshot: &ModuleSnapshot,
+        tracer: &CallTracer,
     ) -> MResult<Self> {
-        let wasm_module = <WB as WasmBackend>::Module::new(store, wasm_bytes)?;
+        snapshot.check_wasm_bytes(wasm_bytes)?;
+        // the virtual environment, in-memory directories, directory policies and output buffers
+        // aren't a part of the module state, so they're taken from the config
+        let deterministic_env = config.wasi_parameters.deterministic_env.take();
+        let output_capture = config.wasi_parameters.output_capture.take();
+        let memory_dirs = std::mem::take(&mut config.wasi_parameters.memory_dirs);
+        let dir_policies = std::mem::take(&mut config.wasi_parameters.dir_policies);
+        config.wasi_parameters = snapshot.wasi.clone().into();
+        config.wasi_parameters.deterministic_env = deterministic_env;
+        config.wasi_parameters.memory_dirs = memory_dirs;
+        config.wasi_parameters.dir_policies = dir_policies;
+        config.wasi_parameters.output_capture = output_capture;
+
+        let compiled = Self::compile(store, wasm_bytes, true)?;
+        let memory_limit = module_memory_limit(&snapshot.name, &config);
+        let previous_limit = store.as_context_mut().set_module_memory_limit(memory_limit);
+        let result = Self::instantiate(&snapshot.name, store, compiled, config, modules, tracer)
+            .await
+            .and_then(|module| {
+                module.restore_state(&mut store.as_context_mut(), snapshot)?;
+                Ok(module)
+            });
+        store
+            .as_context_mut()
+            .set_module_memory_limit(previous_limit);
+
+        result
+    }
+
+    /// Compiles a module, internal mutable globals are exported and the hash is calculated
+    /// only for snapshots, because it requires rewriting the module.
+    fn compile(
+        store: &mut <WB as WasmBackend>::Store,
+        wasm_bytes: &[u8],
+        snapshots: bool,
+    ) -> MResult<CompiledModule<WB>> {
+        let (exported_wasm_bytes, wasm_hash) = match snapshots {
+            true => (
+                export_internal_globals(wasm_bytes)?,
+                Some(wasm_hash(wasm_bytes)),
+            ),
+            false => (Cow::Borrowed(wasm_bytes), None),
+        };
+        let wasm_module = <WB as WasmBackend>::Module::new_cached(store, &exported_wasm_bytes)?;
+
+        Ok(CompiledModule {
+            wasm_module,
+            wasm_hash,
+        })
+    }
+
+    async fn instantiate(
+        name: &str,
+        store: &mut <WB as WasmBackend>::Store,
+        compiled: CompiledModule<WB>,
+        config: MModuleConfig<WB>,
+        modules: &HashMap<String, MModule<WB>>,
+        tracer: &CallTracer,
+    ) -> MResult<Self> {
+        let CompiledModule {
+            wasm_module,
+            wasm_hash,
+        } = compiled;
         crate::misc::check_sdk_version::<WB>(name.to_string(), &wasm_module)?;
 
         let it = extract_it_from_module::<WB>(&wasm_module)?;
@@ -119,12 +279,14 @@ impl<WB: WasmBackend> MModule<WB> {
         let mut wit_instance = Arc::new_uninit();
         let mut linker = <WB as WasmBackend>::Imports::new(store);
 
+        let memory_limit = module_memory_limit(name, &config);
         let MModuleConfig {
             raw_imports,
             host_imports,
             wasi_parameters,
             ..
         } = config;
+        let snapshot_wasi_parameters = wasi_parameters.clone();
 
         Self::add_wit_imports(store, &mut linker, &mit, wit_instance.clone())?;
         Self::add_wasi_imports(store, &mut linker, wasi_parameters)?;
@@ -140,24 +302,16 @@ impl<WB: WasmBackend> MModule<WB> {
             std::mem::transmute::<_, Arc<ITInstance<WB>>>(wit_instance)
         };
 
-        let (export_funcs, export_record_types) = Self::instantiate_exports(&it_instance, &mit)?;
-
-        // backend is not expected to call _start or _initialize
-        // call _initialize to populate the WASI state of the module
-        #[rustfmt::skip]
-        if let Ok(initialize_func) = wasm_instance.get_function(store, INITIALIZE_FUNC) {
-            initialize_func.call_async(store, &[]).await?;
-        }
-        // call _start to call module's main function
-        #[rustfmt::skip]
-        if let Ok(start_func) = wasm_instance.get_function(store, START_FUNC) {
-            start_func.call_async(store, &[]).await?;
-        }
+        let (export_funcs, export_record_types) =
+            Self::instantiate_exports(name, &it_instance, &mit, memory_limit, tracer)?;
 
         Ok(Self {
             wasm_instance: Box::new(wasm_instance),
+            it_instance,
             export_funcs,
             export_record_types,
+            wasm_hash,
+            wasi_parameters: snapshot_wasi_parameters,
         })
     }
 
@@ -206,8 +360,16 @@ impl<WB: WasmBackend> MModule<WB> {
         self.export_record_types.get(&record_type)
     }
 
-    pub(crate) fn get_wasi_state<'s>(&'s mut self) -> Box<dyn WasiState + 's> {
-        <WB as WasmBackend>::Wasi::get_wasi_state(self.wasm_instance.borrow_mut())
+    pub(crate) fn get_wasi_state<'s>(
+        &'s mut self,
+        store: &<WB as WasmBackend>::Store,
+    ) -> Box<dyn WasiState + 's> {
+        <WB as WasmBackend>::Wasi::get_wasi_state(store, self.wasm_instance.borrow_mut())
+    }
+
+    /// Returns an in-memory directory mapped to the guest path, it shares content with the module.
+    pub(crate) fn memory_dir(&self, guest_path: &str) -> Option<MemoryDir> {
+        self.wasi_parameters.memory_dirs.get(guest_path).cloned()
     }
 
     /// Returns Wasm linear memory size that this module consumes in bytes.
@@ -220,6 +382,198 @@ impl<WB: WasmBackend> MModule<WB> {
         memory.size(store)
     }
 
+    /// Returns the interface of a module without instantiating it, so none of its code is run.
+    pub(crate) fn parse_interface(
+        store: &mut <WB as WasmBackend>::Store,
+        wasm_bytes: &[u8],
+        snapshots: bool,
+    ) -> MResult<IModuleInterface> {
+        // compiled the same way as in new, so the module could be reused from the cache
+        let wasm_module = Self::compile(store, wasm_bytes, snapshots)?.wasm_module;
+        let it = extract_it_from_module::<WB>(&wasm_module)?;
+        let mit = MITInterfaces::new(it);
+
+        Ok(marine_module_interface::it_interface::get_interface(&mit)?)
+    }
+
+    /// Checks that a module with `new_interface` exports every function this module imports
+    /// from `import_namespace` with the same signature, so it could replace the module `import_namespace`.
+    pub(crate) fn check_imports_compatibility(
+        &self,
+        module_name: &str,
+        import_namespace: &str,
+        new_interface: &IModuleInterface,
+    ) -> Result<(), ReplacementIncompatibility> {
+        use wasmer_it::interpreter::wasm::structures::LocalImport;
+
+        for import in self.it_instance.imports_from(import_namespace) {
+            let function_name = LocalImport::name(import);
+            let export_func = new_interface
+                .function_signatures
+                .iter()
+                .find(|signature| signature.name.as_str() == function_name)
+                .ok_or_else(|| ReplacementIncompatibility::MissingFunction {
+                    dependent_module: module_name.to_string(),
+                    function_name: function_name.to_string(),
+                })?;
+
+            let import_records = self.it_instance.record_types();
+            let export_records = &new_interface.record_types;
+
+            let arguments_match = import.arguments().len() == export_func.arguments.len()
+                && import
+                    .arguments()
+                    .iter()
+                    .zip(export_func.arguments.iter())
+                    .all(|(lhs, rhs)| {
+                        itypes_compatible(&lhs.ty, import_records, &rhs.ty, export_records)
+                    });
+            let outputs_match = import.outputs().len() == export_func.outputs.len()
+                && import
+                    .outputs()
+                    .iter()
+                    .zip(export_func.outputs.iter())
+                    .all(|(lhs, rhs)| itypes_compatible(lhs, import_records, rhs, export_records));
+
+            if !arguments_match || !outputs_match {
+                return Err(ReplacementIncompatibility::SignatureMismatch {
+                    dependent_module: module_name.to_string(),
+                    function_name: function_name.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes all functions imported from `import_namespace` call exports of `new_module`.
+    /// Compatibility must be checked with `check_imports_compatibility` beforehand.
+    pub(crate) fn relink_imports(
+        &self,
+        import_namespace: &str,
+        new_module: &MModule<WB>,
+    ) -> MResult<()> {
+        use wasmer_it::interpreter::wasm::structures::LocalImport;
+
+        for import in self.it_instance.imports_from(import_namespace) {
+            let callable = new_module.get_callable(import_namespace, LocalImport::name(import))?;
+            import.relink(callable);
+        }
+
+        Ok(())
+    }
+
+    /// Returns `len` bytes of the module linear memory starting from `offset`.
+    pub(crate) fn read_memory(
+        &self,
+        store: &mut <WB as WasmBackend>::ContextMut<'_>,
+        name: &str,
+        offset: usize,
+        len: usize,
+    ) -> MResult<Vec<u8>> {
+        use it_memory_traits::Memory as ITMemory;
+        use it_memory_traits::MemoryReadable;
+
+        let memory = self.standard_memory(store);
+        let memory_size = memory.size(store);
+        let end = offset
+            .checked_add(len)
+            .filter(|&end| end <= memory_size)
+            .ok_or_else(|| MError::MemoryOutOfBounds {
+                module_name: name.to_string(),
+                offset,
+                len,
+                memory_size,
+            })?;
+
+        let view = memory.view();
+        let mut memory_bytes = Vec::with_capacity(len);
+        let mut chunk_offset = offset;
+        while chunk_offset < end {
+            let chunk_size = std::cmp::min(MEMORY_CHUNK_SIZE, end - chunk_offset);
+            memory_bytes.extend(view.read_vec(store, chunk_offset as u32, chunk_size as u32));
+            chunk_offset += chunk_size;
+        }
+
+        Ok(memory_bytes)
+    }
+
+    /// Returns a snapshot of the module state: linear memory, mutable globals and WASI parameters.
+    /// Internal mutable globals are exported on instantiation, so they're saved too.
+    pub(crate) fn snapshot(
+        &self,
+        store: &mut <WB as WasmBackend>::ContextMut<'_>,
+        name: &str,
+    ) -> MResult<ModuleSnapshot> {
+        let wasm_hash = self.wasm_hash.ok_or(SnapshotError::SnapshotsDisabled)?;
+        let memory_size = self.memory_size(store);
+        let memory_bytes = self.read_memory(store, name, 0, memory_size)?;
+
+        let globals = self
+            .wasm_instance
+            .mutable_globals(store)
+            .ok_or_else(|| SnapshotError::UnsupportedBackend(name.to_string()))?
+            .iter()
+            .map(|(name, value)| (name.clone(), value.into()))
+            .collect();
+
+        Ok(ModuleSnapshot {
+            name: name.to_string(),
+            wasm_hash,
+            wasi: (&self.wasi_parameters).into(),
+            memory: memory_bytes,
+            globals,
+        })
+    }
+
+    fn restore_state(
+        &self,
+        store: &mut <WB as WasmBackend>::ContextMut<'_>,
+        snapshot: &ModuleSnapshot,
+    ) -> MResult<()> {
+        use it_memory_traits::Memory as ITMemory;
+        use it_memory_traits::MemoryWritable;
+
+        let memory = self.standard_memory(store);
+        let memory_size = memory.size(store);
+        let snapshot_size = snapshot.memory.len();
+        if memory_size > snapshot_size {
+            return Err(SnapshotError::MemorySizeMismatch {
+                module_name: snapshot.name.clone(),
+                expected: snapshot_size,
+                actual: memory_size,
+            }
+            .into());
+        }
+
+        let delta_pages = (snapshot_size - memory_size) / WASM_PAGE_SIZE;
+        if delta_pages > 0 {
+            memory.grow(store, delta_pages as u32)?;
+        }
+
+        let view = memory.view();
+        for (chunk_id, chunk) in snapshot.memory.chunks(MEMORY_CHUNK_SIZE).enumerate() {
+            let offset = chunk_id * MEMORY_CHUNK_SIZE;
+            view.write_bytes(store, offset as u32, chunk);
+        }
+
+        for (name, value) in &snapshot.globals {
+            self.wasm_instance
+                .set_global(store, name, (*value).into())?;
+        }
+
+        Ok(())
+    }
+
+    fn standard_memory(
+        &self,
+        store: &mut <WB as WasmBackend>::ContextMut<'_>,
+    ) -> <WB as WasmBackend>::Memory {
+        self.wasm_instance
+            .get_nth_memory(store, STANDARD_MEMORY_INDEX)
+            .expect("It is expected that the existence of at least one memory is checked in the MModule::new function")
+    }
+
     // TODO: change the cloning Callable behaviour after changes of Wasmer API
     pub(super) fn get_callable(
         &self,
@@ -461,9 +815,13 @@ impl<WB: WasmBackend> MModule<WB> {
     }
 
     fn instantiate_exports(
+        module_name: &str,
         it_instance: &Arc<ITInstance<WB>>,
         mit: &MITInterfaces<'_>,
+        memory_limit: Option<ModuleMemoryLimit>,
+        tracer: &CallTracer,
     ) -> MResult<(ExportFunctions<WB>, MRecordTypes)> {
+        let module_name: Arc<str> = Arc::from(module_name);
         let module_interface = marine_module_interface::it_interface::get_interface(mit)?;
 
         let export_funcs = module_interface
@@ -485,11 +843,15 @@ impl<WB: WasmBackend> MModule<WB> {
                     output_types: sign.outputs.clone(),
                 };
 
-                let shared_string = SharedString(sign.name);
                 let callable = Arc::new(Callable {
                     it_instance: it_instance.clone(),
                     it_module_func,
+                    memory_limit: memory_limit.clone(),
+                    module_name: module_name.clone(),
+                    function_name: Arc::from(sign.name.as_str()),
+                    tracer: tracer.clone(),
                 });
+                let shared_string = SharedString(sign.name);
 
                 Ok((shared_string, callable))
             })
@@ -498,3 +860,42 @@ impl<WB: WasmBackend> MModule<WB> {
         Ok((export_funcs, module_interface.export_record_types))
     }
 }
+
+fn module_memory_limit<WB: WasmBackend>(
+    module_name: &str,
+    config: &MModuleConfig<WB>,
+) -> Option<ModuleMemoryLimit> {
+    config.max_memory.map(|max_memory| ModuleMemoryLimit {
+        module_name: module_name.to_string(),
+        max_memory,
+    })
+}
+
+/// Checks that two types are the same, records are compared by their structure,
+/// because record ids are local to a module.
+fn itypes_compatible(
+    lhs: &IType,
+    lhs_records: &MRecordTypes,
+    rhs: &IType,
+    rhs_records: &MRecordTypes,
+) -> bool {
+    match (lhs, rhs) {
+        (IType::Array(lhs), IType::Array(rhs)) => {
+            itypes_compatible(lhs, lhs_records, rhs, rhs_records)
+        }
+        (IType::Record(lhs_id), IType::Record(rhs_id)) => {
+            match (lhs_records.get(lhs_id), rhs_records.get(rhs_id)) {
+                (Some(lhs), Some(rhs)) => {
+                    lhs.name == rhs.name
+                        && lhs.fields.len() == rhs.fields.len()
+                        && lhs.fields.iter().zip(rhs.fields.iter()).all(|(lhs, rhs)| {
+                            lhs.name == rhs.name
+                                && itypes_compatible(&lhs.ty, lhs_records, &rhs.ty, rhs_records)
+                        })
+                }
+                _ => false,
+            }
+        }
+        (lhs, rhs) => lhs == rhs,
+    }
+}
@@ -15,6 +15,7 @@
  */
 
 mod exports;
+mod internal_globals;
 mod marine_module;
 mod wit_function;
 mod wit_instance;
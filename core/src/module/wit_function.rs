@@ -32,6 +32,8 @@ use anyhow::anyhow;
 use futures::future::BoxFuture;
 use futures::FutureExt;
 
+use parking_lot::RwLock;
+
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -40,8 +42,11 @@ enum WITFunctionInner<WB: WasmBackend> {
         func: Arc<<WB as WasmBackend>::ExportFunction>,
     },
     Import {
+        /// Name of the module this function is imported from.
+        import_namespace: String,
         // TODO: use dyn Callable here
-        callable: Arc<Callable<WB>>,
+        // shared between all clones of ITInstance, so it could be relinked on module replacement
+        callable: Arc<RwLock<Arc<Callable<WB>>>>,
     },
 }
 
@@ -97,13 +102,17 @@ impl<WB: WasmBackend> WITFunction<WB> {
     pub(super) fn from_import(
         wit_module: &MModule<WB>,
         module_name: &str,
+        import_namespace: &str,
         function_name: &str,
         arguments: Arc<Vec<IFunctionArg>>,
         outputs: Arc<Vec<IType>>,
     ) -> MResult<Self> {
         let callable = wit_module.get_callable(module_name, function_name)?;
 
-        let inner = WITFunctionInner::Import { callable };
+        let inner = WITFunctionInner::Import {
+            import_namespace: import_namespace.to_string(),
+            callable: Arc::new(RwLock::new(callable)),
+        };
 
         let name = function_name.to_string();
 
@@ -116,6 +125,26 @@ impl<WB: WasmBackend> WITFunction<WB> {
     }
 }
 
+impl<WB: WasmBackend> WITFunction<WB> {
+    /// Returns name of the module this function is imported from, if it is an import.
+    pub(super) fn import_namespace(&self) -> Option<&str> {
+        match &self.inner {
+            WITFunctionInner::Import {
+                import_namespace, ..
+            } => Some(import_namespace),
+            WITFunctionInner::Export { .. } => None,
+        }
+    }
+
+    /// Makes an import call the provided function instead of the current one,
+    /// all clones of this function are relinked as well.
+    pub(super) fn relink(&self, new_callable: Arc<Callable<WB>>) {
+        if let WITFunctionInner::Import { callable, .. } = &self.inner {
+            *callable.write() = new_callable;
+        }
+    }
+}
+
 impl<WB: WasmBackend> wasm::structures::LocalImport<DelayedContextLifetime<WB>>
     for WITFunction<WB>
 {
@@ -161,10 +190,14 @@ impl<WB: WasmBackend> wasm::structures::LocalImport<DelayedContextLifetime<WB>>
                     .await
                     .map_err(|e| anyhow!(e))
                     .map(|results| results.iter().map(wval_to_ival).collect()),
-                WITFunctionInner::Import { callable, .. } => Arc::make_mut(&mut callable.clone())
-                    .call_async(store, arguments)
-                    .await
-                    .map_err(|e| anyhow!(e)),
+                WITFunctionInner::Import { callable, .. } => {
+                    // the lock is released before the call, so a relinking can't be blocked by it
+                    let mut callable = callable.read().clone();
+                    Arc::make_mut(&mut callable)
+                        .call_async(store, arguments)
+                        .await
+                        .map_err(|e| anyhow!(e))
+                }
             }
         }
         .boxed()
@@ -75,6 +75,30 @@ impl<WB: WasmBackend> ITInstance<WB> {
         })
     }
 
+    /// Returns functions imported from the module with the given name.
+    pub(super) fn imports_from<'s>(
+        &'s self,
+        import_namespace: &'s str,
+    ) -> impl Iterator<Item = &'s WITFunction<WB>> + 's {
+        self.funcs
+            .values()
+            .filter(move |func| func.import_namespace() == Some(import_namespace))
+    }
+
+    pub(super) fn record_types(&self) -> &MRecordTypes {
+        &self.record_types_by_id
+    }
+
+    /// Returns size of the module memory in bytes.
+    pub(super) fn memory_size(&self, store: &mut <WB as WasmBackend>::ContextMut<'_>) -> usize {
+        use marine_wasm_backend_traits::Memory;
+
+        self.memories
+            .first()
+            .map(|memory| memory.size(store))
+            .unwrap_or_default()
+    }
+
     fn extract_raw_exports(
         wasm_instance: &<WB as WasmBackend>::Instance,
         store: &mut <WB as WasmBackend>::Store,
@@ -125,6 +149,7 @@ impl<WB: WasmBackend> ITInstance<WB> {
                     let func = WITFunction::from_import(
                         module,
                         module_name,
+                        import.namespace,
                         import.name,
                         arguments,
                         output_types,
@@ -0,0 +1,236 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_wasm_backend_traits::WasiParameters;
+use marine_wasm_backend_traits::WValue;
+
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use thiserror::Error as ThisError;
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Magic bytes every snapshot file starts with.
+const SNAPSHOT_MAGIC: &[u8; 4] = b"MRSS";
+
+/// Version of the on-disk snapshot format, it's written right after the magic bytes.
+/// Must be incremented on every incompatible change of the snapshot structures.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+pub type SnapshotResult<T> = std::result::Result<T, SnapshotError>;
+
+#[derive(Debug, ThisError)]
+pub enum SnapshotError {
+    /// The data doesn't start with the snapshot magic bytes.
+    #[error("provided data isn't a Marine snapshot")]
+    NotASnapshot,
+
+    /// The snapshot was made by an incompatible version of Marine.
+    #[error("snapshot format version {actual} is unsupported, expected {expected}")]
+    UnsupportedVersion { expected: u32, actual: u32 },
+
+    /// An error occurred while encoding or decoding a snapshot.
+    #[error("snapshot is corrupted: {0}")]
+    Corrupted(#[from] bincode::Error),
+
+    /// Various errors related to file i/o.
+    #[error("snapshot i/o error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    /// A module was compiled from other bytes than the module in the snapshot.
+    #[error("module '{0}' differs from the one the snapshot was made from")]
+    ModuleMismatch(String),
+
+    /// A module stored in the snapshot wasn't provided for restoring.
+    #[error("module '{0}' is present in the snapshot, but wasn't provided")]
+    MissingModule(String),
+
+    /// Snapshots weren't enabled in the config of the Marine instance.
+    #[error("snapshots aren't enabled for this Marine instance")]
+    SnapshotsDisabled,
+
+    /// The Wasm backend can't read globals, so the module state can't be saved completely.
+    #[error("the Wasm backend doesn't support reading globals of module '{0}'")]
+    UnsupportedBackend(String),
+
+    /// Marine instance the snapshot is restored into already has modules.
+    #[error("snapshot could be restored only into an empty Marine instance")]
+    NonEmptyMarine,
+
+    /// A module memory is larger than the one saved in the snapshot, memory can't be shrunk.
+    #[error(
+        "module '{module_name}' memory is {actual} bytes, but snapshot has only {expected} bytes"
+    )]
+    MemorySizeMismatch {
+        module_name: String,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+/// State of all modules loaded into `MarineCore`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarineCoreSnapshot {
+    /// Modules in the order they were loaded.
+    pub modules: Vec<ModuleSnapshot>,
+}
+
+/// State of one module: its linear memory, globals and WASI parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleSnapshot {
+    pub name: String,
+
+    /// SHA-256 of the Wasm bytes the module was created from.
+    pub wasm_hash: [u8; 32],
+
+    /// WASI parameters the module was instantiated with.
+    pub wasi: WasiSnapshot,
+
+    /// Content of the module linear memory.
+    pub memory: Vec<u8>,
+
+    /// Mutable globals with their values, internal globals are named by their indices.
+    pub globals: Vec<(String, GlobalValue)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WasiSnapshot {
+    pub args: Vec<String>,
+    pub envs: HashMap<String, String>,
+    pub mapped_dirs: HashMap<String, PathBuf>,
+}
+
+/// A serializable counterpart of `WValue`, floats are kept as raw bits to preserve NaNs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GlobalValue {
+    I32(i32),
+    I64(i64),
+    F32(u32),
+    F64(u64),
+}
+
+impl MarineCoreSnapshot {
+    /// Serializes the snapshot into the versioned binary format.
+    pub fn write_to(&self, mut writer: impl Write) -> SnapshotResult<()> {
+        writer.write_all(SNAPSHOT_MAGIC)?;
+        writer.write_all(&SNAPSHOT_FORMAT_VERSION.to_le_bytes())?;
+        bincode::serialize_into(writer, self)?;
+
+        Ok(())
+    }
+
+    /// Deserializes a snapshot previously written by `write_to`.
+    pub fn read_from(mut reader: impl Read) -> SnapshotResult<Self> {
+        let mut magic = [0u8; 4];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|_| SnapshotError::NotASnapshot)?;
+        if &magic != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+
+        let mut version = [0u8; 4];
+        reader.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != SNAPSHOT_FORMAT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                expected: SNAPSHOT_FORMAT_VERSION,
+                actual: version,
+            });
+        }
+
+        let snapshot = bincode::deserialize_from(reader)?;
+        Ok(snapshot)
+    }
+
+    /// Saves the snapshot to a file by the given path.
+    pub fn save(&self, path: impl AsRef<Path>) -> SnapshotResult<()> {
+        let file = std::fs::File::create(path)?;
+        self.write_to(std::io::BufWriter::new(file))
+    }
+
+    /// Loads a snapshot from a file by the given path.
+    pub fn load(path: impl AsRef<Path>) -> SnapshotResult<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::read_from(std::io::BufReader::new(file))
+    }
+}
+
+impl ModuleSnapshot {
+    /// Checks that the module in the snapshot was created from the provided bytes.
+    pub fn check_wasm_bytes(&self, wasm_bytes: &[u8]) -> SnapshotResult<()> {
+        if wasm_hash(wasm_bytes) != self.wasm_hash {
+            return Err(SnapshotError::ModuleMismatch(self.name.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) fn wasm_hash(wasm_bytes: &[u8]) -> [u8; 32] {
+    sha2::Sha256::digest(wasm_bytes).into()
+}
+
+impl From<&WasiParameters> for WasiSnapshot {
+    fn from(parameters: &WasiParameters) -> Self {
+        Self {
+            args: parameters.args.clone(),
+            envs: parameters.envs.clone(),
+            mapped_dirs: parameters.mapped_dirs.clone(),
+        }
+    }
+}
+
+impl From<WasiSnapshot> for WasiParameters {
+    fn from(snapshot: WasiSnapshot) -> Self {
+        Self {
+            args: snapshot.args,
+            envs: snapshot.envs,
+            mapped_dirs: snapshot.mapped_dirs,
+            memory_dirs: HashMap::new(),
+            dir_policies: HashMap::new(),
+            deterministic_env: None,
+            output_capture: None,
+        }
+    }
+}
+
+impl From<&WValue> for GlobalValue {
+    fn from(value: &WValue) -> Self {
+        match value {
+            WValue::I32(value) => Self::I32(*value),
+            WValue::I64(value) => Self::I64(*value),
+            WValue::F32(value) => Self::F32(value.to_bits()),
+            WValue::F64(value) => Self::F64(value.to_bits()),
+        }
+    }
+}
+
+impl From<GlobalValue> for WValue {
+    fn from(value: GlobalValue) -> Self {
+        match value {
+            GlobalValue::I32(value) => Self::I32(value),
+            GlobalValue::I64(value) => Self::I64(value),
+            GlobalValue::F32(value) => Self::F32(f32::from_bits(value)),
+            GlobalValue::F64(value) => Self::F64(f64::from_bits(value)),
+        }
+    }
+}
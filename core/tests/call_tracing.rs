@@ -0,0 +1,89 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_core::MarineCore;
+use marine_core::MarineCoreConfig;
+use marine_wasm_backend_traits::WasmBackend;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+
+async fn load_records() -> MarineCore {
+    let effector_wasm_bytes = std::fs::read("../examples/records/artifacts/records_effector.wasm")
+        .expect("../examples/records/artifacts/records_effector.wasm should presence");
+    let pure_wasm_bytes = std::fs::read("../examples/records/artifacts/records_pure.wasm")
+        .expect("../examples/records/artifacts/records_pure.wasm should presence");
+
+    let backend = WasmtimeWasmBackend::new_async().unwrap();
+    let mut marine_core = MarineCore::new(MarineCoreConfig::new(backend, None)).unwrap();
+    marine_core
+        .load_module("records_effector", &effector_wasm_bytes, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+    marine_core
+        .load_module("records_pure", &pure_wasm_bytes, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    marine_core
+}
+
+#[tokio::test]
+pub async fn trace_cross_module_call() {
+    let mut marine_core = load_records().await;
+    marine_core.set_call_tracing(true);
+
+    marine_core
+        .call_async("records_pure", "invoke", &[])
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke pure: {:?}", e));
+
+    let trace = marine_core
+        .last_call_trace()
+        .expect("trace should be recorded");
+    assert_eq!(trace.spans.len(), 1);
+
+    let root = &trace.spans[0];
+    assert_eq!(root.module_name, "records_pure");
+    assert_eq!(root.function_name, "invoke");
+    assert!(root.error.is_none());
+    assert!(root.results_size > 0);
+    assert_eq!(root.children.len(), 1);
+
+    let child = &root.children[0];
+    assert_eq!(child.module_name, "records_effector");
+    assert_eq!(child.function_name, "mutate_struct");
+    assert!(child.arguments_size > 0);
+    assert!(child.start >= root.start);
+    assert!(child.duration <= root.duration);
+
+    let chrome_trace: serde_json::Value = serde_json::from_str(&trace.to_chrome_trace()).unwrap();
+    let events = chrome_trace["traceEvents"].as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["name"], "records_pure::invoke");
+    assert_eq!(events[1]["name"], "records_effector::mutate_struct");
+    assert_eq!(events[1]["ph"], "X");
+}
+
+#[tokio::test]
+pub async fn tracing_is_disabled_by_default() {
+    let mut marine_core = load_records().await;
+
+    marine_core
+        .call_async("records_pure", "invoke", &[])
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke pure: {:?}", e));
+
+    assert!(marine_core.last_call_trace().is_none());
+}
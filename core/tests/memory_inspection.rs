@@ -0,0 +1,64 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_core::MarineCore;
+use marine_core::MarineCoreConfig;
+use marine_core::MError;
+use marine_wasm_backend_traits::WasmBackend;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+
+use once_cell::sync::Lazy;
+
+static GREETING_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("../examples/greeting/artifacts/greeting.wasm")
+        .expect("../examples/greeting/artifacts/greeting.wasm should presence")
+});
+
+#[tokio::test]
+pub async fn read_module_memory() {
+    let backend = WasmtimeWasmBackend::new_async().unwrap();
+    let mut marine_core =
+        MarineCore::new(MarineCoreConfig::new(backend, None).with_snapshots()).unwrap();
+    marine_core
+        .load_module("greeting", &GREETING_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    let memory_size = marine_core.module_memory_size("greeting").unwrap();
+    let memory = marine_core
+        .read_module_memory("greeting", 0, memory_size)
+        .unwrap_or_else(|e| panic!("can't read module memory: {:?}", e));
+
+    let snapshot = marine_core.snapshot().unwrap();
+    assert_eq!(memory, snapshot.modules[0].memory);
+
+    let tail = marine_core
+        .read_module_memory("greeting", memory_size - 16, 16)
+        .unwrap();
+    assert_eq!(tail, memory[memory_size - 16..]);
+
+    let result = marine_core.read_module_memory("greeting", memory_size - 16, 17);
+    assert!(std::matches!(
+        result,
+        Err(MError::MemoryOutOfBounds { offset, len: 17, .. }) if offset == memory_size - 16
+    ));
+
+    let result = marine_core.read_module_memory("greeting", usize::MAX, 1);
+    assert!(std::matches!(result, Err(MError::MemoryOutOfBounds { .. })));
+
+    let result = marine_core.read_module_memory("unknown", 0, 1);
+    assert!(std::matches!(result, Err(MError::NoSuchModule(_))));
+}
@@ -0,0 +1,188 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_core::MarineCore;
+use marine_core::MarineCoreConfig;
+use marine_core::IValue;
+use marine_wasm_backend_traits::WasmBackend;
+use marine_wasmtime_backend::ModuleCacheLocation;
+use marine_wasmtime_backend::ModuleCacheStats;
+use marine_wasmtime_backend::WasmtimeConfig;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+
+use once_cell::sync::Lazy;
+
+use std::path::Path;
+
+static GREETING_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("../examples/greeting/artifacts/greeting.wasm")
+        .expect("../examples/greeting/artifacts/greeting.wasm should presence")
+});
+
+static EFFECTOR_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("../examples/records/artifacts/records_effector.wasm")
+        .expect("../examples/records/artifacts/records_effector.wasm should presence")
+});
+
+async fn call_greeting(backend: WasmtimeWasmBackend) {
+    let mut marine_core = MarineCore::new(MarineCoreConfig::new(backend, None)).unwrap();
+    marine_core
+        .load_module("greeting", &GREETING_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    let result = marine_core
+        .call_async(
+            "greeting",
+            "greeting",
+            &[IValue::String(String::from("Fluence"))],
+        )
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke greeting: {:?}", e));
+
+    assert_eq!(result, vec![IValue::String(String::from("Hi, Fluence"))]);
+}
+
+async fn call_records(backend: WasmtimeWasmBackend) {
+    let mut marine_core = MarineCore::new(MarineCoreConfig::new(backend, None)).unwrap();
+    marine_core
+        .load_module("records_effector", &EFFECTOR_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+}
+
+fn cached_modules_count(dir: &Path) -> usize {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .unwrap()
+                .path()
+                .extension()
+                .map_or(false, |ext| ext == "cwasm")
+        })
+        .count()
+}
+
+fn cache_stats(backend: &WasmtimeWasmBackend) -> ModuleCacheStats {
+    backend
+        .module_cache_stats()
+        .expect("the module cache must be configured")
+}
+
+#[tokio::test]
+pub async fn in_memory_cache() {
+    let mut config = WasmtimeConfig::default();
+    config.module_cache(ModuleCacheLocation::InMemory { max_modules: 8 });
+    let backend = WasmtimeWasmBackend::new(config).unwrap();
+
+    call_greeting(backend.clone()).await;
+    let stats = cache_stats(&backend);
+    assert_eq!((stats.hits, stats.misses), (0, 1));
+    assert_eq!(stats.cached_modules, 1);
+
+    // another Marine created from the same backend reuses the compiled module
+    call_greeting(backend.clone()).await;
+    let stats = cache_stats(&backend);
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+    assert_eq!(stats.cached_modules, 1);
+
+    call_records(backend.clone()).await;
+    let stats = cache_stats(&backend);
+    assert_eq!((stats.hits, stats.misses), (1, 2));
+    assert_eq!(stats.cached_modules, 2);
+    assert_eq!(stats.evictions, 0);
+}
+
+#[tokio::test]
+pub async fn in_memory_cache_eviction() {
+    let mut config = WasmtimeConfig::default();
+    config.module_cache(ModuleCacheLocation::InMemory { max_modules: 1 });
+    let backend = WasmtimeWasmBackend::new(config).unwrap();
+
+    call_greeting(backend.clone()).await;
+    call_records(backend.clone()).await;
+    let stats = cache_stats(&backend);
+    assert_eq!(stats.evictions, 1);
+    assert_eq!(stats.cached_modules, 1);
+
+    // modules evicted from the full cache are compiled again
+    call_greeting(backend.clone()).await;
+    let stats = cache_stats(&backend);
+    assert_eq!((stats.hits, stats.misses), (0, 3));
+    assert_eq!(stats.evictions, 2);
+
+    // the most recently used module stays in the cache
+    call_greeting(backend.clone()).await;
+    let stats = cache_stats(&backend);
+    assert_eq!((stats.hits, stats.misses), (1, 3));
+    assert_eq!(stats.evictions, 2);
+    assert_eq!(stats.cached_modules, 1);
+}
+
+#[tokio::test]
+pub async fn disabled_cache() {
+    let backend = WasmtimeWasmBackend::new(WasmtimeConfig::default()).unwrap();
+
+    call_greeting(backend.clone()).await;
+    assert_eq!(backend.module_cache_stats(), None);
+}
+
+#[tokio::test]
+pub async fn shared_module_cache() {
+    let backend = WasmtimeWasmBackend::new(WasmtimeConfig::default()).unwrap();
+    let backend = backend.with_shared_module_cache(4);
+
+    call_greeting(backend.clone()).await;
+    call_greeting(backend.clone()).await;
+    let stats = cache_stats(&backend);
+    assert_eq!((stats.hits, stats.misses), (1, 1));
+
+    // a configured cache is kept as is
+    let mut config = WasmtimeConfig::default();
+    config.module_cache(ModuleCacheLocation::InMemory { max_modules: 1 });
+    let backend = WasmtimeWasmBackend::new(config).unwrap();
+    call_greeting(backend.clone()).await;
+    let shared_backend = backend.with_shared_module_cache(4);
+    call_greeting(shared_backend).await;
+    assert_eq!(cache_stats(&backend).hits, 1);
+}
+
+#[tokio::test]
+pub async fn directory_cache() {
+    let cache_dir = tempfile::tempdir().unwrap();
+
+    let mut config = WasmtimeConfig::default();
+    config.module_cache(ModuleCacheLocation::Directory(
+        cache_dir.path().to_path_buf(),
+    ));
+
+    call_greeting(WasmtimeWasmBackend::new(config.clone()).unwrap()).await;
+    assert_eq!(cached_modules_count(cache_dir.path()), 1);
+
+    // a new backend with the same config reuses the compiled module
+    let backend = WasmtimeWasmBackend::new(config.clone()).unwrap();
+    call_greeting(backend.clone()).await;
+    assert_eq!(cached_modules_count(cache_dir.path()), 1);
+    let stats = cache_stats(&backend);
+    assert_eq!((stats.hits, stats.misses), (1, 0));
+
+    // a config change invalidates the cached module
+    config.consume_fuel(true);
+    call_greeting(WasmtimeWasmBackend::new(config).unwrap()).await;
+    assert_eq!(cached_modules_count(cache_dir.path()), 2);
+}
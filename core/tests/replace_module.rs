@@ -0,0 +1,175 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_core::MarineCore;
+use marine_core::MarineCoreConfig;
+use marine_core::IValue;
+use marine_core::MError;
+use marine_core::ReplacementIncompatibility;
+use marine_wasm_backend_traits::WasmBackend;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+
+use once_cell::sync::Lazy;
+
+static EFFECTOR_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("../examples/records/artifacts/records_effector.wasm")
+        .expect("../examples/records/artifacts/records_effector.wasm should presence")
+});
+
+static PURE_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("../examples/records/artifacts/records_pure.wasm")
+        .expect("../examples/records/artifacts/records_pure.wasm should presence")
+});
+
+static GREETING_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("../examples/greeting/artifacts/greeting.wasm")
+        .expect("../examples/greeting/artifacts/greeting.wasm should presence")
+});
+
+static DEPENDENCY_V1_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("./tests/wasm_tests/relinking/artifacts/relinking_dependency_v1.wasm").expect(
+        "./tests/wasm_tests/relinking/artifacts/relinking_dependency_v1.wasm should presence",
+    )
+});
+
+static DEPENDENCY_V2_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("./tests/wasm_tests/relinking/artifacts/relinking_dependency_v2.wasm").expect(
+        "./tests/wasm_tests/relinking/artifacts/relinking_dependency_v2.wasm should presence",
+    )
+});
+
+static DEPENDENCY_BROKEN_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("./tests/wasm_tests/relinking/artifacts/relinking_dependency_broken.wasm").expect(
+        "./tests/wasm_tests/relinking/artifacts/relinking_dependency_broken.wasm should presence",
+    )
+});
+
+static DEPENDENT_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("./tests/wasm_tests/relinking/artifacts/relinking_dependent.wasm")
+        .expect("./tests/wasm_tests/relinking/artifacts/relinking_dependent.wasm should presence")
+});
+
+async fn load_relinking() -> MarineCore {
+    let backend = WasmtimeWasmBackend::new_async().unwrap();
+    let mut marine_core = MarineCore::new(MarineCoreConfig::new(backend, None)).unwrap();
+
+    marine_core
+        .load_module("dependency", &DEPENDENCY_V1_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    marine_core
+        .load_module("dependent", &DEPENDENT_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    marine_core
+}
+
+async fn load_records() -> MarineCore {
+    let backend = WasmtimeWasmBackend::new_async().unwrap();
+    let mut marine_core = MarineCore::new(MarineCoreConfig::new(backend, None)).unwrap();
+
+    marine_core
+        .load_module("records_effector", &EFFECTOR_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    marine_core
+        .load_module("records_pure", &PURE_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    marine_core
+}
+
+#[tokio::test]
+pub async fn replace_dependency() {
+    let mut marine_core = load_relinking().await;
+
+    let result = marine_core
+        .call_async("dependent", "dependency_version", &[])
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke dependent: {:?}", e));
+    assert_eq!(result, vec![IValue::String("dependency v1".to_string())]);
+
+    marine_core
+        .replace_module("dependency", &DEPENDENCY_V2_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't replace a module: {:?}", e));
+
+    let result = marine_core
+        .call_async("dependent", "dependency_version", &[])
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke dependent after replacement: {:?}", e));
+    assert_eq!(result, vec![IValue::String("dependency v2".to_string())]);
+}
+
+#[tokio::test]
+pub async fn incompatible_module_is_not_started() {
+    let mut marine_core = load_relinking().await;
+
+    // main of this module panics, so the replacement would fail with a trap if it was started
+    let replace_result = marine_core
+        .replace_module("dependency", &DEPENDENCY_BROKEN_WASM_BYTES, <_>::default())
+        .await;
+
+    assert!(std::matches!(
+        replace_result,
+        Err(MError::IncompatibleModuleReplacement {
+            reason: ReplacementIncompatibility::MissingFunction { .. },
+            ..
+        })
+    ));
+
+    let result = marine_core
+        .call_async("dependent", "dependency_version", &[])
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke dependent: {:?}", e));
+    assert_eq!(result, vec![IValue::String("dependency v1".to_string())]);
+}
+
+#[tokio::test]
+pub async fn replace_with_incompatible_module() {
+    let mut marine_core = load_records().await;
+
+    let replace_result = marine_core
+        .replace_module("records_effector", &GREETING_WASM_BYTES, <_>::default())
+        .await;
+
+    assert!(std::matches!(
+        replace_result,
+        Err(MError::IncompatibleModuleReplacement {
+            reason: ReplacementIncompatibility::MissingFunction { .. },
+            ..
+        })
+    ));
+
+    // the old module is still in use
+    let result = marine_core.call_async("records_pure", "invoke", &[]).await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+pub async fn replace_not_loaded_module() {
+    let mut marine_core = load_records().await;
+
+    let replace_result = marine_core
+        .replace_module("greeting", &GREETING_WASM_BYTES, <_>::default())
+        .await;
+
+    assert!(std::matches!(replace_result, Err(MError::NoSuchModule(_))));
+}
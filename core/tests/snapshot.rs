@@ -0,0 +1,162 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_core::MarineCore;
+use marine_core::MarineCoreConfig;
+use marine_core::MarineCoreSnapshot;
+use marine_core::IValue;
+use marine_core::SnapshotError;
+use marine_wasm_backend_traits::WasmBackend;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+
+use once_cell::sync::Lazy;
+
+use std::collections::HashMap;
+
+static STATEFUL_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("./tests/wasm_tests/stateful/artifacts/stateful.wasm")
+        .expect("./tests/wasm_tests/stateful/artifacts/stateful.wasm should presence")
+});
+
+static GREETING_WASM_BYTES: Lazy<Vec<u8>> = Lazy::new(|| {
+    std::fs::read("../examples/greeting/artifacts/greeting.wasm")
+        .expect("../examples/greeting/artifacts/greeting.wasm should presence")
+});
+
+fn new_core() -> MarineCore {
+    let backend = WasmtimeWasmBackend::new_async().unwrap();
+    MarineCore::new(MarineCoreConfig::new(backend, None).with_snapshots()).unwrap()
+}
+
+async fn call_stateful(marine_core: &mut MarineCore, func_name: &str, args: &[IValue]) -> IValue {
+    let mut result = marine_core
+        .call_async("stateful", func_name, args)
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke {}: {:?}", func_name, e));
+    result.remove(0)
+}
+
+async fn push(marine_core: &mut MarineCore, item: &str) -> IValue {
+    call_stateful(marine_core, "push", &[IValue::String(item.to_string())]).await
+}
+
+fn strings(items: &[&str]) -> IValue {
+    let items = items
+        .iter()
+        .map(|item| IValue::String(item.to_string()))
+        .collect::<Vec<_>>();
+    IValue::Array(items)
+}
+
+#[tokio::test]
+pub async fn snapshot_restore() {
+    let mut marine_core = new_core();
+    marine_core
+        .load_module("stateful", &STATEFUL_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    push(&mut marine_core, "first").await;
+    push(&mut marine_core, "second").await;
+
+    let snapshot = marine_core
+        .snapshot()
+        .unwrap_or_else(|e| panic!("can't make a snapshot: {:?}", e));
+    // the stack pointer isn't exported by the module, but must be saved
+    assert!(snapshot.modules[0]
+        .globals
+        .iter()
+        .any(|(name, _)| name.starts_with("__marine_global_")));
+
+    // changes after the snapshot mustn't be restored
+    push(&mut marine_core, "not saved").await;
+
+    let mut snapshot_bytes = Vec::new();
+    snapshot.write_to(&mut snapshot_bytes).unwrap();
+    let snapshot = MarineCoreSnapshot::read_from(snapshot_bytes.as_slice()).unwrap();
+
+    let mut restored_core = new_core();
+    let modules = HashMap::from([(
+        "stateful".to_string(),
+        (STATEFUL_WASM_BYTES.clone(), <_>::default()),
+    )]);
+    restored_core
+        .restore(&snapshot, modules)
+        .await
+        .unwrap_or_else(|e| panic!("can't restore from a snapshot: {:?}", e));
+
+    let restored_snapshot = restored_core.snapshot().unwrap();
+    assert_eq!(restored_snapshot, snapshot);
+
+    let items = call_stateful(&mut restored_core, "items", &[]).await;
+    assert_eq!(items, strings(&["first", "second"]));
+
+    // the module keeps working from the restored state
+    let count = push(&mut restored_core, "third").await;
+    assert_eq!(count, IValue::U32(3));
+    let items = call_stateful(&mut restored_core, "items", &[]).await;
+    assert_eq!(items, strings(&["first", "second", "third"]));
+}
+
+#[tokio::test]
+pub async fn snapshots_are_disabled_by_default() {
+    let backend = WasmtimeWasmBackend::new_async().unwrap();
+    let mut marine_core = MarineCore::new(MarineCoreConfig::new(backend, None)).unwrap();
+    marine_core
+        .load_module("stateful", &STATEFUL_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    let result = marine_core.snapshot();
+    assert!(std::matches!(
+        result,
+        Err(marine_core::MError::SnapshotError(
+            SnapshotError::SnapshotsDisabled
+        ))
+    ));
+}
+
+#[tokio::test]
+pub async fn snapshot_of_another_module() {
+    let mut marine_core = new_core();
+    marine_core
+        .load_module("greeting", &GREETING_WASM_BYTES, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    let snapshot = marine_core.snapshot().unwrap();
+
+    let mut other_bytes = GREETING_WASM_BYTES.clone();
+    // append an empty custom section to change the module hash
+    other_bytes.extend_from_slice(&[0, 2, 1, b'x']);
+
+    let mut restored_core = new_core();
+    let modules = HashMap::from([("greeting".to_string(), (other_bytes, <_>::default()))]);
+    let result = restored_core.restore(&snapshot, modules).await;
+
+    assert!(std::matches!(
+        result,
+        Err(marine_core::MError::SnapshotError(
+            SnapshotError::ModuleMismatch(_)
+        ))
+    ));
+}
+
+#[test]
+pub fn corrupted_snapshot() {
+    let result = MarineCoreSnapshot::read_from(&b"not a snapshot"[..]);
+    assert!(std::matches!(result, Err(SnapshotError::NotASnapshot)));
+}
@@ -0,0 +1,120 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_core::MarineCore;
+use marine_core::MarineCoreConfig;
+use marine_core::MModuleConfig;
+use marine_wasm_backend_traits::OpenFd;
+use marine_wasm_backend_traits::OpenFdKind;
+use marine_wasm_backend_traits::DirUsage;
+use marine_wasm_backend_traits::MappedDirPolicy;
+use marine_wasm_backend_traits::MemoryDir;
+use marine_wasm_backend_traits::MemoryFile;
+use marine_wasm_backend_traits::MemoryNode;
+use marine_wasm_backend_traits::PreopenedDir;
+use marine_wasm_backend_traits::WasiParameters;
+use marine_wasm_backend_traits::WasmBackend;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+
+use std::collections::HashMap;
+
+#[tokio::test]
+pub async fn wasi_state_reports_parameters() {
+    let greeting_wasm_bytes = std::fs::read("../examples/greeting/artifacts/greeting.wasm")
+        .expect("../examples/greeting/artifacts/greeting.wasm should presence");
+    let host_dir = tempfile::tempdir().unwrap();
+    let scratch_dir = MemoryDir::default();
+    scratch_dir.insert(
+        "file",
+        MemoryNode::File(MemoryFile::from_bytes(b"abc".to_vec())),
+    );
+    let read_only = MappedDirPolicy {
+        read_only: true,
+        ..<_>::default()
+    };
+    let limited = MappedDirPolicy {
+        max_bytes: Some(1024),
+        ..<_>::default()
+    };
+
+    let wasi_parameters = WasiParameters {
+        args: vec![String::from("greeting"), String::from("--verbose")],
+        envs: HashMap::from([(String::from("NAME"), String::from("Fluence"))]),
+        mapped_dirs: HashMap::from([(String::from("/data"), host_dir.path().to_path_buf())]),
+        memory_dirs: HashMap::from([(String::from("/scratch"), scratch_dir)]),
+        dir_policies: HashMap::from([
+            (String::from("/data"), read_only.clone()),
+            (String::from("/scratch"), limited.clone()),
+        ]),
+        deterministic_env: None,
+        output_capture: None,
+    };
+    let config = MModuleConfig {
+        wasi_parameters,
+        ..<_>::default()
+    };
+
+    let backend = WasmtimeWasmBackend::new_async().unwrap();
+    let mut marine_core = MarineCore::new(MarineCoreConfig::new(backend, None)).unwrap();
+    marine_core
+        .load_module("greeting", &greeting_wasm_bytes, config)
+        .await
+        .unwrap_or_else(|e| panic!("can't load a module into Marine: {:?}", e));
+
+    let wasi_state = marine_core.module_wasi_state("greeting").unwrap();
+    assert_eq!(wasi_state.args(), ["greeting", "--verbose"]);
+    assert_eq!(wasi_state.envs(), [b"NAME=Fluence".to_vec()]);
+    assert_eq!(
+        wasi_state.preopened_dirs(),
+        [
+            PreopenedDir {
+                fd: 3,
+                guest_path: String::from("/data"),
+                host_path: Some(host_dir.path().to_path_buf()),
+                policy: read_only,
+                usage: None,
+            },
+            PreopenedDir {
+                fd: 4,
+                guest_path: String::from("/scratch"),
+                host_path: None,
+                policy: limited,
+                usage: Some(DirUsage { bytes: 3, files: 1 }),
+            }
+        ]
+    );
+
+    let stdio = |fd| OpenFd {
+        fd,
+        kind: OpenFdKind::Stdio,
+    };
+    assert_eq!(
+        wasi_state.open_fds(),
+        [
+            stdio(0),
+            stdio(1),
+            stdio(2),
+            OpenFd {
+                fd: 3,
+                kind: OpenFdKind::PreopenedDir
+            },
+            OpenFd {
+                fd: 4,
+                kind: OpenFdKind::PreopenedDir
+            }
+        ]
+    );
+}
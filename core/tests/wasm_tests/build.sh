@@ -9,3 +9,21 @@
 )
 
 cp ../../../target/wasm32-wasi/release/lilo_after_2gb.wasm lilo_after_2gb/artifacts/
+
+(
+  cd relinking || exit;
+  cargo run  --release -p marine -- build --release;
+  rm artifacts/* || true;
+  mkdir artifacts
+)
+
+cp ../../../target/wasm32-wasi/release/relinking_*.wasm relinking/artifacts/
+
+(
+  cd stateful || exit;
+  cargo run  --release -p marine -- build --release;
+  rm artifacts/* || true;
+  mkdir artifacts
+)
+
+cp ../../../target/wasm32-wasi/release/stateful.wasm stateful/artifacts/
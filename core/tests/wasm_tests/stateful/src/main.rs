@@ -0,0 +1,43 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_rs_sdk::marine;
+use marine_rs_sdk::module_manifest;
+
+use std::cell::RefCell;
+
+module_manifest!();
+
+thread_local! {
+    static ITEMS: RefCell<Vec<String>> = RefCell::new(Vec::new());
+}
+
+pub fn main() {}
+
+/// Keeps an item in the module memory, returns the number of kept items.
+#[marine]
+pub fn push(item: String) -> u32 {
+    ITEMS.with(|items| {
+        let mut items = items.borrow_mut();
+        items.push(item);
+        items.len() as u32
+    })
+}
+
+#[marine]
+pub fn items() -> Vec<String> {
+    ITEMS.with(|items| items.borrow().clone())
+}
@@ -16,6 +16,7 @@
 
 use crate::generic::AppService;
 use crate::generic::AppServiceConfig;
+use crate::generic::AppServicePool;
 use crate::AppServiceError;
 
 use marine_wasm_backend_traits::WasmBackend;
@@ -23,6 +24,7 @@ use marine_wasmtime_backend::WasmtimeConfig;
 use marine_wasmtime_backend::WasmtimeWasmBackend;
 
 use std::collections::HashMap;
+use std::convert::TryInto;
 
 #[derive(Clone)]
 pub struct AppServiceFactory<WB: WasmBackend> {
@@ -45,6 +47,22 @@ impl<WB: WasmBackend> AppServiceFactory<WB> {
         AppService::new_with_backend(self.backend.clone(), config, service_id, envs).await
     }
 
+    /// Creates a pool of `size` identical services, see [`AppServicePool`].
+    pub async fn new_app_service_pool<C, S>(
+        &self,
+        config: C,
+        service_id: S,
+        envs: HashMap<String, String>,
+        size: usize,
+    ) -> crate::Result<AppServicePool<WB>>
+    where
+        C: TryInto<AppServiceConfig<WB>> + Clone,
+        S: Into<String>,
+        AppServiceError: From<C::Error>,
+    {
+        AppServicePool::new_with_backend(self.backend.clone(), config, service_id, envs, size).await
+    }
+
     #[cfg(feature = "raw-module-api")]
     pub async fn new_app_service_empty_facade<S>(
         &self,
@@ -0,0 +1,250 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::Result;
+use crate::config::AppServiceConfig;
+use crate::service::AppService;
+use crate::service_interface::ServiceInterface;
+use super::AppServiceError;
+
+use marine_wasm_backend_traits::WasmBackend;
+use marine::IValue;
+
+use futures::channel::mpsc;
+use futures::lock::Mutex;
+use futures::StreamExt;
+use serde_json::Value as JValue;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// A pool of identically configured services that allows to serve several calls concurrently.
+///
+/// Each call is dispatched to an idle instance, if there are no idle instances the call waits
+/// for one to be released. Instances don't share their state, so the pool is suitable for
+/// services which facade doesn't rely on the state left by previous calls.
+///
+/// Instances share compiled modules: if the backend has no module cache,
+/// the pool enables an in-memory one, so every module is compiled only once.
+///
+/// All instances are created from the same config with the same service id, so they share
+/// mapped host directories and in-memory directories: files written by one instance are seen
+/// by the others, and concurrent writes to the same file aren't synchronized.
+pub struct AppServicePool<WB: WasmBackend> {
+    instances: Vec<Mutex<AppService<WB>>>,
+
+    /// Last observed memory size of every instance, updated after each call.
+    memory_sizes: Vec<AtomicUsize>,
+
+    idle_sender: mpsc::UnboundedSender<usize>,
+    idle_receiver: Mutex<mpsc::UnboundedReceiver<usize>>,
+
+    total_memory_limit: Option<u64>,
+}
+
+// calls are expected to be dispatched to a pool from several threads
+#[cfg(feature = "wasmtime")]
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<AppServicePool<marine_wasmtime_backend::WasmtimeWasmBackend>>();
+};
+
+/// Memory consumed by all instances of a pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolMemoryStats {
+    /// Sum of the memory sizes of all instances (in bytes).
+    pub memory_size: usize,
+
+    /// Memory size of each instance (in bytes).
+    pub instances_memory_size: Vec<usize>,
+
+    /// Memory limit of the whole pool, it's evenly split between instances.
+    pub total_memory_limit: Option<u64>,
+}
+
+/// Returns an instance to the idle queue when dropped, even if the call was cancelled.
+struct IdleGuard<'pool> {
+    index: usize,
+    idle_sender: &'pool mpsc::UnboundedSender<usize>,
+}
+
+impl<WB: WasmBackend> AppServicePool<WB> {
+    /// Create a pool of `size` services with the given config and service id.
+    /// `total_memory_limit` from the config is applied to the whole pool,
+    /// so each instance gets `total_memory_limit / size` bytes.
+    pub async fn new_with_backend<C, S>(
+        backend: WB,
+        config: C,
+        service_id: S,
+        envs: HashMap<String, String>,
+        size: usize,
+    ) -> Result<Self>
+    where
+        C: TryInto<AppServiceConfig<WB>> + Clone,
+        S: Into<String>,
+        AppServiceError: From<C::Error>,
+    {
+        if size == 0 {
+            return Err(AppServiceError::InvalidConfig(String::from(
+                "service pool should contain at least one instance",
+            )));
+        }
+
+        let service_id = service_id.into();
+        let instance_config: AppServiceConfig<WB> = config.clone().try_into()?;
+        let modules_count = instance_config.marine_config.modules_config.len();
+        let backend = backend.with_shared_module_cache(modules_count.max(1));
+
+        let mut instances = Vec::with_capacity(size);
+        let mut total_memory_limit = None;
+        for _ in 0..size {
+            let mut config: AppServiceConfig<WB> = config.clone().try_into()?;
+            total_memory_limit = config.marine_config.total_memory_limit;
+            config.marine_config.total_memory_limit =
+                total_memory_limit.map(|limit| limit / size as u64);
+
+            let service = AppService::new_with_backend::<AppServiceConfig<WB>, _>(
+                backend.clone(),
+                config,
+                service_id.clone(),
+                envs.clone(),
+            )
+            .await?;
+            instances.push(service);
+        }
+
+        let memory_sizes = instances
+            .iter()
+            .map(|service| AtomicUsize::new(memory_size(service)))
+            .collect();
+
+        let (idle_sender, idle_receiver) = mpsc::unbounded();
+        for index in 0..size {
+            // the receiver is alive, so sending can't fail
+            let _ = idle_sender.unbounded_send(index);
+        }
+
+        Ok(Self {
+            instances: instances.into_iter().map(Mutex::new).collect(),
+            memory_sizes,
+            idle_sender,
+            idle_receiver: Mutex::new(idle_receiver),
+            total_memory_limit,
+        })
+    }
+
+    /// Call a specified function of the facade module on an idle instance
+    /// with arguments in json format.
+    pub async fn call_async(
+        &self,
+        func_name: impl AsRef<str>,
+        arguments: JValue,
+        call_parameters: crate::CallParameters,
+    ) -> Result<JValue> {
+        let guard = self.acquire().await;
+        let mut service = self.instances[guard.index].lock().await;
+        let result = service
+            .call_async(func_name, arguments, call_parameters)
+            .await;
+        self.update_memory_size(guard.index, &service);
+
+        result
+    }
+
+    /// Call a specified function of the facade module on an idle instance
+    /// with arguments in IValue format.
+    pub async fn call_with_ivalues_async(
+        &self,
+        func_name: impl AsRef<str>,
+        arguments: &[IValue],
+        call_parameters: crate::CallParameters,
+    ) -> Result<Vec<IValue>> {
+        let guard = self.acquire().await;
+        let mut service = self.instances[guard.index].lock().await;
+        let result = service
+            .call_with_ivalues_async(func_name, arguments, call_parameters)
+            .await;
+        self.update_memory_size(guard.index, &service);
+
+        result
+    }
+
+    /// Return interface of the service, it's the same for all instances.
+    pub async fn get_interface(&self) -> ServiceInterface {
+        let guard = self.acquire().await;
+        let service = self.instances[guard.index].lock().await;
+        service.get_interface()
+    }
+
+    /// Return memory consumed by the pool, sizes are updated after each call.
+    /// This operation is cheap and doesn't wait for running calls.
+    pub fn memory_stats(&self) -> PoolMemoryStats {
+        let instances_memory_size = self
+            .memory_sizes
+            .iter()
+            .map(|size| size.load(Ordering::Relaxed))
+            .collect::<Vec<_>>();
+
+        PoolMemoryStats {
+            memory_size: instances_memory_size.iter().sum(),
+            instances_memory_size,
+            total_memory_limit: self.total_memory_limit,
+        }
+    }
+
+    /// Return number of instances in the pool.
+    pub fn size(&self) -> usize {
+        self.instances.len()
+    }
+
+    async fn acquire(&self) -> IdleGuard<'_> {
+        let index = self
+            .idle_receiver
+            .lock()
+            .await
+            .next()
+            .await
+            // the pool owns a sender, so the stream never ends
+            .expect("idle instances queue can't be closed while the pool is alive");
+
+        IdleGuard {
+            index,
+            idle_sender: &self.idle_sender,
+        }
+    }
+
+    fn update_memory_size(&self, index: usize, service: &AppService<WB>) {
+        self.memory_sizes[index].store(memory_size(service), Ordering::Relaxed);
+    }
+}
+
+impl Drop for IdleGuard<'_> {
+    fn drop(&mut self) {
+        // the pool owns the receiver, so sending can't fail
+        let _ = self.idle_sender.unbounded_send(self.index);
+    }
+}
+
+fn memory_size<WB: WasmBackend>(service: &AppService<WB>) -> usize {
+    service
+        .module_memory_stats()
+        .modules
+        .iter()
+        .map(|module| module.memory_size)
+        .sum()
+}
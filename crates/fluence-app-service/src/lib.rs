@@ -30,6 +30,7 @@ mod service;
 mod service_interface;
 mod raw_toml_config;
 mod app_service_factory;
+mod app_service_pool;
 
 pub(crate) type Result<T> = std::result::Result<T, AppServiceError>;
 
@@ -39,6 +40,7 @@ pub use service_interface::RecordType;
 pub use service_interface::ServiceInterface;
 
 pub use raw_toml_config::TomlAppServiceConfig;
+pub use app_service_pool::PoolMemoryStats;
 
 pub use marine::ConfigContext;
 pub use marine::WithContext;
@@ -48,8 +50,19 @@ pub use marine::TomlMarineNamedModuleConfig;
 pub use marine::TomlValue;
 pub use marine::TomlValueTable;
 pub use marine::TomlWASIConfig;
+pub use marine::MemoryLimit;
+pub use marine::TomlMountedBinary;
+pub use marine::TomlMountedBinaryPolicy;
+pub use marine::TomlMappedDir;
+pub use marine::TomlMappedDirConfig;
+pub use marine::TomlMappedDirKind;
+pub use marine::TomlMappedDirAccess;
+pub use marine::TomlModuleOutput;
+pub use marine::ConfigIssue;
+pub use marine::ConfigIssueKind;
 
 pub use marine::MarineError;
+pub use marine::ModuleDependencyIssue;
 pub use marine::MError;
 
 pub use marine::IValue;
@@ -62,6 +75,21 @@ pub use marine::from_interface_values;
 pub use marine::ModuleMemoryStat;
 pub use marine::MemoryStats;
 pub use marine::ne_vec;
+pub use marine::MarineSnapshot;
+pub use marine::FuelMeteredResult;
+pub use marine::ModuleCallOutput;
+pub use marine::CapturedOutput;
+pub use marine::ModuleSnapshot;
+pub use marine::SnapshotError;
+pub use marine::CallTrace;
+pub use marine::CallSpan;
+pub use marine::DirUsage;
+pub use marine::MappedDirPolicy;
+pub use marine::MemoryDir;
+pub use marine::MemoryFile;
+pub use marine::MemoryNode;
+pub use marine::MemoryDirSeed;
+pub use marine::ModuleOutput;
 
 pub use marine_min_it_version::min_sdk_version;
 pub use marine_min_it_version::min_it_version;
@@ -73,6 +101,7 @@ pub use marine::SecurityTetraplet;
 pub mod generic {
     pub use crate::service::AppService;
     pub use crate::app_service_factory::AppServiceFactory;
+    pub use crate::app_service_pool::AppServicePool;
     pub use crate::config::AppServiceConfig;
 
     pub use marine::generic::MarineConfig;
@@ -86,9 +115,12 @@ pub mod wasmtime {
     pub type WasmBackend = marine_wasmtime_backend::WasmtimeWasmBackend;
 
     pub use marine_wasmtime_backend::WasmtimeConfig;
+    pub use marine_wasmtime_backend::ModuleCacheLocation;
+    pub use marine_wasmtime_backend::ModuleCacheStats;
 
     pub type AppService = crate::service::AppService<WasmBackend>;
     pub type AppServiceFactory = crate::app_service_factory::AppServiceFactory<WasmBackend>;
+    pub type AppServicePool = crate::app_service_pool::AppServicePool<WasmBackend>;
     pub type AppServiceConfig = crate::config::AppServiceConfig<WasmBackend>;
     pub use crate::app_service_factory::EpochTicker;
 
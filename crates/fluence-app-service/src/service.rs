@@ -28,6 +28,11 @@ use marine::generic::MarineModuleConfig;
 use marine::MarineError;
 use marine::MError;
 use marine::IValue;
+use marine::MarineSnapshot;
+use marine::FuelMeteredResult;
+use marine::CallTrace;
+use marine::ModuleCallOutput;
+use marine::MemoryDir;
 
 use serde_json::Value as JValue;
 
@@ -68,23 +73,88 @@ impl<WB: WasmBackend> AppService<WB> {
         S: Into<String>,
         AppServiceError: From<C::Error>,
     {
-        let mut config: AppServiceConfig<WB> = config.try_into()?;
-        let facade_module_name = config
-            .marine_config
-            .modules_config
-            .last()
-            .ok_or_else(|| {
-                AppServiceError::ConfigParseError(String::from(
-                    "config should contain at least one module",
-                ))
-            })?
-            .import_name
-            .clone();
+        Self::new_with_backend_(backend, config.try_into()?, service_id.into(), envs, None).await
+    }
+
+    /// Create Service with given modules and restore its state from the snapshot
+    /// previously made by [`AppService::snapshot`].
+    pub async fn new_from_snapshot<C, S>(
+        config: C,
+        service_id: S,
+        envs: HashMap<String, String>,
+        snapshot: &MarineSnapshot,
+    ) -> Result<Self>
+    where
+        C: TryInto<AppServiceConfig<WB>>,
+        S: Into<String>,
+        AppServiceError: From<C::Error>,
+    {
+        let backend = <WB as WasmBackend>::new_async()
+            .map_err(|e| MarineError::EngineError(MError::WasmBackendError(e)))?;
+
+        Self::new_from_snapshot_with_backend(backend, config, service_id, envs, snapshot).await
+    }
+
+    pub async fn new_from_snapshot_with_backend<C, S>(
+        backend: WB,
+        config: C,
+        service_id: S,
+        envs: HashMap<String, String>,
+        snapshot: &MarineSnapshot,
+    ) -> Result<Self>
+    where
+        C: TryInto<AppServiceConfig<WB>>,
+        S: Into<String>,
+        AppServiceError: From<C::Error>,
+    {
+        Self::new_with_backend_(
+            backend,
+            config.try_into()?,
+            service_id.into(),
+            envs,
+            Some(snapshot),
+        )
+        .await
+    }
+
+    async fn new_with_backend_(
+        backend: WB,
+        mut config: AppServiceConfig<WB>,
+        service_id: String,
+        envs: HashMap<String, String>,
+        snapshot: Option<&MarineSnapshot>,
+    ) -> Result<Self> {
+        let modules_config = &config.marine_config.modules_config;
+        let facade_module_name = match &config.marine_config.facade {
+            Some(facade) if modules_config.iter().any(|m| &m.import_name == facade) => {
+                facade.clone()
+            }
+            Some(facade) => {
+                return Err(AppServiceError::ConfigParseError(format!(
+                    "facade module {} isn't listed in the config",
+                    facade
+                )))
+            }
+            None => modules_config
+                .last()
+                .ok_or_else(|| {
+                    AppServiceError::ConfigParseError(String::from(
+                        "config should contain at least one module",
+                    ))
+                })?
+                .import_name
+                .clone(),
+        };
 
-        let service_id = service_id.into();
         Self::set_env_and_dirs(&mut config, service_id, envs)?;
 
-        let marine = Marine::with_raw_config(backend, config.marine_config).await?;
+        let marine = match snapshot {
+            Some(snapshot) => {
+                Marine::with_raw_config_from_snapshot(backend, config.marine_config, snapshot)
+                    .await?
+            }
+            None => Marine::with_raw_config(backend, config.marine_config).await?,
+        };
 
         Ok(Self {
             marine,
@@ -110,6 +180,46 @@ impl<WB: WasmBackend> AppService<WB> {
             .map_err(Into::into)
     }
 
+    /// Call a specified function like `call_async`, but fail with `MarineError::FuelExhausted`
+    /// if the call consumes more than `fuel` units. Returns the result with the consumed fuel.
+    /// Fuel metering must be enabled in the Wasm backend config.
+    pub async fn call_with_fuel_async(
+        &mut self,
+        func_name: impl AsRef<str>,
+        arguments: JValue,
+        call_parameters: crate::CallParameters,
+        fuel: u64,
+    ) -> Result<FuelMeteredResult<JValue>> {
+        self.marine
+            .call_with_json_and_fuel_async(
+                &self.facade_module_name,
+                func_name,
+                arguments,
+                call_parameters,
+                fuel,
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Call a specified function of loaded module by its name with arguments in MessagePack format.
+    pub async fn call_with_msgpack_async(
+        &mut self,
+        func_name: impl AsRef<str>,
+        arguments: &[u8],
+        call_parameters: crate::CallParameters,
+    ) -> Result<Vec<u8>> {
+        self.marine
+            .call_with_msgpack_async(
+                &self.facade_module_name,
+                func_name,
+                arguments,
+                call_parameters,
+            )
+            .await
+            .map_err(Into::into)
+    }
+
     /// Call a specified function of loaded module by its name with arguments in IValue format.
     pub async fn call_with_ivalues_async(
         &mut self,
@@ -143,6 +253,15 @@ impl<WB: WasmBackend> AppService<WB> {
         into_service_interface(marine_facade_interface)
     }
 
+    /// Return JSON Schema of arguments and results of the service functions,
+    /// it describes the same functions as `get_interface`.
+    pub fn get_interface_schema(&self) -> Result<JValue> {
+        self.marine
+            .get_interface()
+            .module_json_schema(&self.facade_module_name)
+            .ok_or_else(|| MarineError::NoSuchModule(self.facade_module_name.clone()).into())
+    }
+
     /// Prepare service before starting by:
     ///  1. rooting all mapped directories at service_working_dir, keeping absolute paths as-is
     ///  2. adding service_id to environment variables
@@ -172,6 +291,41 @@ impl<WB: WasmBackend> AppService<WB> {
     pub fn module_memory_stats(&self) -> MemoryStats<'_> {
         self.marine.module_memory_stats()
     }
+
+    /// Enable or disable recording of calls between modules of the service.
+    pub fn set_call_tracing(&mut self, enabled: bool) {
+        self.marine.set_call_tracing(enabled)
+    }
+
+    /// Return the tree of calls made by the last call into the service, if tracing is enabled.
+    pub fn last_call_trace(&self) -> Option<CallTrace> {
+        self.marine.last_call_trace()
+    }
+
+    /// Return stdout and stderr captured from modules during the last call into the service.
+    pub fn last_call_output(&self) -> &[ModuleCallOutput] {
+        self.marine.last_call_output()
+    }
+
+    /// Return an in-memory directory mapped to the guest path of a module,
+    /// e.g. to export files the service wrote there.
+    pub fn memory_dir(
+        &self,
+        module_name: impl AsRef<str>,
+        guest_path: impl AsRef<str>,
+    ) -> Result<MemoryDir> {
+        self.marine
+            .module_memory_dir(module_name, guest_path)
+            .map_err(Into::into)
+    }
+
+    /// Make a snapshot of the service state: memories, globals and WASI parameters of all modules.
+    /// It could be saved with [`MarineSnapshot::save`] and passed to
+    /// [`AppService::new_from_snapshot`] to continue from the same state, e.g. after a restart.
+    /// Snapshots must be enabled with `snapshots = true` in the service config.
+    pub fn snapshot(&mut self) -> Result<MarineSnapshot> {
+        self.marine.snapshot().map_err(Into::into)
+    }
 }
 
 // This API is intended for testing purposes (mostly in Marine REPL)
@@ -239,6 +393,25 @@ impl<WB: WasmBackend> AppService<WB> {
         self.marine.get_interface()
     }
 
+    /// Return the linear memory size of a module in bytes.
+    pub fn module_memory_size(&self, module_name: impl AsRef<str>) -> Result<usize> {
+        self.marine
+            .module_memory_size(module_name)
+            .map_err(Into::into)
+    }
+
+    /// Return `len` bytes of a module linear memory starting from `offset`, it's left unchanged.
+    pub fn read_module_memory(
+        &self,
+        module_name: impl AsRef<str>,
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<u8>> {
+        self.marine
+            .read_module_memory(module_name, offset, len)
+            .map_err(Into::into)
+    }
+
     /// Return
     pub fn get_wasi_state(
         &mut self,
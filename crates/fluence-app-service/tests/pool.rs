@@ -0,0 +1,131 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::AppServiceFactory;
+use fluence_app_service::TomlAppServiceConfig;
+use fluence_app_service::WasmtimeConfig;
+use fluence_app_service::wasmtime::AppServicePool;
+use fluence_app_service::wasmtime::ModuleCacheLocation;
+use fluence_app_service::wasmtime::WasmBackend;
+
+use serde_json::json;
+
+fn greeting_config(total_memory_limit: &str) -> TomlAppServiceConfig {
+    let config_raw = format!(
+        r#"
+        modules_dir = "../../examples/greeting/artifacts"
+        total_memory_limit = "{total_memory_limit}"
+
+        [[module]]
+            name = "greeting"
+        "#
+    );
+
+    toml::from_str(&config_raw).expect("greeting config should be well-formed")
+}
+
+#[tokio::test]
+async fn pool_serves_concurrent_calls() {
+    let (factory, _) = AppServiceFactory::new(WasmtimeConfig::default()).unwrap();
+    let pool = factory
+        .new_app_service_pool(greeting_config("infinity"), "greeting", <_>::default(), 3)
+        .await
+        .unwrap_or_else(|e| panic!("can't create service pool: {}", e));
+    assert_eq!(pool.size(), 3);
+
+    let calls =
+        (0..10).map(|id| pool.call_async("greeting", json!([id.to_string()]), <_>::default()));
+    let results = futures::future::join_all(calls).await;
+
+    for (id, result) in results.into_iter().enumerate() {
+        assert_eq!(result.unwrap(), json!(format!("Hi, {}", id)));
+    }
+
+    let stats = pool.memory_stats();
+    assert_eq!(stats.instances_memory_size.len(), 3);
+    assert!(stats.instances_memory_size.iter().all(|size| *size > 0));
+    assert_eq!(
+        stats.memory_size,
+        stats.instances_memory_size.iter().sum::<usize>()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn pool_serves_calls_from_several_threads() {
+    let (factory, _) = AppServiceFactory::new(WasmtimeConfig::default()).unwrap();
+    let pool = factory
+        .new_app_service_pool(greeting_config("infinity"), "greeting", <_>::default(), 2)
+        .await
+        .unwrap_or_else(|e| panic!("can't create service pool: {}", e));
+    let pool = std::sync::Arc::new(pool);
+
+    // more tasks than instances, so calls overlap and some of them wait for an idle instance
+    let tasks = (0..8).map(|id| {
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            let result = pool
+                .call_async("greeting", json!([id.to_string()]), <_>::default())
+                .await;
+            (id, result)
+        })
+    });
+
+    for task in futures::future::join_all(tasks).await {
+        let (id, result) = task.unwrap();
+        assert_eq!(result.unwrap(), json!(format!("Hi, {}", id)));
+    }
+}
+
+#[tokio::test]
+async fn pool_splits_memory_limit() {
+    let (factory, _) = AppServiceFactory::new(WasmtimeConfig::default()).unwrap();
+
+    // enough for one instance, but not for two
+    let result = factory
+        .new_app_service_pool(greeting_config("2 MiB"), "greeting", <_>::default(), 2)
+        .await;
+    assert!(result.is_err());
+
+    let pool = factory
+        .new_app_service_pool(greeting_config("4 MiB"), "greeting", <_>::default(), 2)
+        .await
+        .unwrap_or_else(|e| panic!("can't create service pool: {}", e));
+    assert_eq!(
+        pool.memory_stats().total_memory_limit,
+        Some(4 * 1024 * 1024)
+    );
+}
+
+#[tokio::test]
+async fn pool_compiles_modules_once() {
+    let mut config = WasmtimeConfig::default();
+    config.module_cache(ModuleCacheLocation::InMemory { max_modules: 8 });
+    let backend = WasmBackend::new(config).unwrap();
+
+    let pool = AppServicePool::new_with_backend(
+        backend.clone(),
+        greeting_config("infinity"),
+        "greeting",
+        <_>::default(),
+        3,
+    )
+    .await
+    .unwrap_or_else(|e| panic!("can't create service pool: {}", e));
+    assert_eq!(pool.size(), 3);
+
+    let stats = backend.module_cache_stats().unwrap();
+    assert_eq!((stats.hits, stats.misses), (2, 1));
+}
@@ -0,0 +1,206 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::IValue;
+use crate::IType;
+use crate::ITMsgPackSeDeError::Se;
+use crate::MsgPackResult;
+use crate::MRecordTypes;
+
+use rmpv::Value as MValue;
+
+/// Encode function results to MessagePack according to the function output types.
+/// Byte arrays are encoded as MessagePack binaries, records as maps of their fields,
+/// and the absence of results as nil.
+pub fn ivalues_to_msgpack(
+    mut ivalues: Vec<IValue>,
+    outputs: &[IType],
+    record_types: &MRecordTypes,
+) -> MsgPackResult<Vec<u8>> {
+    if outputs.len() != ivalues.len() {
+        return Err(Se(format!(
+            "resulted values {:?} and function signature {:?} aren't compatible",
+            ivalues, outputs
+        )));
+    }
+
+    let value = match ivalues.len() {
+        0 => MValue::Nil,
+        1 => ivalue_to_mvalue(ivalues.remove(0), outputs.first().unwrap(), record_types)?,
+        _ => {
+            return Err(Se(format!(
+                "multi-values aren't supported, but {} results were returned",
+                ivalues.len()
+            )))
+        }
+    };
+
+    let mut result = Vec::new();
+    rmpv::encode::write_value(&mut result, &value).map_err(|e| {
+        Se(format!(
+            "error {} occurred while encoding function results",
+            e
+        ))
+    })?;
+
+    Ok(result)
+}
+
+fn ivalue_to_mvalue(
+    ivalue: IValue,
+    output: &IType,
+    record_types: &MRecordTypes,
+) -> MsgPackResult<MValue> {
+    // clone here needed because binding by-value and by-ref in the same pattern in unstable
+    match (ivalue, output.clone()) {
+        (IValue::Boolean(value), IType::Boolean) => Ok(MValue::from(value)),
+        (IValue::S8(value), IType::S8) => Ok(MValue::from(value)),
+        (IValue::S16(value), IType::S16) => Ok(MValue::from(value)),
+        (IValue::S32(value), IType::S32) => Ok(MValue::from(value)),
+        (IValue::S64(value), IType::S64) => Ok(MValue::from(value)),
+        (IValue::U8(value), IType::U8) => Ok(MValue::from(value)),
+        (IValue::U16(value), IType::U16) => Ok(MValue::from(value)),
+        (IValue::U32(value), IType::U32) => Ok(MValue::from(value)),
+        (IValue::U64(value), IType::U64) => Ok(MValue::from(value)),
+        (IValue::I32(value), IType::I32) => Ok(MValue::from(value)),
+        (IValue::I64(value), IType::I64) => Ok(MValue::from(value)),
+        (IValue::F32(value), IType::F32) => Ok(MValue::from(value)),
+        (IValue::F64(value), IType::F64) => Ok(MValue::from(value)),
+        (IValue::String(value), IType::String) => Ok(MValue::from(value)),
+        (IValue::ByteArray(value), IType::ByteArray) => Ok(MValue::Binary(value)),
+        (IValue::ByteArray(value), IType::Array(array_ty)) if *array_ty == IType::U8 => {
+            Ok(MValue::Binary(value))
+        }
+        (IValue::Array(value), IType::ByteArray) => Ok(MValue::Binary(ivalues_to_bytes(value)?)),
+        (IValue::Array(value), IType::Array(array_ty)) if *array_ty == IType::U8 => {
+            Ok(MValue::Binary(ivalues_to_bytes(value)?))
+        }
+        (IValue::ByteArray(value), IType::Array(array_ty)) => {
+            let result = value
+                .into_iter()
+                .map(|v| ivalue_to_mvalue(IValue::U8(v), &array_ty, record_types))
+                .collect::<MsgPackResult<Vec<_>>>()?;
+
+            Ok(MValue::Array(result))
+        }
+        (IValue::Array(value), IType::Array(array_ty)) => {
+            let result = value
+                .into_iter()
+                .map(|v| ivalue_to_mvalue(v, &array_ty, record_types))
+                .collect::<MsgPackResult<Vec<_>>>()?;
+
+            Ok(MValue::Array(result))
+        }
+        (IValue::Record(field_values), IType::Record(record_id)) => {
+            let record_type = record_types.get(&record_id).ok_or_else(|| {
+                Se(format!(
+                    "record id {} wasn't found in module record types",
+                    record_id
+                ))
+            })?;
+            let field_types = &record_type.fields;
+
+            if field_values.len() != field_types.len() {
+                return Err(Se(format!(
+                    "output record {:?} isn't compatible to output record fields {:?}",
+                    field_values, field_types
+                )));
+            }
+
+            let result = field_values
+                .into_vec()
+                .into_iter()
+                .zip(field_types.iter())
+                .map(|(field_value, field_type)| {
+                    let value = ivalue_to_mvalue(field_value, &field_type.ty, record_types)?;
+                    Ok((MValue::from(field_type.name.as_str()), value))
+                })
+                .collect::<MsgPackResult<Vec<_>>>()?;
+
+            Ok(MValue::Map(result))
+        }
+        (ivalue, itype) => Err(Se(format!(
+            "value {:?} is incompatible to type {:?}",
+            ivalue, itype
+        ))),
+    }
+}
+
+fn ivalues_to_bytes(ivalues: Vec<IValue>) -> MsgPackResult<Vec<u8>> {
+    ivalues
+        .into_iter()
+        .map(|v| match v {
+            IValue::U8(byte) => Ok(byte),
+            v => Err(Se(format!("value {:?} is incompatible to type U8", v))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ivalues_to_msgpack;
+    use crate::IValue;
+    use crate::IType;
+    use crate::ITMsgPackSeDeError;
+    use crate::MRecordTypes;
+
+    use rmpv::Value as MValue;
+
+    fn encode(ivalues: Vec<IValue>, outputs: &[IType]) -> MValue {
+        let bytes = ivalues_to_msgpack(ivalues, outputs, &MRecordTypes::new()).unwrap();
+        rmpv::decode::read_value(&mut bytes.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn no_results_are_nil() {
+        assert_eq!(encode(vec![], &[]), MValue::Nil);
+    }
+
+    #[test]
+    fn byte_arrays_are_binaries() {
+        let bytes = vec![0, 1, 255];
+
+        let value = encode(vec![IValue::ByteArray(bytes.clone())], &[IType::ByteArray]);
+        assert_eq!(value, MValue::Binary(bytes.clone()));
+
+        let array = bytes.iter().copied().map(IValue::U8).collect();
+        let value = encode(
+            vec![IValue::Array(array)],
+            &[IType::Array(Box::new(IType::U8))],
+        );
+        assert_eq!(value, MValue::Binary(bytes));
+    }
+
+    #[test]
+    fn multi_values_are_rejected() {
+        let result = ivalues_to_msgpack(
+            vec![IValue::U8(1), IValue::U8(2)],
+            &[IType::U8, IType::U8],
+            &MRecordTypes::new(),
+        );
+        assert!(matches!(result, Err(ITMsgPackSeDeError::Se(_))));
+    }
+
+    #[test]
+    fn incompatible_value_is_rejected() {
+        let result = ivalues_to_msgpack(
+            vec![IValue::String(String::from("1"))],
+            &[IType::U8],
+            &MRecordTypes::new(),
+        );
+        assert!(matches!(result, Err(ITMsgPackSeDeError::Se(_))));
+    }
+}
@@ -0,0 +1,41 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#![warn(rust_2018_idioms)]
+#![deny(
+    dead_code,
+    nonstandard_style,
+    unused_imports,
+    unused_mut,
+    unused_variables,
+    unused_unsafe,
+    unreachable_patterns
+)]
+mod ivalues_to_msgpack;
+mod msgpack_to_ivalues;
+mod errors;
+
+pub type MsgPackResult<T> = Result<T, ITMsgPackSeDeError>;
+pub use errors::ITMsgPackSeDeError;
+pub use ivalues_to_msgpack::ivalues_to_msgpack;
+pub use msgpack_to_ivalues::msgpack_to_ivalues;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub(crate) use wasmer_it::IValue;
+pub(crate) use wasmer_it::IType;
+pub(crate) use wasmer_it::IRecordType;
+pub(crate) type MRecordTypes = HashMap<u64, Arc<IRecordType>>;
@@ -0,0 +1,432 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::IValue;
+use crate::IType;
+use crate::ITMsgPackSeDeError::De;
+use crate::MsgPackResult;
+use crate::MRecordTypes;
+
+use rmpv::Value as MValue;
+use wasmer_it::NEVec;
+
+use std::iter::ExactSizeIterator;
+
+/// Decode MessagePack encoded arguments to an array of ivalues according to the supplied
+/// argument types. Arguments could be passed as an array, as a map by their names,
+/// as a single value for functions with one argument, or as nil for functions without them.
+pub fn msgpack_to_ivalues<'a, 'b>(
+    args: &[u8],
+    arg_types: impl Iterator<Item = (&'a String, &'a IType)> + ExactSizeIterator,
+    record_types: &'b MRecordTypes,
+) -> MsgPackResult<Vec<IValue>> {
+    let mut reader = args;
+    let args = rmpv::decode::read_value(&mut reader).map_err(|e| {
+        De(format!(
+            "error {} occurred while decoding function arguments",
+            e
+        ))
+    })?;
+    if !reader.is_empty() {
+        return Err(De(format!(
+            "{} trailing bytes found after function arguments",
+            reader.len()
+        )));
+    }
+
+    match args {
+        MValue::Map(map) => map_to_ivalues(map, arg_types, record_types),
+        MValue::Array(array) => array_to_ivalues(array, arg_types.map(|arg| arg.1), record_types),
+        MValue::Nil => nil_to_ivalues(arg_types),
+        value => value_to_ivalues(value, arg_types, record_types),
+    }
+}
+
+/// Convert map of named values to an array of ivalues according to the supplied argument types.
+fn map_to_ivalues<'a, 'b>(
+    map: Vec<(MValue, MValue)>,
+    arg_types: impl Iterator<Item = (&'a String, &'a IType)>,
+    record_types: &'b MRecordTypes,
+) -> MsgPackResult<Vec<IValue>> {
+    let mut map = map
+        .into_iter()
+        .map(|(key, value)| match key {
+            MValue::String(key) if key.is_str() => Ok((key.into_str().unwrap(), value)),
+            key => Err(De(format!("expected string key, got {:?}", key))),
+        })
+        .collect::<MsgPackResult<Vec<_>>>()?;
+
+    let mut iargs = Vec::new();
+    for (arg_name, arg_type) in arg_types {
+        let position = map
+            .iter()
+            .position(|(key, _)| key == arg_name)
+            .ok_or_else(|| De(format!("missing argument with name {}", arg_name)))?;
+        let (_, value) = map.swap_remove(position);
+        iargs.push(mvalue_to_ivalue(value, arg_type, record_types)?);
+    }
+
+    if !map.is_empty() {
+        return Err(De(format!(
+            "function requires {} arguments, {} provided",
+            iargs.len(),
+            iargs.len() + map.len()
+        )));
+    }
+
+    Ok(iargs)
+}
+
+/// Convert array of values to an array of ivalues according to the supplied argument types.
+fn array_to_ivalues<'a, 'b>(
+    array: Vec<MValue>,
+    arg_types: impl Iterator<Item = &'a IType> + ExactSizeIterator,
+    record_types: &'b MRecordTypes,
+) -> MsgPackResult<Vec<IValue>> {
+    if array.len() != arg_types.len() {
+        return Err(De(format!(
+            "function requires {} arguments, {} provided",
+            arg_types.len(),
+            array.len()
+        )));
+    }
+
+    array
+        .into_iter()
+        .zip(arg_types)
+        .map(|(value, arg_type)| mvalue_to_ivalue(value, arg_type, record_types))
+        .collect()
+}
+
+/// Convert a single value to an array of ivalues according to the supplied argument types.
+fn value_to_ivalues<'a>(
+    value: MValue,
+    mut arg_types: impl Iterator<Item = (&'a String, &'a IType)> + ExactSizeIterator,
+    record_types: &MRecordTypes,
+) -> MsgPackResult<Vec<IValue>> {
+    if arg_types.len() != 1 {
+        return Err(De(format!(
+            "called function has the following signature: '{:?}', and it isn't suitable for an argument '{:?}' provided",
+            arg_types.collect::<Vec<_>>(),
+            value,
+        )));
+    }
+
+    // unwrap is safe here because iterator size's been checked
+    let arg_type = arg_types.next().unwrap().1;
+    let ivalue = mvalue_to_ivalue(value, arg_type, record_types)?;
+
+    Ok(vec![ivalue])
+}
+
+/// Convert nil to an empty array of ivalues.
+fn nil_to_ivalues<'a>(
+    arg_types: impl Iterator<Item = (&'a String, &'a IType)> + ExactSizeIterator,
+) -> MsgPackResult<Vec<IValue>> {
+    if arg_types.len() != 0 {
+        return Err(De(format!(
+            "the called function has the following signature: {:?}, but no arguments is provided",
+            arg_types.collect::<Vec<_>>()
+        )));
+    }
+
+    Ok(vec![])
+}
+
+/// Convert one value to an ivalue according to the supplied type.
+fn mvalue_to_ivalue(
+    value: MValue,
+    ty: &IType,
+    record_types: &MRecordTypes,
+) -> MsgPackResult<IValue> {
+    macro_rules! to_integer(
+        ($value:expr, $ty:ident) => {
+            match &$value {
+                MValue::Integer(integer) => integer
+                    .as_i64()
+                    .and_then(|value| value.try_into().ok())
+                    .or_else(|| integer.as_u64().and_then(|value| value.try_into().ok()))
+                    .map(IValue::$ty)
+                    .ok_or_else(|| De(format!("integer {} doesn't fit into {:?}", integer, ty))),
+                _ => Err(De(format!("expected {:?}, got {:?}", ty, $value))),
+            }
+        }
+    );
+
+    match ty {
+        IType::Boolean => match value {
+            MValue::Boolean(value) => Ok(IValue::Boolean(value)),
+            value => Err(De(format!("expected boolean, got {:?}", value))),
+        },
+        IType::S8 => to_integer!(value, S8),
+        IType::S16 => to_integer!(value, S16),
+        IType::S32 => to_integer!(value, S32),
+        IType::S64 => to_integer!(value, S64),
+        IType::U8 => to_integer!(value, U8),
+        IType::U16 => to_integer!(value, U16),
+        IType::U32 => to_integer!(value, U32),
+        IType::U64 => to_integer!(value, U64),
+        IType::I32 => to_integer!(value, I32),
+        IType::I64 => to_integer!(value, I64),
+        IType::F32 => match value.as_f64() {
+            Some(value) => Ok(IValue::F32(value as f32)),
+            None => Err(De(format!("expected f32, got {:?}", value))),
+        },
+        IType::F64 => match value.as_f64() {
+            Some(value) => Ok(IValue::F64(value)),
+            None => Err(De(format!("expected f64, got {:?}", value))),
+        },
+        IType::String => match value {
+            MValue::String(value) if value.is_str() => {
+                Ok(IValue::String(value.into_str().unwrap()))
+            }
+            value => Err(De(format!("expected string, got {:?}", value))),
+        },
+        IType::ByteArray => match value {
+            MValue::Binary(value) => Ok(IValue::ByteArray(value)),
+            MValue::Array(array) => {
+                let ivalues = array
+                    .into_iter()
+                    .map(|value| mvalue_to_ivalue(value, &IType::U8, record_types))
+                    .collect::<MsgPackResult<Vec<_>>>()?;
+
+                Ok(IValue::Array(ivalues))
+            }
+            value => Err(De(format!("expected bytearray, got {:?}", value))),
+        },
+        IType::Array(value_type) => match value {
+            MValue::Binary(value) if **value_type == IType::U8 => {
+                Ok(IValue::Array(value.into_iter().map(IValue::U8).collect()))
+            }
+            MValue::Array(array) => {
+                let ivalues = array
+                    .into_iter()
+                    .map(|value| mvalue_to_ivalue(value, value_type, record_types))
+                    .collect::<MsgPackResult<Vec<_>>>()?;
+
+                Ok(IValue::Array(ivalues))
+            }
+            value => Err(De(format!(
+                "expected array of {:?} types, got {:?}",
+                value_type, value
+            ))),
+        },
+        IType::Record(record_type_id) => {
+            let value = record_to_ivalue(value, *record_type_id, record_types)?;
+            Ok(IValue::Record(value))
+        }
+    }
+}
+
+/// Convert a map or an array of fields to an IValue record type.
+fn record_to_ivalue(
+    value: MValue,
+    record_type_id: u64,
+    record_types: &MRecordTypes,
+) -> MsgPackResult<NEVec<IValue>> {
+    let record_type = record_types.get(&record_type_id).ok_or_else(|| {
+        De(format!(
+            "record with type id `{}` wasn't found",
+            record_type_id
+        ))
+    })?;
+
+    let fields = match value {
+        MValue::Map(map) => map_to_ivalues(
+            map,
+            record_type
+                .fields
+                .iter()
+                .map(|field| (&field.name, &field.ty)),
+            record_types,
+        )?,
+        MValue::Array(array) => array_to_ivalues(
+            array,
+            record_type.fields.iter().map(|field| &field.ty),
+            record_types,
+        )?,
+        _ => {
+            return Err(De(format!(
+                "record with type id `{}` should be encoded as array or map of fields",
+                record_type_id
+            )))
+        }
+    };
+
+    NEVec::new(fields).map_err(|_| {
+        De(format!(
+            "record with type id `{}` has no fields",
+            record_type_id
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::msgpack_to_ivalues;
+    use crate::ivalues_to_msgpack;
+    use crate::IValue;
+    use crate::IType;
+    use crate::IRecordType;
+    use crate::ITMsgPackSeDeError;
+    use crate::MRecordTypes;
+    use crate::MsgPackResult;
+
+    use rmpv::Value as MValue;
+    use wasmer_it::IRecordFieldType;
+    use wasmer_it::NEVec;
+
+    use std::sync::Arc;
+
+    const RECORD_ID: u64 = 1;
+
+    fn record_types() -> MRecordTypes {
+        let fields = vec![
+            IRecordFieldType {
+                name: String::from("name"),
+                ty: IType::String,
+            },
+            IRecordFieldType {
+                name: String::from("count"),
+                ty: IType::U64,
+            },
+        ];
+        let record_type = IRecordType {
+            name: String::from("Item"),
+            fields: NEVec::new(fields).unwrap(),
+        };
+
+        MRecordTypes::from([(RECORD_ID, Arc::new(record_type))])
+    }
+
+    fn write(value: &MValue) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        rmpv::encode::write_value(&mut bytes, value).unwrap();
+        bytes
+    }
+
+    fn decode_args(args: &MValue, ty: &IType) -> MsgPackResult<Vec<IValue>> {
+        let name = String::from("arg");
+        msgpack_to_ivalues(&write(args), [(&name, ty)].into_iter(), &record_types())
+    }
+
+    /// Decodes the value passed as the only argument in an array of arguments.
+    fn decode(value: &MValue, ty: &IType) -> MsgPackResult<IValue> {
+        let mut ivalues = decode_args(&MValue::Array(vec![value.clone()]), ty)?;
+        assert_eq!(ivalues.len(), 1);
+        Ok(ivalues.remove(0))
+    }
+
+    /// Encodes the value as a function result and passes it back as an argument.
+    fn round_trip(ivalue: IValue, ty: IType) -> IValue {
+        let bytes = ivalues_to_msgpack(vec![ivalue], &[ty.clone()], &record_types()).unwrap();
+        let value = rmpv::decode::read_value(&mut bytes.as_slice()).unwrap();
+        decode(&value, &ty).unwrap()
+    }
+
+    #[test]
+    fn integer_bounds() {
+        for value in [u64::MIN, u64::MAX] {
+            assert_eq!(
+                round_trip(IValue::U64(value), IType::U64),
+                IValue::U64(value)
+            );
+        }
+        for value in [i64::MIN, i64::MAX] {
+            assert_eq!(
+                round_trip(IValue::S64(value), IType::S64),
+                IValue::S64(value)
+            );
+        }
+
+        let result = decode(&MValue::from(-1), &IType::U64);
+        assert!(matches!(result, Err(ITMsgPackSeDeError::De(_))));
+        let result = decode(&MValue::from(u64::MAX), &IType::S64);
+        assert!(matches!(result, Err(ITMsgPackSeDeError::De(_))));
+        let result = decode(&MValue::from(256), &IType::U8);
+        assert!(matches!(result, Err(ITMsgPackSeDeError::De(_))));
+    }
+
+    #[test]
+    fn binary_and_byte_arrays() {
+        let bytes = vec![0u8, 1, 255];
+        let u8_array = IType::Array(Box::new(IType::U8));
+        let array = IValue::Array(bytes.iter().copied().map(IValue::U8).collect());
+
+        assert_eq!(round_trip(array.clone(), u8_array.clone()), array);
+        assert_eq!(
+            round_trip(IValue::ByteArray(bytes.clone()), IType::ByteArray),
+            IValue::ByteArray(bytes.clone())
+        );
+
+        // binaries and arrays of numbers are accepted for both types
+        let binary = MValue::Binary(bytes.clone());
+        assert_eq!(decode(&binary, &u8_array).unwrap(), array);
+        let numbers = MValue::Array(bytes.iter().copied().map(MValue::from).collect());
+        let ivalue = decode(&numbers, &IType::ByteArray).unwrap();
+        assert_eq!(
+            ivalue,
+            IValue::Array(bytes.into_iter().map(IValue::U8).collect())
+        );
+    }
+
+    #[test]
+    fn records_as_maps_and_arrays() {
+        let fields = vec![IValue::String(String::from("item")), IValue::U64(u64::MAX)];
+        let record = IValue::Record(NEVec::new(fields).unwrap());
+        let ty = IType::Record(RECORD_ID);
+
+        assert_eq!(round_trip(record.clone(), ty.clone()), record);
+
+        // fields could be passed in any order by their names
+        let map = MValue::Map(vec![
+            (MValue::from("count"), MValue::from(u64::MAX)),
+            (MValue::from("name"), MValue::from("item")),
+        ]);
+        assert_eq!(decode(&map, &ty).unwrap(), record);
+
+        let array = MValue::Array(vec![MValue::from("item"), MValue::from(u64::MAX)]);
+        assert_eq!(decode(&array, &ty).unwrap(), record);
+
+        let unknown_field = MValue::Map(vec![
+            (MValue::from("name"), MValue::from("item")),
+            (MValue::from("count"), MValue::from(1)),
+            (MValue::from("size"), MValue::from(1)),
+        ]);
+        assert!(decode(&unknown_field, &ty).is_err());
+    }
+
+    #[test]
+    fn trailing_bytes_are_rejected() {
+        let mut bytes = write(&MValue::from(1));
+        bytes.push(0);
+
+        let name = String::from("arg");
+        let result = msgpack_to_ivalues(&bytes, [(&name, &IType::U8)].into_iter(), &record_types());
+        assert!(matches!(result, Err(ITMsgPackSeDeError::De(_))));
+    }
+
+    #[test]
+    fn nil_is_no_arguments() {
+        let bytes = write(&MValue::Nil);
+
+        let ivalues = msgpack_to_ivalues(&bytes, std::iter::empty(), &record_types()).unwrap();
+        assert!(ivalues.is_empty());
+
+        // nil isn't accepted for a function with arguments
+        assert!(decode_args(&MValue::Nil, &IType::String).is_err());
+    }
+}
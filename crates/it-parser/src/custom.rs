@@ -14,10 +14,14 @@
  * limitations under the License.
  */
 
+use crate::ITParserError;
+use crate::ParserResult;
+
 use walrus::CustomSection;
 use walrus::IdsToIndices;
 
 use std::borrow::Cow;
+use std::ops::Range;
 
 pub const IT_SECTION_NAME: &str = "interface-types";
 
@@ -33,3 +37,87 @@ impl CustomSection for ITCustomSection {
         Cow::Borrowed(&self.0)
     }
 }
+
+const WASM_HEADER_SIZE: usize = 8;
+const CUSTOM_SECTION_ID: u8 = 0;
+
+/// A custom section found in raw bytes of a Wasm module.
+pub(crate) struct RawCustomSection<'w> {
+    pub(crate) name: &'w str,
+    pub(crate) data: &'w [u8],
+    /// Range of the whole section including its id and size.
+    pub(crate) range: Range<usize>,
+}
+
+/// Returns all custom sections of a Wasm module without re-encoding it, so the rest of the
+/// module bytes could be used as is, e.g. to compute a signature.
+pub(crate) fn raw_custom_sections(wasm_bytes: &[u8]) -> ParserResult<Vec<RawCustomSection<'_>>> {
+    let corrupted = |reason: &str| ITParserError::CorruptedWasmFile(anyhow::anyhow!("{}", reason));
+
+    if wasm_bytes.len() < WASM_HEADER_SIZE || &wasm_bytes[0..4] != b"\0asm" {
+        return Err(corrupted("Wasm header is absent"));
+    }
+
+    let mut sections = Vec::new();
+    let mut position = WASM_HEADER_SIZE;
+    while position < wasm_bytes.len() {
+        let section_start = position;
+        let section_id = wasm_bytes[position];
+        position += 1;
+
+        let section_size = read_leb_u32(wasm_bytes, &mut position)
+            .ok_or_else(|| corrupted("section size is malformed"))?;
+        let payload_start = position;
+        let section_end = payload_start
+            .checked_add(section_size as usize)
+            .filter(|end| *end <= wasm_bytes.len())
+            .ok_or_else(|| corrupted("section is out of module bounds"))?;
+
+        if section_id == CUSTOM_SECTION_ID {
+            let name_size = read_leb_u32(&wasm_bytes[..section_end], &mut position)
+                .ok_or_else(|| corrupted("custom section name is malformed"))?;
+            let name_end = position
+                .checked_add(name_size as usize)
+                .filter(|end| *end <= section_end)
+                .ok_or_else(|| corrupted("custom section name is out of section bounds"))?;
+            let name = std::str::from_utf8(&wasm_bytes[position..name_end])
+                .map_err(|_| corrupted("custom section name isn't a valid utf-8 string"))?;
+
+            sections.push(RawCustomSection {
+                name,
+                data: &wasm_bytes[name_end..section_end],
+                range: section_start..section_end,
+            });
+        }
+
+        position = section_end;
+    }
+
+    Ok(sections)
+}
+
+pub(crate) fn write_leb_u32(mut value: u32, buffer: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer.push(byte);
+            return;
+        }
+        buffer.push(byte | 0x80);
+    }
+}
+
+fn read_leb_u32(bytes: &[u8], position: &mut usize) -> Option<u32> {
+    let mut result = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = *bytes.get(*position)?;
+        *position += 1;
+        result |= ((byte & 0x7f) as u32).checked_shl(shift)?;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+    }
+
+    None
+}
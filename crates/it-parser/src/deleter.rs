@@ -16,6 +16,8 @@
 
 use super::errors::ITParserError;
 use super::custom::IT_SECTION_NAME;
+use super::custom::raw_custom_sections;
+use crate::ParserResult;
 
 use walrus::ModuleConfig;
 
@@ -59,3 +61,19 @@ pub fn delete_it_section(mut wasm_module: walrus::Module) -> walrus::Module {
 
     wasm_module
 }
+
+/// Delete all custom sections with provided name from raw bytes of a Wasm module,
+/// other sections are left intact.
+pub fn delete_custom_section(wasm_bytes: &[u8], name: &str) -> ParserResult<Vec<u8>> {
+    let mut result = Vec::with_capacity(wasm_bytes.len());
+    let mut position = 0;
+    for section in raw_custom_sections(wasm_bytes)? {
+        if section.name == name {
+            result.extend_from_slice(&wasm_bytes[position..section.range.start]);
+            position = section.range.end;
+        }
+    }
+    result.extend_from_slice(&wasm_bytes[position..]);
+
+    Ok(result)
+}
@@ -58,3 +58,23 @@ pub fn embed_it(mut wasm_module: walrus::Module, interfaces: &Interfaces<'_>) ->
 
     wasm_module
 }
+
+/// Append a custom section with provided name and data to raw bytes of a Wasm module,
+/// other sections are left intact.
+pub fn embed_custom_section(wasm_bytes: &[u8], name: &str, data: &[u8]) -> Vec<u8> {
+    use super::custom::write_leb_u32;
+
+    let mut payload = Vec::with_capacity(name.len() + data.len() + 5);
+    write_leb_u32(name.len() as u32, &mut payload);
+    payload.extend_from_slice(name.as_bytes());
+    payload.extend_from_slice(data);
+
+    let mut result = Vec::with_capacity(wasm_bytes.len() + payload.len() + 6);
+    result.extend_from_slice(wasm_bytes);
+    // custom section id
+    result.push(0);
+    write_leb_u32(payload.len() as u32, &mut result);
+    result.extend_from_slice(&payload);
+
+    result
+}
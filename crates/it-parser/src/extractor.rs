@@ -18,11 +18,14 @@ mod it;
 
 pub use it::*;
 
+use crate::custom::IT_SECTION_NAME;
 use crate::interface::ModuleInterface;
 use crate::it_interface::IModuleInterface;
 use crate::ParserResult;
 use crate::ITParserError;
 
+use marine_module_interface::diff::ModuleApi;
+use marine_module_interface::interface::InterfaceResult;
 use marine_module_interface::it_interface;
 use marine_module_interface::interface;
 use marine_it_interfaces::MITInterfaces;
@@ -42,6 +45,59 @@ where
     create_mit_with(module_path, |it| it_interface::get_interface(&it))
 }
 
+/// Returns exported interface of the module together with functions it imports.
+pub fn module_api<P>(module_path: P) -> ParserResult<ModuleApi>
+where
+    P: AsRef<Path>,
+{
+    create_mit_with(module_path, |it| -> InterfaceResult<_> {
+        Ok(ModuleApi {
+            interface: interface::get_interface(&it)?,
+            imports: interface::get_imports(&it)?,
+        })
+    })
+}
+
+/// Returns data of all custom sections with provided name from raw bytes of a Wasm module.
+pub fn extract_custom_sections<'w>(
+    wasm_bytes: &'w [u8],
+    name: &str,
+) -> ParserResult<Vec<&'w [u8]>> {
+    let sections = crate::custom::raw_custom_sections(wasm_bytes)?
+        .into_iter()
+        .filter(|section| section.name == name)
+        .map(|section| section.data)
+        .collect();
+
+    Ok(sections)
+}
+
+/// Returns names of modules the module imports functions from through interface types,
+/// in order of their first appearance. Only the IT section is parsed.
+pub fn extract_import_namespaces(wasm_bytes: &[u8]) -> ParserResult<Vec<String>> {
+    let sections = extract_custom_sections(wasm_bytes, IT_SECTION_NAME)?;
+    let it_section = match sections.as_slice() {
+        [] => return Err(ITParserError::NoITSection),
+        [section] => *section,
+        _ => return Err(ITParserError::MultipleITSections),
+    };
+
+    let it = extract_it_from_bytes(it_section)?;
+    let mit = MITInterfaces::new(it);
+
+    let mut namespaces = Vec::<String>::new();
+    for import in it_interface::get_import_funcs(&mit)? {
+        if !namespaces
+            .iter()
+            .any(|namespace| namespace == import.namespace.as_str())
+        {
+            namespaces.push(import.namespace.to_string());
+        }
+    }
+
+    Ok(namespaces)
+}
+
 fn create_mit_with<P, T, E>(
     module_path: P,
     transformer: impl FnOnce(MITInterfaces<'_>) -> std::result::Result<T, E>,
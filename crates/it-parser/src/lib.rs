@@ -32,15 +32,20 @@ mod extractor;
 
 pub use errors::ITParserError;
 
+pub use deleter::delete_custom_section;
 pub use deleter::delete_it_section;
 pub use deleter::delete_it_section_from_file;
 
+pub use embedder::embed_custom_section;
 pub use embedder::embed_it;
 pub use embedder::embed_text_it;
 
+pub use extractor::extract_custom_sections;
+pub use extractor::extract_import_namespaces;
 pub use extractor::extract_it_from_module;
 pub use extractor::extract_version_from_module;
 pub use extractor::extract_text_it;
+pub use extractor::module_api;
 pub use extractor::module_interface;
 pub use extractor::module_it_interface;
 
@@ -49,6 +54,7 @@ pub mod interface {
     pub use marine_module_interface::interface::RecordType;
     pub use marine_module_interface::interface::RecordField;
     pub use marine_module_interface::interface::FunctionSignature;
+    pub use marine_module_interface::interface::ImportFunctionSignature;
 }
 
 pub mod it_interface {
@@ -191,4 +191,21 @@ impl Instance<JsWasmBackend> for JsInstance {
             }),
         }
     }
+
+    fn mutable_globals(
+        &self,
+        _store: &mut impl AsContextMut<JsWasmBackend>,
+    ) -> Option<Vec<(String, WValue)>> {
+        // globals are not tracked by this backend
+        None
+    }
+
+    fn set_global(
+        &self,
+        _store: &mut impl AsContextMut<JsWasmBackend>,
+        name: &str,
+        _value: WValue,
+    ) -> ResolveResult<()> {
+        Err(ResolveError::ExportNotFound(name.to_string()))
+    }
 }
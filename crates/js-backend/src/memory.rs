@@ -19,6 +19,7 @@ use crate::JsWasmBackend;
 use it_memory_traits::MemoryAccessError;
 use marine_wasm_backend_traits::prelude::*;
 
+use anyhow::anyhow;
 use js_sys::WebAssembly;
 use wasm_bindgen::JsCast;
 
@@ -56,6 +57,28 @@ impl Memory<JsWasmBackend> for JsMemory {
     fn size(&self, _store: &mut <JsWasmBackend as WasmBackend>::ContextMut<'_>) -> usize {
         self.array_buffer().byte_length() as usize
     }
+
+    fn grow(
+        &self,
+        _store: &mut <JsWasmBackend as WasmBackend>::ContextMut<'_>,
+        delta_pages: u32,
+    ) -> RuntimeResult<()> {
+        // `WebAssembly.Memory.grow` throws a RangeError if the memory can't be grown,
+        // js_sys binding doesn't catch it, so the method is called through reflection
+        let grow = js_sys::Reflect::get(self.inner.as_ref(), &"grow".into())
+            .map_err(|e| RuntimeError::Other(anyhow!("can't get Memory.grow: {:?}", e)))?
+            .unchecked_into::<js_sys::Function>();
+
+        grow.call1(self.inner.as_ref(), &delta_pages.into())
+            .map(|_| ())
+            .map_err(|e| {
+                RuntimeError::Other(anyhow!(
+                    "failed to grow memory by {} pages: {:?}",
+                    delta_pages,
+                    e
+                ))
+            })
+    }
 }
 
 impl it_memory_traits::Memory<JsMemory, DelayedContextLifetime<JsWasmBackend>> for JsMemory {
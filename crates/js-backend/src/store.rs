@@ -135,11 +135,27 @@ impl Store<JsWasmBackend> for JsStore {
     }
 
     fn clear_allocation_stats(&mut self) {}
+
+    fn set_fuel(&mut self, _fuel: Option<u64>) -> RuntimeResult<()> {
+        Err(RuntimeError::FuelNotSupported)
+    }
+
+    fn consumed_fuel(&self) -> Option<u64> {
+        None
+    }
 }
 
 impl<'c> Context<JsWasmBackend> for JsContext<'c> {}
 
-impl<'c> ContextMut<JsWasmBackend> for JsContextMut<'c> {}
+impl<'c> ContextMut<JsWasmBackend> for JsContextMut<'c> {
+    fn set_module_memory_limit(
+        &mut self,
+        _limit: Option<ModuleMemoryLimit>,
+    ) -> Option<ModuleMemoryLimit> {
+        // memory limits are not supported by JS backend
+        None
+    }
+}
 
 impl AsContext<JsWasmBackend> for JsStore {
     fn as_context(&self) -> <JsWasmBackend as WasmBackend>::Context<'_> {
@@ -33,6 +33,24 @@ impl WasiImplementation<JsWasmBackend> for JsWasi {
         linker: &mut <JsWasmBackend as WasmBackend>::Imports,
         config: WasiParameters,
     ) -> Result<(), WasiError> {
+        // the JS WASI implementation takes the time and randomness from the host
+        if config.deterministic_env.is_some() {
+            return Err(WasiError::NondeterministicBackend);
+        }
+
+        // the JS WASI implementation has its own in-memory file system without mapped directories
+        if !config.memory_dirs.is_empty() {
+            return Err(WasiError::UnsupportedParameter("memory directories"));
+        }
+        if !config.dir_policies.is_empty() {
+            return Err(WasiError::UnsupportedParameter("directory policies"));
+        }
+
+        // stdout and stderr are written to the file system of the JS WASI implementation
+        if config.output_capture.is_some() {
+            return Err(WasiError::UnsupportedParameter("output capture"));
+        }
+
         let context_index = store
             .inner
             .store_wasi_context(WasiContext::new(config.envs)?);
@@ -42,6 +60,7 @@ impl WasiImplementation<JsWasmBackend> for JsWasi {
     }
 
     fn get_wasi_state<'s>(
+        _store: &impl AsContext<JsWasmBackend>,
         _instance: &'s mut <JsWasmBackend as WasmBackend>::Instance,
     ) -> Box<dyn WasiState + 's> {
         Box::new(JsWasiState {})
@@ -54,6 +73,18 @@ impl WasiState for JsWasiState {
     fn envs(&self) -> &[Vec<u8>] {
         &[]
     }
+
+    fn args(&self) -> &[String] {
+        &[]
+    }
+
+    fn preopened_dirs(&self) -> &[PreopenedDir] {
+        &[]
+    }
+
+    fn open_fds(&self) -> &[OpenFd] {
+        &[]
+    }
 }
 
 pub(crate) struct WasiContext {
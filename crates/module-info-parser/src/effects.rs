@@ -75,6 +75,7 @@ fn inspect_import(module: &str, name: &str) -> Option<WasmEffect> {
     }
 }
 
-fn is_host_import(namespace: &str) -> bool {
+/// Returns true if functions from the namespace are provided by the host.
+pub fn is_host_import(namespace: &str) -> bool {
     namespace == HOST_IMPORT_NAMESPACE_V0 || namespace.starts_with(HOST_IMPORT_NAMESPACE_PREFIX)
 }
@@ -27,6 +27,7 @@
 pub mod manifest;
 pub mod sdk_version;
 pub mod effects;
+pub mod signature;
 mod custom_section_extractor;
 mod errors;
 
@@ -0,0 +1,234 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_it_parser::delete_custom_section;
+use marine_it_parser::embed_custom_section;
+use marine_it_parser::extract_custom_sections;
+use marine_it_parser::ITParserError;
+
+use ed25519_dalek::Signature;
+use ed25519_dalek::Signer;
+use ed25519_dalek::SignatureError as Ed25519Error;
+use ed25519_dalek::PUBLIC_KEY_LENGTH;
+use ed25519_dalek::SECRET_KEY_LENGTH;
+use ed25519_dalek::SIGNATURE_LENGTH;
+use thiserror::Error as ThisError;
+
+pub use ed25519_dalek::SigningKey;
+pub use ed25519_dalek::VerifyingKey;
+
+/// Name of the custom section containing a signer public key followed by an ed25519 signature.
+pub const SIGNATURE_SECTION_NAME: &str = "marine-signature";
+
+const SIGNATURE_SECTION_SIZE: usize = PUBLIC_KEY_LENGTH + SIGNATURE_LENGTH;
+
+#[derive(Debug, ThisError)]
+pub enum SignatureError {
+    /// Signature section is absent.
+    #[error("the module isn't signed")]
+    Unsigned,
+
+    /// Multiple signature sections.
+    #[error("the module contains {0} signature sections, but only one is allowed")]
+    MultipleSignatures(usize),
+
+    /// Signature section has an unexpected size.
+    #[error("signature section is malformed: it should contain {SIGNATURE_SECTION_SIZE} bytes, but contains {0}")]
+    MalformedSection(usize),
+
+    /// Public key from signature section isn't a valid ed25519 key.
+    #[error("signer public key is invalid: {0}")]
+    InvalidPublicKey(Ed25519Error),
+
+    /// Signature doesn't match the module bytes.
+    #[error("signature doesn't match the module content: {0}")]
+    InvalidSignature(Ed25519Error),
+
+    /// The module is signed with a key that isn't trusted.
+    #[error("the module is signed with untrusted key {0}")]
+    UntrustedKey(String),
+
+    /// A key isn't a valid hex-encoded ed25519 key.
+    #[error("'{0}' isn't a valid hex-encoded ed25519 key")]
+    InvalidKeyEncoding(String),
+
+    /// An error occurred while parsing Wasm file.
+    #[error(transparent)]
+    CorruptedWasmFile(#[from] ITParserError),
+}
+
+/// Signs the module with provided key, replacing its previous signature if any.
+/// The signature covers all the module bytes except the signature section itself,
+/// including the manifest and the version sections.
+pub fn sign(wasm_bytes: &[u8], signing_key: &SigningKey) -> Result<Vec<u8>, SignatureError> {
+    let unsigned_bytes = delete_custom_section(wasm_bytes, SIGNATURE_SECTION_NAME)?;
+    let signature = signing_key.sign(&unsigned_bytes);
+
+    let mut section = Vec::with_capacity(SIGNATURE_SECTION_SIZE);
+    section.extend_from_slice(signing_key.verifying_key().as_bytes());
+    section.extend_from_slice(&signature.to_bytes());
+
+    Ok(embed_custom_section(
+        &unsigned_bytes,
+        SIGNATURE_SECTION_NAME,
+        &section,
+    ))
+}
+
+/// Verifies the module signature and returns the key the module was signed with.
+pub fn verify(wasm_bytes: &[u8]) -> Result<VerifyingKey, SignatureError> {
+    let sections = extract_custom_sections(wasm_bytes, SIGNATURE_SECTION_NAME)?;
+    let section = match sections.as_slice() {
+        [] => return Err(SignatureError::Unsigned),
+        [section] => *section,
+        sections => return Err(SignatureError::MultipleSignatures(sections.len())),
+    };
+
+    if section.len() != SIGNATURE_SECTION_SIZE {
+        return Err(SignatureError::MalformedSection(section.len()));
+    }
+
+    let (key_bytes, signature_bytes) = section.split_at(PUBLIC_KEY_LENGTH);
+    // sizes are checked above, so these conversions can't fail
+    let key_bytes: &[u8; PUBLIC_KEY_LENGTH] = key_bytes.try_into().unwrap();
+    let signature_bytes: &[u8; SIGNATURE_LENGTH] = signature_bytes.try_into().unwrap();
+
+    let verifying_key =
+        VerifyingKey::from_bytes(key_bytes).map_err(SignatureError::InvalidPublicKey)?;
+    let signature = Signature::from_bytes(signature_bytes);
+
+    let unsigned_bytes = delete_custom_section(wasm_bytes, SIGNATURE_SECTION_NAME)?;
+    verifying_key
+        .verify_strict(&unsigned_bytes, &signature)
+        .map_err(SignatureError::InvalidSignature)?;
+
+    Ok(verifying_key)
+}
+
+/// Verifies the module signature and checks that the module is signed with one of the trusted keys.
+pub fn verify_trusted(
+    wasm_bytes: &[u8],
+    trusted_keys: &[VerifyingKey],
+) -> Result<VerifyingKey, SignatureError> {
+    let verifying_key = verify(wasm_bytes)?;
+    if !trusted_keys.contains(&verifying_key) {
+        return Err(SignatureError::UntrustedKey(key_to_hex(&verifying_key)));
+    }
+
+    Ok(verifying_key)
+}
+
+/// Parses a hex-encoded ed25519 public key.
+pub fn parse_verifying_key(key: &str) -> Result<VerifyingKey, SignatureError> {
+    let key_bytes = decode_key::<PUBLIC_KEY_LENGTH>(key)?;
+    VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|_| SignatureError::InvalidKeyEncoding(key.to_string()))
+}
+
+/// Parses a hex-encoded ed25519 secret key.
+pub fn parse_signing_key(key: &str) -> Result<SigningKey, SignatureError> {
+    // secret key isn't included into the error to not leak it into logs
+    let key_bytes = decode_key::<SECRET_KEY_LENGTH>(key)
+        .map_err(|_| SignatureError::InvalidKeyEncoding(String::from("<secret key>")))?;
+    Ok(SigningKey::from_bytes(&key_bytes))
+}
+
+/// Returns a hex representation of a public key.
+pub fn key_to_hex(key: &VerifyingKey) -> String {
+    hex::encode(key.as_bytes())
+}
+
+fn decode_key<const N: usize>(key: &str) -> Result<[u8; N], SignatureError> {
+    let mut key_bytes = [0u8; N];
+    hex::decode_to_slice(key.trim(), &mut key_bytes)
+        .map_err(|_| SignatureError::InvalidKeyEncoding(key.to_string()))?;
+
+    Ok(key_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use marine_rs_sdk_main::MANIFEST_SECTION_NAME;
+
+    // the smallest valid Wasm module: magic number and version
+    const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; SECRET_KEY_LENGTH])
+    }
+
+    fn module_with_manifest(manifest: &[u8]) -> Vec<u8> {
+        embed_custom_section(EMPTY_MODULE, MANIFEST_SECTION_NAME, manifest)
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let key = signing_key(1);
+        let signed = sign(&module_with_manifest(b"manifest"), &key).unwrap();
+
+        let signer = verify(&signed).unwrap();
+        assert_eq!(signer, key.verifying_key());
+
+        let resigned = sign(&signed, &signing_key(2)).unwrap();
+        assert_eq!(resigned.len(), signed.len());
+        assert_eq!(verify(&resigned).unwrap(), signing_key(2).verifying_key());
+    }
+
+    #[test]
+    fn unsigned_module() {
+        let result = verify(EMPTY_MODULE);
+        assert!(matches!(result, Err(SignatureError::Unsigned)));
+    }
+
+    #[test]
+    fn tampered_manifest() {
+        let signed = sign(&module_with_manifest(b"manifest"), &signing_key(1)).unwrap();
+        let position = signed
+            .windows(b"manifest".len())
+            .position(|window| window == b"manifest")
+            .unwrap();
+
+        let mut tampered = signed.clone();
+        tampered[position] = b'M';
+
+        let result = verify(&tampered);
+        assert!(matches!(result, Err(SignatureError::InvalidSignature(_))));
+    }
+
+    #[test]
+    fn untrusted_key() {
+        let signed = sign(EMPTY_MODULE, &signing_key(1)).unwrap();
+        let trusted = [signing_key(2).verifying_key()];
+
+        let result = verify_trusted(&signed, &trusted);
+        assert!(matches!(result, Err(SignatureError::UntrustedKey(_))));
+
+        let trusted = [signing_key(1).verifying_key()];
+        assert!(verify_trusted(&signed, &trusted).is_ok());
+    }
+
+    #[test]
+    fn key_encoding() {
+        let key = signing_key(3).verifying_key();
+        let parsed = parse_verifying_key(&key_to_hex(&key)).unwrap();
+        assert_eq!(parsed, key);
+
+        let result = parse_verifying_key("not a key");
+        assert!(matches!(result, Err(SignatureError::InvalidKeyEncoding(_))));
+    }
+}
@@ -0,0 +1,708 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Detection of changes between two versions of a module or a service,
+//! each change is classified as compatible or breaking for the module callers.
+
+use crate::interface::FunctionSignature;
+use crate::interface::ImportFunctionSignature;
+use crate::interface::ModuleInterface;
+use crate::interface::RecordField;
+
+use serde::Serialize;
+use serde::Deserialize;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Everything a module provides to its callers and requires from other modules.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleApi {
+    pub interface: ModuleInterface,
+    pub imports: Vec<ImportFunctionSignature>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compatibility {
+    /// Existing callers and dependencies keep working.
+    Compatible,
+    /// Existing callers or dependencies could stop working.
+    Breaking,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    FunctionAdded {
+        function: FunctionSignature,
+    },
+    FunctionRemoved {
+        function: FunctionSignature,
+    },
+    FunctionSignatureChanged {
+        old: FunctionSignature,
+        new: FunctionSignature,
+    },
+    RecordAdded {
+        record: String,
+    },
+    RecordRemoved {
+        record: String,
+    },
+    RecordFieldsChanged {
+        record: String,
+        old_fields: Vec<RecordField>,
+        new_fields: Vec<RecordField>,
+    },
+    ImportAdded {
+        import: ImportFunctionSignature,
+    },
+    ImportRemoved {
+        import: ImportFunctionSignature,
+    },
+    ImportSignatureChanged {
+        old: ImportFunctionSignature,
+        new: ImportFunctionSignature,
+    },
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct ClassifiedChange {
+    /// A module the change relates to, it's set only for service diffs.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub module: Option<String>,
+    pub compatibility: Compatibility,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InterfaceDiff {
+    pub changes: Vec<ClassifiedChange>,
+}
+
+impl Change {
+    pub fn compatibility(&self) -> Compatibility {
+        match self {
+            Change::FunctionAdded { .. }
+            | Change::RecordAdded { .. }
+            | Change::ImportRemoved { .. } => Compatibility::Compatible,
+            Change::FunctionRemoved { .. }
+            | Change::FunctionSignatureChanged { .. }
+            | Change::RecordRemoved { .. }
+            | Change::RecordFieldsChanged { .. }
+            | Change::ImportAdded { .. }
+            | Change::ImportSignatureChanged { .. } => Compatibility::Breaking,
+        }
+    }
+}
+
+impl InterfaceDiff {
+    /// Returns true if at least one change could break existing callers or dependencies.
+    pub fn is_breaking(&self) -> bool {
+        self.breaking_changes().next().is_some()
+    }
+
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &ClassifiedChange> {
+        self.changes
+            .iter()
+            .filter(|change| change.compatibility == Compatibility::Breaking)
+    }
+
+    fn push(&mut self, module: Option<&str>, change: Change) {
+        self.changes.push(ClassifiedChange {
+            module: module.map(ToString::to_string),
+            compatibility: change.compatibility(),
+            change,
+        });
+    }
+}
+
+/// Compares two versions of a module: its exported functions, records and IT imports.
+pub fn diff_modules(old: &ModuleApi, new: &ModuleApi) -> InterfaceDiff {
+    let mut diff = InterfaceDiff::default();
+    diff_interfaces(&mut diff, None, &old.interface, &new.interface);
+    diff_imports(&mut diff, None, old.imports.iter(), new.imports.iter());
+
+    diff
+}
+
+/// Compares two versions of a service described by its modules, the last one is a facade.
+/// Only the facade interface, exports of other modules that are still imported inside
+/// the service, and imports of functions from other modules of the same service are compared.
+pub fn diff_services(old: &[(String, ModuleApi)], new: &[(String, ModuleApi)]) -> InterfaceDiff {
+    let mut diff = InterfaceDiff::default();
+
+    if let (Some((_, old_facade)), Some((facade_name, new_facade))) = (old.last(), new.last()) {
+        diff_interfaces(
+            &mut diff,
+            Some(facade_name),
+            &old_facade.interface,
+            &new_facade.interface,
+        );
+    }
+
+    diff_internal_exports(&mut diff, old, new);
+
+    let module_names = old
+        .iter()
+        .chain(new.iter())
+        .map(|(name, _)| name.as_str())
+        .collect::<BTreeSet<_>>();
+
+    for module_name in module_names {
+        diff_imports(
+            &mut diff,
+            Some(module_name),
+            cross_module_imports(old, module_name),
+            cross_module_imports(new, module_name),
+        );
+    }
+
+    diff
+}
+
+/// Compares exports of non-facade modules, they are used only by other modules of the service,
+/// so a change breaks it only if the export is still imported by the new version.
+fn diff_internal_exports(
+    diff: &mut InterfaceDiff,
+    old: &[(String, ModuleApi)],
+    new: &[(String, ModuleApi)],
+) {
+    let old_internal = old.split_last().map_or(&[][..], |(_, internal)| internal);
+
+    for (module_name, old_api) in old_internal {
+        let new_functions = new
+            .iter()
+            .find(|(name, _)| name == module_name)
+            .map(|(_, api)| by_name(api.interface.function_signatures.iter(), |f| f.name.clone()))
+            .unwrap_or_default();
+
+        for old_function in old_api.interface.function_signatures.iter() {
+            let still_imported = new
+                .iter()
+                .flat_map(|(_, api)| api.imports.iter())
+                .any(|import| {
+                    &import.namespace == module_name && import.signature.name == old_function.name
+                });
+            if !still_imported {
+                continue;
+            }
+
+            match new_functions.get(&old_function.name) {
+                None => diff.push(
+                    Some(module_name),
+                    Change::FunctionRemoved {
+                        function: old_function.clone(),
+                    },
+                ),
+                Some(new_function) if *new_function != old_function => diff.push(
+                    Some(module_name),
+                    Change::FunctionSignatureChanged {
+                        old: old_function.clone(),
+                        new: (*new_function).clone(),
+                    },
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+/// Returns imports of the module from other modules of the service.
+fn cross_module_imports<'m>(
+    modules: &'m [(String, ModuleApi)],
+    module_name: &str,
+) -> impl Iterator<Item = &'m ImportFunctionSignature> {
+    let imports = modules
+        .iter()
+        .find(|(name, _)| name == module_name)
+        .map(|(_, api)| api.imports.as_slice())
+        .unwrap_or_default();
+
+    imports
+        .iter()
+        .filter(|import| modules.iter().any(|(name, _)| name == &import.namespace))
+}
+
+fn diff_interfaces(
+    diff: &mut InterfaceDiff,
+    module: Option<&str>,
+    old: &ModuleInterface,
+    new: &ModuleInterface,
+) {
+    let old_functions = by_name(old.function_signatures.iter(), |f| f.name.clone());
+    let new_functions = by_name(new.function_signatures.iter(), |f| f.name.clone());
+
+    for (name, old_function) in old_functions.iter() {
+        match new_functions.get(name) {
+            None => diff.push(
+                module,
+                Change::FunctionRemoved {
+                    function: (*old_function).clone(),
+                },
+            ),
+            Some(new_function) if old_function != new_function => diff.push(
+                module,
+                Change::FunctionSignatureChanged {
+                    old: (*old_function).clone(),
+                    new: (*new_function).clone(),
+                },
+            ),
+            Some(_) => {}
+        }
+    }
+
+    for (name, new_function) in new_functions.iter() {
+        if !old_functions.contains_key(name) {
+            diff.push(
+                module,
+                Change::FunctionAdded {
+                    function: (*new_function).clone(),
+                },
+            );
+        }
+    }
+
+    let old_records = by_name(old.record_types.iter(), |r| r.name.clone());
+    let new_records = by_name(new.record_types.iter(), |r| r.name.clone());
+
+    for (name, old_record) in old_records.iter() {
+        match new_records.get(name) {
+            None => diff.push(
+                module,
+                Change::RecordRemoved {
+                    record: name.clone(),
+                },
+            ),
+            // fields are passed by their position, so any change in them is breaking
+            Some(new_record) if old_record.fields != new_record.fields => diff.push(
+                module,
+                Change::RecordFieldsChanged {
+                    record: name.clone(),
+                    old_fields: old_record.fields.clone(),
+                    new_fields: new_record.fields.clone(),
+                },
+            ),
+            Some(_) => {}
+        }
+    }
+
+    for name in new_records.keys() {
+        if !old_records.contains_key(name) {
+            diff.push(
+                module,
+                Change::RecordAdded {
+                    record: name.clone(),
+                },
+            );
+        }
+    }
+}
+
+fn diff_imports<'i>(
+    diff: &mut InterfaceDiff,
+    module: Option<&str>,
+    old: impl Iterator<Item = &'i ImportFunctionSignature>,
+    new: impl Iterator<Item = &'i ImportFunctionSignature>,
+) {
+    let import_key = |import: &ImportFunctionSignature| {
+        (import.namespace.clone(), import.signature.name.clone())
+    };
+    let old_imports = by_name(old, import_key);
+    let new_imports = by_name(new, import_key);
+
+    for (key, old_import) in old_imports.iter() {
+        match new_imports.get(key) {
+            None => diff.push(
+                module,
+                Change::ImportRemoved {
+                    import: (*old_import).clone(),
+                },
+            ),
+            Some(new_import) if old_import != new_import => diff.push(
+                module,
+                Change::ImportSignatureChanged {
+                    old: (*old_import).clone(),
+                    new: (*new_import).clone(),
+                },
+            ),
+            Some(_) => {}
+        }
+    }
+
+    for (key, new_import) in new_imports.iter() {
+        if !old_imports.contains_key(key) {
+            diff.push(
+                module,
+                Change::ImportAdded {
+                    import: (*new_import).clone(),
+                },
+            );
+        }
+    }
+}
+
+fn by_name<'i, T: 'i, K: Ord>(
+    items: impl Iterator<Item = &'i T>,
+    key: impl Fn(&T) -> K,
+) -> BTreeMap<K, &'i T> {
+    items.map(|item| (key(item), item)).collect()
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compatibility::Compatible => write!(f, "compatible"),
+            Compatibility::Breaking => write!(f, "breaking"),
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // signatures are displayed with a trailing new line
+        let signature = |signature: &dyn fmt::Display| signature.to_string().trim_end().to_string();
+
+        match self {
+            Change::FunctionAdded { function } => {
+                write!(f, "function added: {}", signature(function))
+            }
+            Change::FunctionRemoved { function } => {
+                write!(f, "function removed: {}", signature(function))
+            }
+            Change::FunctionSignatureChanged { old, new } => write!(
+                f,
+                "function signature changed: {} => {}",
+                signature(old),
+                signature(new)
+            ),
+            Change::RecordAdded { record } => write!(f, "record added: {}", record),
+            Change::RecordRemoved { record } => write!(f, "record removed: {}", record),
+            Change::RecordFieldsChanged {
+                record,
+                old_fields,
+                new_fields,
+            } => {
+                use itertools::Itertools;
+
+                let fields = |fields: &[RecordField]| {
+                    fields
+                        .iter()
+                        .map(|field| format!("{}: {}", field.name, field.ty))
+                        .join(", ")
+                };
+
+                write!(
+                    f,
+                    "record fields changed: {} {{ {} }} => {{ {} }}",
+                    record,
+                    fields(old_fields),
+                    fields(new_fields)
+                )
+            }
+            Change::ImportAdded { import } => write!(f, "import added: {}", signature(import)),
+            Change::ImportRemoved { import } => {
+                write!(f, "import removed: {}", signature(import))
+            }
+            Change::ImportSignatureChanged { old, new } => write!(
+                f,
+                "import signature changed: {} => {}",
+                signature(old),
+                signature(new)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ClassifiedChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.module {
+            Some(module) => write!(f, "{}: [{}] {}", self.compatibility, module, self.change),
+            None => write!(f, "{}: {}", self.compatibility, self.change),
+        }
+    }
+}
+
+impl fmt::Display for InterfaceDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.changes.iter() {
+            writeln!(f, "{}", change)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::RecordType;
+
+    fn function(name: &str, arguments: &[(&str, &str)], output: Option<&str>) -> FunctionSignature {
+        FunctionSignature {
+            name: name.to_string(),
+            arguments: arguments
+                .iter()
+                .map(|(name, ty)| (name.to_string(), ty.to_string()))
+                .collect(),
+            output_types: output.into_iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    fn record(name: &str, fields: &[(&str, &str)]) -> RecordType {
+        RecordType {
+            name: name.to_string(),
+            id: 0,
+            fields: fields
+                .iter()
+                .map(|(name, ty)| RecordField {
+                    name: name.to_string(),
+                    ty: ty.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn import(namespace: &str, signature: FunctionSignature) -> ImportFunctionSignature {
+        ImportFunctionSignature {
+            namespace: namespace.to_string(),
+            signature,
+        }
+    }
+
+    fn module(
+        function_signatures: Vec<FunctionSignature>,
+        record_types: Vec<RecordType>,
+        imports: Vec<ImportFunctionSignature>,
+    ) -> ModuleApi {
+        ModuleApi {
+            interface: ModuleInterface {
+                function_signatures,
+                record_types,
+            },
+            imports,
+        }
+    }
+
+    fn kinds(diff: &InterfaceDiff) -> Vec<(Compatibility, &'static str)> {
+        diff.changes
+            .iter()
+            .map(|change| {
+                let kind = match change.change {
+                    Change::FunctionAdded { .. } => "function_added",
+                    Change::FunctionRemoved { .. } => "function_removed",
+                    Change::FunctionSignatureChanged { .. } => "function_signature_changed",
+                    Change::RecordAdded { .. } => "record_added",
+                    Change::RecordRemoved { .. } => "record_removed",
+                    Change::RecordFieldsChanged { .. } => "record_fields_changed",
+                    Change::ImportAdded { .. } => "import_added",
+                    Change::ImportRemoved { .. } => "import_removed",
+                    Change::ImportSignatureChanged { .. } => "import_signature_changed",
+                };
+                (change.compatibility, kind)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_module_has_no_changes() {
+        let api = module(
+            vec![function("greeting", &[("name", "string")], Some("string"))],
+            vec![record("User", &[("name", "string")])],
+            vec![import("host", function("curl", &[("url", "string")], None))],
+        );
+
+        let diff = diff_modules(&api, &api);
+        assert!(diff.changes.is_empty());
+        assert!(!diff.is_breaking());
+    }
+
+    #[test]
+    fn function_changes() {
+        let old = module(
+            vec![
+                function("greeting", &[("name", "string")], Some("string")),
+                function("removed", &[], None),
+                function("renamed_arg", &[("a", "u8")], None),
+            ],
+            vec![],
+            vec![],
+        );
+        let new = module(
+            vec![
+                function("added", &[], None),
+                function("greeting", &[("name", "string")], Some("string")),
+                function("renamed_arg", &[("b", "u8")], None),
+            ],
+            vec![],
+            vec![],
+        );
+
+        let diff = diff_modules(&old, &new);
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (Compatibility::Breaking, "function_removed"),
+                (Compatibility::Breaking, "function_signature_changed"),
+                (Compatibility::Compatible, "function_added"),
+            ]
+        );
+        assert!(diff.is_breaking());
+    }
+
+    #[test]
+    fn record_and_import_changes() {
+        let old = module(
+            vec![],
+            vec![
+                record("Changed", &[("a", "u8"), ("b", "u8")]),
+                record("Removed", &[("a", "u8")]),
+            ],
+            vec![
+                import("effector", function("removed", &[], None)),
+                import("effector", function("changed", &[("a", "u8")], None)),
+            ],
+        );
+        let new = module(
+            vec![],
+            vec![
+                record("Added", &[("a", "u8")]),
+                record("Changed", &[("b", "u8"), ("a", "u8")]),
+            ],
+            vec![
+                import("effector", function("changed", &[("a", "u16")], None)),
+                import("host", function("added", &[], None)),
+            ],
+        );
+
+        let diff = diff_modules(&old, &new);
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (Compatibility::Breaking, "record_fields_changed"),
+                (Compatibility::Breaking, "record_removed"),
+                (Compatibility::Compatible, "record_added"),
+                (Compatibility::Breaking, "import_signature_changed"),
+                (Compatibility::Compatible, "import_removed"),
+                (Compatibility::Breaking, "import_added"),
+            ]
+        );
+    }
+
+    #[test]
+    fn service_compares_facade_and_cross_module_imports() {
+        let effector = module(
+            vec![function("internal", &[], None)],
+            vec![],
+            vec![import("host", function("curl", &[], None))],
+        );
+        let old_facade = module(
+            vec![function("greeting", &[], None)],
+            vec![],
+            vec![import("effector", function("internal", &[], None))],
+        );
+        let new_effector = module(vec![], vec![], vec![]);
+        let new_facade = module(
+            vec![function("greeting", &[], None)],
+            vec![],
+            vec![
+                import("effector", function("internal", &[], None)),
+                import("effector", function("other", &[], None)),
+            ],
+        );
+
+        let old = vec![
+            ("effector".to_string(), effector),
+            ("facade".to_string(), old_facade),
+        ];
+        let new = vec![
+            ("effector".to_string(), new_effector),
+            ("facade".to_string(), new_facade),
+        ];
+
+        let diff = diff_services(&old, &new);
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (Compatibility::Breaking, "function_removed"),
+                (Compatibility::Breaking, "import_added"),
+            ]
+        );
+        assert_eq!(diff.changes[0].module.as_deref(), Some("effector"));
+        assert_eq!(diff.changes[1].module.as_deref(), Some("facade"));
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["changes"][1]["kind"], "import_added");
+        assert_eq!(json["changes"][1]["compatibility"], "breaking");
+        assert_eq!(json["changes"][1]["module"], "facade");
+    }
+
+    #[test]
+    fn service_ignores_exports_no_longer_imported() {
+        let effector = module(
+            vec![
+                function("dropped", &[], None),
+                function("changed", &[("a", "u8")], None),
+            ],
+            vec![],
+            vec![],
+        );
+        let old_facade = module(
+            vec![function("greeting", &[], None)],
+            vec![],
+            vec![
+                import("effector", function("dropped", &[], None)),
+                import("effector", function("changed", &[("a", "u8")], None)),
+            ],
+        );
+        let new_effector = module(
+            vec![function("changed", &[("a", "u16")], None)],
+            vec![],
+            vec![],
+        );
+        let new_facade = module(
+            vec![function("greeting", &[], None)],
+            vec![],
+            vec![import(
+                "effector",
+                function("changed", &[("a", "u8")], None),
+            )],
+        );
+
+        let old = vec![
+            ("effector".to_string(), effector),
+            ("facade".to_string(), old_facade),
+        ];
+        let new = vec![
+            ("effector".to_string(), new_effector),
+            ("facade".to_string(), new_facade),
+        ];
+
+        // the removed export isn't imported anymore, while the changed one still is
+        let diff = diff_services(&old, &new);
+        assert_eq!(
+            kinds(&diff),
+            vec![
+                (Compatibility::Breaking, "function_signature_changed"),
+                (Compatibility::Compatible, "import_removed"),
+            ]
+        );
+        assert_eq!(diff.changes[0].module.as_deref(), Some("effector"));
+    }
+}
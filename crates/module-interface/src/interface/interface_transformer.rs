@@ -22,6 +22,9 @@ use crate::it_interface::IModuleInterface;
 use crate::it_interface::IFunctionSignature;
 use crate::it_interface::IRecordTypes;
 
+use wasmer_it::IType;
+use wasmer_it::ast::FunctionArg as IFunctionArg;
+
 pub fn it_to_module_interface(mm_interface: IModuleInterface) -> InterfaceResult<ModuleInterface> {
     let record_types = mm_interface.export_record_types;
 
@@ -44,23 +47,35 @@ pub fn it_to_module_interface(mm_interface: IModuleInterface) -> InterfaceResult
 fn serialize_function_signature(
     signature: IFunctionSignature,
     record_types: &IRecordTypes,
+) -> FunctionSignature {
+    serialize_function_type(
+        &signature.name,
+        &signature.arguments,
+        &signature.outputs,
+        record_types,
+    )
+}
+
+pub(super) fn serialize_function_type(
+    name: &str,
+    arguments: &[IFunctionArg],
+    outputs: &[IType],
+    record_types: &IRecordTypes,
 ) -> FunctionSignature {
     use super::itype_text_view;
 
-    let arguments = signature
-        .arguments
+    let arguments = arguments
         .iter()
         .map(|arg| (arg.name.clone(), itype_text_view(&arg.ty, record_types)))
         .collect();
 
-    let output_types = signature
-        .outputs
+    let output_types = outputs
         .iter()
         .map(|itype| itype_text_view(itype, record_types))
         .collect();
 
     FunctionSignature {
-        name: signature.name.to_string(),
+        name: name.to_string(),
         arguments,
         output_types,
     }
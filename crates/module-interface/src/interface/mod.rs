@@ -36,3 +36,22 @@ pub fn get_interface(mit: &MITInterfaces<'_>) -> InterfaceResult<ModuleInterface
 
     Ok(interface)
 }
+
+/// Returns functions imported by a Marine module.
+pub fn get_imports(mit: &MITInterfaces<'_>) -> InterfaceResult<Vec<ImportFunctionSignature>> {
+    let record_types = crate::it_interface::get_all_records(mit);
+    let imports = crate::it_interface::get_import_funcs(mit)?
+        .into_iter()
+        .map(|import| ImportFunctionSignature {
+            namespace: import.namespace.to_string(),
+            signature: interface_transformer::serialize_function_type(
+                &import.name,
+                &import.arguments,
+                &import.outputs,
+                &record_types,
+            ),
+        })
+        .collect();
+
+    Ok(imports)
+}
@@ -56,6 +56,13 @@ pub struct RecordType {
     pub fields: Vec<RecordField>,
 }
 
+/// A function imported by a module, `namespace` is a name of the module or host providing it.
+#[derive(PartialEq, Eq, Debug, Clone, Hash, Serialize, Deserialize)]
+pub struct ImportFunctionSignature {
+    pub namespace: String,
+    pub signature: FunctionSignature,
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Hash, Serialize, Deserialize)]
 pub struct ModuleInterface {
     pub function_signatures: Vec<FunctionSignature>,
@@ -84,6 +91,12 @@ impl fmt::Display for FunctionSignature {
     }
 }
 
+impl fmt::Display for ImportFunctionSignature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.namespace, self.signature)
+    }
+}
+
 impl fmt::Display for RecordType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(f, "data {}:", self.name)?;
@@ -111,7 +111,7 @@ fn handle_record_type(
     Ok(())
 }
 
-fn get_all_records(mit: &MITInterfaces<'_>) -> IRecordTypes {
+pub(crate) fn get_all_records(mit: &MITInterfaces<'_>) -> IRecordTypes {
     use marine_it_interfaces::ITAstType;
 
     mit.types()
@@ -0,0 +1,66 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::ITInterfaceError;
+use super::RIResult;
+
+use marine_it_interfaces::MITInterfaces;
+use wasmer_it::IType;
+use wasmer_it::ast::FunctionArg as IFunctionArg;
+
+use serde::Serialize;
+use serde::Deserialize;
+
+use std::sync::Arc;
+
+/// Represent a function imported by Marine module from another module or host.
+#[derive(PartialEq, Eq, Debug, Clone, Hash, Serialize, Deserialize)]
+pub struct IImportFunctionSignature {
+    pub namespace: Arc<String>,
+    pub name: Arc<String>,
+    pub arguments: Arc<Vec<IFunctionArg>>,
+    pub outputs: Arc<Vec<IType>>,
+}
+
+/// Returns all imported IT functions.
+pub fn get_import_funcs(mit: &MITInterfaces<'_>) -> RIResult<Vec<IImportFunctionSignature>> {
+    use marine_it_interfaces::ITAstType;
+
+    // Each IT import is declared twice: with its IT type and with a type of the corresponding
+    // raw import, which has an adapter. The IT type always precedes the raw one by two
+    // positions, the same assumption is used while the module is instantiated.
+    mit.imports()
+        .filter(|import| {
+            mit.adapter_types_by_core_type(import.function_type)
+                .is_some()
+        })
+        .map(|import| {
+            let it_function_type = import.function_type - 2;
+            match mit.type_by_idx_r(it_function_type)? {
+                ITAstType::Function {
+                    arguments,
+                    output_types,
+                } => Ok(IImportFunctionSignature {
+                    namespace: Arc::new(import.namespace.to_string()),
+                    name: Arc::new(import.name.to_string()),
+                    arguments: arguments.clone(),
+                    outputs: output_types.clone(),
+                }),
+                _ => Err(ITInterfaceError::ITTypeNotFunction(it_function_type)),
+            }
+        })
+        .collect()
+}
@@ -17,11 +17,13 @@
 mod errors;
 mod export_it_functions;
 mod export_it_records;
+mod import_it_functions;
 mod it_module_interface;
 
 pub use errors::*;
 pub use export_it_functions::*;
 pub use export_it_records::*;
+pub use import_it_functions::*;
 pub use it_module_interface::*;
 
 pub type RIResult<T> = std::result::Result<T, ITInterfaceError>;
@@ -0,0 +1,251 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::it_interface::IRecordTypes;
+
+use wasmer_it::IType;
+use wasmer_it::ast::FunctionArg as IFunctionArg;
+
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value as JValue;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+pub const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Types of a function to generate a schema for.
+#[derive(Debug, Clone, Copy)]
+pub struct FunctionTypes<'a> {
+    pub name: &'a str,
+    pub arguments: &'a [IFunctionArg],
+    pub outputs: &'a [IType],
+}
+
+/// Generates a JSON Schema document describing functions of a module.
+///
+/// Records are placed into `$defs`, and each function is described under `functions/<name>`
+/// by a schema of its arguments array and a schema of its result. These schemas reference
+/// records relatively to the document, so they should be addressed by a JSON pointer
+/// inside it, e.g. `urn:marine:<module>#/functions/<name>/arguments`.
+///
+/// SAFETY:
+///     It's assumed that all records used by functions have a corresponding type in record_types.
+pub fn module_schema<'a>(
+    module_name: &str,
+    functions: impl IntoIterator<Item = FunctionTypes<'a>>,
+    record_types: &IRecordTypes,
+) -> JValue {
+    let generator = SchemaGenerator::new(record_types);
+    let functions = functions.into_iter().collect::<Vec<_>>();
+
+    let used_types = functions
+        .iter()
+        .flat_map(|function| {
+            let arguments = function.arguments.iter().map(|arg| &arg.ty);
+            arguments.chain(function.outputs.iter())
+        })
+        .collect::<Vec<_>>();
+
+    let schemas = functions
+        .iter()
+        .map(|function| (function.name.to_string(), generator.function(function)))
+        .collect::<Map<_, _>>();
+
+    json!({
+        "$schema": JSON_SCHEMA_DIALECT,
+        "$id": format!("urn:marine:{}", module_name),
+        "title": module_name,
+        "$defs": generator.definitions(used_types),
+        "functions": schemas,
+    })
+}
+
+/// Converts the supplied IType to a JSON Schema accepted by `it-json-serde`.
+/// Records are referenced by `#/$defs/<record name>`.
+pub fn itype_schema(ty: &IType, record_types: &IRecordTypes) -> JValue {
+    SchemaGenerator::new(record_types).itype(ty)
+}
+
+struct SchemaGenerator<'r> {
+    record_types: &'r IRecordTypes,
+    definition_names: HashMap<u64, String>,
+}
+
+impl<'r> SchemaGenerator<'r> {
+    fn new(record_types: &'r IRecordTypes) -> Self {
+        let mut ids = record_types.keys().copied().collect::<Vec<_>>();
+        ids.sort_unstable();
+
+        let mut name_counts = HashMap::<&str, usize>::new();
+        for id in ids.iter() {
+            *name_counts.entry(&record_types[id].name).or_default() += 1;
+        }
+
+        // records from different modules could have the same name, ids disambiguate them
+        let definition_names = ids
+            .into_iter()
+            .map(|id| {
+                let name = &record_types[&id].name;
+                let definition_name = match name_counts[name.as_str()] {
+                    1 => name.clone(),
+                    _ => format!("{}_{}", name, id),
+                };
+                (id, definition_name)
+            })
+            .collect();
+
+        Self {
+            record_types,
+            definition_names,
+        }
+    }
+
+    /// Returns definitions of records used by the supplied types, including nested ones.
+    fn definitions<'t>(&self, types: impl IntoIterator<Item = &'t IType>) -> Map<String, JValue> {
+        let mut used_records = HashSet::new();
+        let mut types = types.into_iter().collect::<Vec<_>>();
+
+        while let Some(ty) = types.pop() {
+            match ty {
+                IType::Array(ty) => types.push(ty),
+                IType::Record(id) if used_records.insert(*id) => {
+                    let fields = self.record_types[id].fields.iter();
+                    types.extend(fields.map(|field| &field.ty));
+                }
+                _ => {}
+            }
+        }
+
+        used_records
+            .into_iter()
+            .map(|id| (self.definition_names[&id].clone(), self.record(id)))
+            .collect()
+    }
+
+    fn function(&self, function: &FunctionTypes<'_>) -> JValue {
+        let arguments = function
+            .arguments
+            .iter()
+            .map(|arg| {
+                let mut schema = self.itype(&arg.ty);
+                schema["title"] = json!(arg.name);
+                schema
+            })
+            .collect::<Vec<_>>();
+
+        let result = match function.outputs {
+            [] => json!({ "type": "null" }),
+            [output] => self.itype(output),
+            // several outputs are returned as an array, the same way as arguments are passed
+            outputs => {
+                let outputs = outputs
+                    .iter()
+                    .map(|output| self.itype(output))
+                    .collect::<Vec<_>>();
+                json!({
+                    "type": "array",
+                    "prefixItems": outputs,
+                    "items": false,
+                    "minItems": outputs.len(),
+                })
+            }
+        };
+
+        json!({
+            "arguments": {
+                "type": "array",
+                "prefixItems": arguments,
+                "items": false,
+                "minItems": arguments.len(),
+            },
+            "result": result,
+        })
+    }
+
+    fn record(&self, id: u64) -> JValue {
+        let record = &self.record_types[&id];
+
+        let properties = record
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), self.itype(&field.ty)))
+            .collect::<Map<_, _>>();
+        let required = record
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect::<Vec<_>>();
+
+        json!({
+            "title": record.name,
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+
+    fn itype(&self, ty: &IType) -> JValue {
+        match ty {
+            IType::Boolean => json!({ "type": "boolean" }),
+            IType::S8 => integer(i8::MIN, i8::MAX),
+            IType::S16 => integer(i16::MIN, i16::MAX),
+            IType::S32 | IType::I32 => integer(i32::MIN, i32::MAX),
+            IType::U8 => integer(u8::MIN, u8::MAX),
+            IType::U16 => integer(u16::MIN, u16::MAX),
+            IType::U32 => integer(u32::MIN, u32::MAX),
+            // 64-bit integers don't fit into IEEE 754 doubles used by many JSON parsers,
+            // so the format is set explicitly to let validators and clients treat them precisely
+            IType::S64 | IType::I64 => {
+                let mut schema = integer(i64::MIN, i64::MAX);
+                schema["format"] = json!("int64");
+                schema
+            }
+            IType::U64 => {
+                let mut schema = integer(u64::MIN, u64::MAX);
+                schema["format"] = json!("uint64");
+                schema
+            }
+            IType::F32 => json!({ "type": "number", "format": "float" }),
+            IType::F64 => json!({ "type": "number", "format": "double" }),
+            IType::String => json!({ "type": "string" }),
+            // byte arrays are represented by arrays of numbers in JSON
+            IType::ByteArray => json!({
+                "type": "array",
+                "items": integer(u8::MIN, u8::MAX),
+            }),
+            IType::Array(ty) => json!({
+                "type": "array",
+                "items": self.itype(ty),
+            }),
+            IType::Record(id) => {
+                // assumed that this functions called with well-formed args
+                let name = &self.definition_names[id];
+                json!({ "$ref": format!("#/$defs/{}", name) })
+            }
+        }
+    }
+}
+
+fn integer(minimum: impl Into<JValue>, maximum: impl Into<JValue>) -> JValue {
+    json!({
+        "type": "integer",
+        "minimum": minimum.into(),
+        "maximum": maximum.into(),
+    })
+}
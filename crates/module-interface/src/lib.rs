@@ -25,5 +25,7 @@
     unreachable_patterns
 )]
 
+pub mod diff;
 pub mod interface;
 pub mod it_interface;
+pub mod json_schema;
@@ -24,6 +24,7 @@ pub type RuntimeResult<T> = Result<T, RuntimeError>;
 pub type ModuleCreationResult<T> = Result<T, ModuleCreationError>;
 pub type InstantiationResult<T> = Result<T, InstantiationError>;
 pub type WasiResult<T> = Result<T, WasiError>;
+pub type MemoryFileResult<T> = Result<T, MemoryFileError>;
 
 /*
    General error design goals:
@@ -86,6 +87,12 @@ pub enum RuntimeError {
     #[error("A function returned invalid number of results: expected {expected}, got {actual}")]
     IncorrectResultsNumber { expected: usize, actual: usize },
 
+    #[error("Fuel metering is not supported by the backend or not enabled in its config")]
+    FuelNotSupported,
+
+    #[error("Execution exhausted its fuel")]
+    OutOfFuel,
+
     #[error("Unrecognized error: {0}")]
     Other(anyhow::Error),
 }
@@ -133,6 +140,21 @@ pub enum WasiError {
 
     #[error("Cumulative size of envs array exceeds 2^32")]
     TooLargeEnvsArray,
+
+    #[error("Deterministic WASI requires the Wasm backend to be configured for deterministic execution")]
+    NondeterministicBackend,
+
+    #[error("The Wasm backend doesn't support WASI {0}")]
+    UnsupportedParameter(&'static str),
+}
+
+#[derive(Debug, Error)]
+pub enum MemoryFileError {
+    #[error("File size {0} exceeds the limit of {} bytes", crate::MAX_MEMORY_FILE_SIZE)]
+    TooBig(u64),
+
+    #[error("Failed to allocate {0} bytes for a file")]
+    OutOfMemory(u64),
 }
 
 #[derive(Debug, Error)]
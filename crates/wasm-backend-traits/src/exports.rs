@@ -18,6 +18,7 @@ pub static STANDARD_MEMORY_EXPORT_NAME: &str = "memory";
 pub static STANDARD_MEMORY_INDEX: u32 = 0;
 
 use crate::DelayedContextLifetime;
+use crate::RuntimeResult;
 use crate::WasmBackend;
 
 /// Contains Wasm exports necessary for internal usage.
@@ -41,4 +42,13 @@ pub trait Memory<WB: WasmBackend>:
 {
     /// Get the size of the allocated memory in bytes.
     fn size(&self, store: &mut <WB as WasmBackend>::ContextMut<'_>) -> usize;
+
+    /// Grows the memory by `delta_pages` Wasm pages (64 KiB each).
+    /// # Errors:
+    ///     Returns an error if the memory can't be grown, e.g. because of a memory limit.
+    fn grow(
+        &self,
+        store: &mut <WB as WasmBackend>::ContextMut<'_>,
+        delta_pages: u32,
+    ) -> RuntimeResult<()>;
 }
@@ -18,6 +18,7 @@ use crate::AsContextMut;
 use crate::Export;
 use crate::ResolveResult;
 use crate::WasmBackend;
+use crate::WValue;
 
 /// A handle to an instantiated Wasm module. Cloning is cheap.
 pub trait Instance<WB: WasmBackend>: Clone {
@@ -53,4 +54,18 @@ pub trait Instance<WB: WasmBackend>: Clone {
         store: &mut impl AsContextMut<WB>,
         name: &str,
     ) -> ResolveResult<<WB as WasmBackend>::ExportFunction>;
+
+    /// Returns names and current values of all exported mutable globals,
+    /// or `None` if the backend can't inspect globals.
+    fn mutable_globals(&self, store: &mut impl AsContextMut<WB>) -> Option<Vec<(String, WValue)>>;
+
+    /// Sets a new value for an exported mutable global with the given name.
+    /// # Errors:
+    ///     Returns an error if there is no export with such name, or it is not a mutable global.
+    fn set_global(
+        &self,
+        store: &mut impl AsContextMut<WB>,
+        name: &str,
+        value: WValue,
+    ) -> ResolveResult<()>;
 }
@@ -25,6 +25,7 @@ pub mod instance;
 pub mod caller;
 pub mod function;
 pub mod macros;
+pub mod memory_fs;
 
 /// Helper functions for backend implementations.
 pub mod impl_utils;
@@ -40,6 +41,7 @@ pub mod prelude {
     pub use crate::instance::*;
     pub use crate::caller::*;
     pub use crate::function::*;
+    pub use crate::memory_fs::*;
     pub use crate::WasmBackend;
     pub use crate::DelayedContextLifetime;
 }
@@ -83,6 +85,13 @@ pub trait WasmBackend: Clone + Send + Sync + 'static {
     /// Creates a new wasm backend with default configuration. In future, a configuration
     /// may be passed as argument. The only option at the moment is an asynchronous backend.
     fn new_async() -> WasmBackendResult<Self>;
+
+    /// Returns a backend that shares compiled modules between all stores created from it,
+    /// so that every module is compiled only once. At least `max_modules` are kept.
+    /// A backend that already has a module cache or can't cache modules returns itself.
+    fn with_shared_module_cache(&self, _max_modules: usize) -> Self {
+        self.clone()
+    }
 }
 
 /// This struct is a helper, that allows passing `<WB as WasmBackend>::ContextMut` as template parameter,
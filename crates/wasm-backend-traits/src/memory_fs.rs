@@ -0,0 +1,503 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::DirUsage;
+use crate::MemoryFileError;
+use crate::MemoryFileResult;
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Component;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+
+/// Maximum size of a single in-memory file, writes beyond it fail instead of allocating.
+pub const MAX_MEMORY_FILE_SIZE: u64 = 1 << 30;
+
+/// A directory kept in memory, which could be mapped into a module instead of a host one.
+/// Clones share the content, so the directory could be inspected or exported after calls.
+#[derive(Default, Clone, Debug)]
+pub struct MemoryDir {
+    entries: Arc<RwLock<BTreeMap<String, MemoryNode>>>,
+}
+
+/// A file kept in memory, clones share the content.
+#[derive(Default, Clone, Debug)]
+pub struct MemoryFile {
+    content: Arc<RwLock<Vec<u8>>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum MemoryNode {
+    File(MemoryFile),
+    Dir(MemoryDir),
+}
+
+impl MemoryDir {
+    /// Copies regular files and directories from a host directory, other entries are skipped.
+    /// Symlinks aren't followed, so the copy can't loop or take files from outside the directory.
+    pub fn from_host_dir(path: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = Self::default();
+        for entry in std::fs::read_dir(path)? {
+            let entry = entry?;
+            let name = entry.file_name().into_string().map_err(|name| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("file name {:?} isn't valid UTF-8", name),
+                )
+            })?;
+
+            let path = entry.path();
+            // the file type of a symlink is not the type of its target
+            let file_type = entry.file_type()?;
+            let node = if file_type.is_dir() {
+                MemoryNode::Dir(Self::from_host_dir(&path)?)
+            } else if file_type.is_file() {
+                check_file_size(entry.metadata()?.len())?;
+                MemoryNode::File(MemoryFile::from_bytes(std::fs::read(&path)?))
+            } else {
+                continue;
+            };
+            dir.insert(name, node);
+        }
+
+        Ok(dir)
+    }
+
+    /// Reads regular files and directories from a tar archive, other entries are skipped.
+    pub fn from_tar(reader: impl Read) -> io::Result<Self> {
+        let root = Self::default();
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = tar_entry_path(&entry.path()?)?;
+            let (name, parents) = match path.split_last() {
+                Some(path) => path,
+                // the archive root itself
+                None => continue,
+            };
+
+            let entry_type = entry.header().entry_type();
+            if entry_type.is_dir() {
+                root.create_dirs(&path)?;
+            } else if entry_type.is_file() {
+                // the size comes from the header, so the content is read without preallocation
+                let size = entry.size();
+                check_file_size(size)?;
+                let mut data = Vec::new();
+                (&mut entry).take(size).read_to_end(&mut data)?;
+                if data.len() as u64 != size {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("tar entry {} is truncated", path.join("/")),
+                    ));
+                }
+
+                let parent = root.create_dirs(parents)?;
+                parent.insert(name.clone(), MemoryNode::File(MemoryFile::from_bytes(data)));
+            }
+        }
+
+        Ok(root)
+    }
+
+    /// Writes the directory content into a host directory, which is created if it doesn't exist.
+    pub fn export_to_host_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        std::fs::create_dir_all(path)?;
+
+        for (name, node) in self.entries() {
+            match node {
+                MemoryNode::File(file) => std::fs::write(path.join(name), file.to_vec())?,
+                MemoryNode::Dir(dir) => dir.export_to_host_dir(path.join(name))?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the directory content as a tar archive.
+    pub fn export_to_tar(&self, writer: impl Write) -> io::Result<()> {
+        let mut builder = tar::Builder::new(writer);
+        self.append_tar_entries(&mut builder, Path::new(""))?;
+        builder.finish()
+    }
+
+    pub fn get(&self, name: &str) -> Option<MemoryNode> {
+        self.read().get(name).cloned()
+    }
+
+    /// Adds an entry replacing the previous one with the same name.
+    pub fn insert(&self, name: impl Into<String>, node: MemoryNode) -> Option<MemoryNode> {
+        self.write().insert(name.into(), node)
+    }
+
+    pub fn remove(&self, name: &str) -> Option<MemoryNode> {
+        self.write().remove(name)
+    }
+
+    /// Returns entries sorted by names.
+    pub fn entries(&self) -> Vec<(String, MemoryNode)> {
+        self.read()
+            .iter()
+            .map(|(name, node)| (name.clone(), node.clone()))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Returns the total size of files and the number of entries in the directory tree.
+    pub fn usage(&self) -> DirUsage {
+        let mut usage = DirUsage::default();
+        for (_, node) in self.entries() {
+            usage.files += 1;
+            match node {
+                MemoryNode::File(file) => usage.bytes += file.len(),
+                MemoryNode::Dir(dir) => {
+                    let nested = dir.usage();
+                    usage.bytes += nested.bytes;
+                    usage.files += nested.files;
+                }
+            }
+        }
+
+        usage
+    }
+
+    /// Returns true if both handles refer to the same directory.
+    pub fn same_as(&self, other: &MemoryDir) -> bool {
+        Arc::ptr_eq(&self.entries, &other.entries)
+    }
+
+    /// Returns a unique number of the directory while it exists.
+    pub fn id(&self) -> u64 {
+        Arc::as_ptr(&self.entries) as u64
+    }
+
+    fn create_dirs(&self, path: &[String]) -> io::Result<MemoryDir> {
+        let mut dir = self.clone();
+        for name in path {
+            let next = match dir.get(name) {
+                Some(MemoryNode::Dir(next)) => next,
+                Some(MemoryNode::File(_)) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{} is both a file and a directory", path.join("/")),
+                    ))
+                }
+                None => {
+                    let next = MemoryDir::default();
+                    dir.insert(name.clone(), MemoryNode::Dir(next.clone()));
+                    next
+                }
+            };
+            dir = next;
+        }
+
+        Ok(dir)
+    }
+
+    fn append_tar_entries<W: Write>(
+        &self,
+        builder: &mut tar::Builder<W>,
+        prefix: &Path,
+    ) -> io::Result<()> {
+        for (name, node) in self.entries() {
+            let path = prefix.join(name);
+            let mut header = tar::Header::new_gnu();
+            header.set_mtime(0);
+            match node {
+                MemoryNode::File(file) => {
+                    let content = file.to_vec();
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_mode(0o644);
+                    header.set_size(content.len() as u64);
+                    builder.append_data(&mut header, &path, content.as_slice())?;
+                }
+                MemoryNode::Dir(dir) => {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_mode(0o755);
+                    header.set_size(0);
+                    builder.append_data(&mut header, &path, io::empty())?;
+                    dir.append_tar_entries(builder, &path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, BTreeMap<String, MemoryNode>> {
+        self.entries.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, BTreeMap<String, MemoryNode>> {
+        self.entries.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl MemoryFile {
+    pub fn from_bytes(content: Vec<u8>) -> Self {
+        Self {
+            content: Arc::new(RwLock::new(content)),
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.read().len() as u64
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.read().clone()
+    }
+
+    /// Reads bytes starting from `offset`, returns how many bytes were read.
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> usize {
+        let content = self.read();
+        let start = std::cmp::min(offset, content.len() as u64) as usize;
+        let count = std::cmp::min(buf.len(), content.len() - start);
+        buf[..count].copy_from_slice(&content[start..start + count]);
+        count
+    }
+
+    /// Writes bytes starting from `offset`, the gap after the current end is filled with zeroes.
+    /// Fails without changes if the file would grow beyond `MAX_MEMORY_FILE_SIZE`.
+    pub fn write_at(&self, offset: u64, data: &[u8]) -> MemoryFileResult<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let end = offset.saturating_add(data.len() as u64);
+        let mut content = self.write();
+        grow(&mut content, end)?;
+        content[offset as usize..end as usize].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Truncates or extends the file with zeroes, the size is limited by `MAX_MEMORY_FILE_SIZE`.
+    pub fn set_len(&self, len: u64) -> MemoryFileResult<()> {
+        let mut content = self.write();
+        grow(&mut content, len)?;
+        content.truncate(len as usize);
+        Ok(())
+    }
+
+    /// Returns a unique number of the file while it exists.
+    pub fn id(&self) -> u64 {
+        Arc::as_ptr(&self.content) as u64
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Vec<u8>> {
+        self.content.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, Vec<u8>> {
+        self.content.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Extends the content with zeroes up to `len` bytes, if it's shorter.
+fn grow(content: &mut Vec<u8>, len: u64) -> MemoryFileResult<()> {
+    if len > MAX_MEMORY_FILE_SIZE {
+        return Err(MemoryFileError::TooBig(len));
+    }
+
+    let len = len as usize;
+    if content.len() < len {
+        content
+            .try_reserve(len - content.len())
+            .map_err(|_| MemoryFileError::OutOfMemory(len as u64))?;
+        content.resize(len, 0);
+    }
+
+    Ok(())
+}
+
+fn check_file_size(size: u64) -> io::Result<()> {
+    match size > MAX_MEMORY_FILE_SIZE {
+        true => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            MemoryFileError::TooBig(size),
+        )),
+        false => Ok(()),
+    }
+}
+
+fn tar_entry_path(path: &Path) -> io::Result<Vec<String>> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => {
+                let name = name.to_str().ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("tar entry {:?} isn't valid UTF-8", path),
+                    )
+                })?;
+                components.push(name.to_string());
+            }
+            Component::ParentDir => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("tar entry {:?} points outside of the archive", path),
+                ))
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+
+    Ok(components)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tar_round_trip() {
+        let dir = MemoryDir::default();
+        dir.insert(
+            "a.txt",
+            MemoryNode::File(MemoryFile::from_bytes(b"abc".to_vec())),
+        );
+        let nested = dir
+            .create_dirs(&[String::from("b"), "c".repeat(60), "d".repeat(60)])
+            .unwrap();
+        nested.insert(
+            "d.bin",
+            MemoryNode::File(MemoryFile::from_bytes(vec![7; 1000])),
+        );
+        dir.create_dirs(&[String::from("empty")]).unwrap();
+
+        let mut tar = Vec::new();
+        dir.export_to_tar(&mut tar).unwrap();
+        assert_eq!(tar.len() % 512, 0);
+
+        let restored = MemoryDir::from_tar(tar.as_slice()).unwrap();
+        let mut restored_tar = Vec::new();
+        restored.export_to_tar(&mut restored_tar).unwrap();
+        assert_eq!(tar, restored_tar);
+
+        let names = restored
+            .entries()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a.txt", "b", "empty"]);
+        assert_eq!(
+            restored.usage(),
+            DirUsage {
+                bytes: 1003,
+                files: 6
+            }
+        );
+    }
+
+    #[test]
+    fn tar_entries_are_kept_inside_archive() {
+        let mut header = tar::Header::new_gnu();
+        header.as_gnu_mut().unwrap().name[..9].copy_from_slice(b"../escape");
+        header.set_size(0);
+        header.set_cksum();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.append(&header, io::empty()).unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        let result = MemoryDir::from_tar(tar.as_slice());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn tar_entry_sizes_are_checked() {
+        let archive = |size: u64| {
+            let mut header = tar::Header::new_gnu();
+            header.set_path("file").unwrap();
+            header.set_size(size);
+            header.set_cksum();
+            header.as_bytes().to_vec()
+        };
+
+        let result = MemoryDir::from_tar(archive(MAX_MEMORY_FILE_SIZE + 1).as_slice());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // the header promises more data than the archive has
+        let result = MemoryDir::from_tar(archive(1 << 20).as_slice());
+        assert!(result.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn host_dir_symlinks_are_skipped() {
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.txt"), b"secret").unwrap();
+
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("a.txt"), b"abc").unwrap();
+        std::fs::create_dir(root.path().join("nested")).unwrap();
+        let symlink = |target: &Path, name: &str| {
+            std::os::unix::fs::symlink(target, root.path().join(name)).unwrap()
+        };
+        // a loop, a directory and a file outside of the copied one
+        symlink(root.path(), "nested/loop");
+        symlink(outside.path(), "outside");
+        symlink(&outside.path().join("secret.txt"), "secret.txt");
+
+        let dir = MemoryDir::from_host_dir(root.path()).unwrap();
+        let names = dir
+            .entries()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["a.txt", "nested"]);
+        assert_eq!(dir.usage(), DirUsage { bytes: 3, files: 2 });
+    }
+
+    #[test]
+    fn file_writes_extend_content() {
+        let file = MemoryFile::default();
+        file.write_at(2, b"xy").unwrap();
+        assert_eq!(file.to_vec(), b"\0\0xy");
+
+        let mut buf = [0u8; 8];
+        assert_eq!(file.read_at(3, &mut buf), 1);
+        assert_eq!(file.read_at(10, &mut buf), 0);
+    }
+
+    #[test]
+    fn file_size_is_limited() {
+        let file = MemoryFile::from_bytes(b"abc".to_vec());
+        let result = file.write_at(1 << 60, b"x");
+        assert!(matches!(result, Err(MemoryFileError::TooBig(_))));
+        let result = file.set_len(MAX_MEMORY_FILE_SIZE + 1);
+        assert!(matches!(result, Err(MemoryFileError::TooBig(_))));
+        assert_eq!(file.to_vec(), b"abc");
+
+        file.set_len(1).unwrap();
+        assert_eq!(file.to_vec(), b"a");
+    }
+}
@@ -25,6 +25,15 @@ pub trait Module<WB: WasmBackend>: Sized {
     /// Compiles a wasm bytes into a module and extracts custom sections.
     fn new(store: &mut <WB as WasmBackend>::Store, wasm: &[u8]) -> ModuleCreationResult<Self>;
 
+    /// Compiles a wasm bytes into a module like `new`, but reuses a previously compiled artifact
+    /// if the backend has a cache of compiled modules. Backends without a cache just call `new`.
+    fn new_cached(
+        store: &mut <WB as WasmBackend>::Store,
+        wasm: &[u8],
+    ) -> ModuleCreationResult<Self> {
+        Self::new(store, wasm)
+    }
+
     /// Returns custom sections corresponding to `name`, empty slice if there is no sections.
     fn custom_sections(&self, name: &str) -> &[Vec<u8>];
 
@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use crate::RuntimeResult;
 use crate::WasmBackend;
 
 /// `Store` is an object that stores modules, instances, functions memories and so on.
@@ -31,13 +32,33 @@ pub trait Store<WB: WasmBackend>: AsContextMut<WB> {
     fn report_memory_allocation_stats(&self) -> Option<MemoryAllocationStats>;
 
     fn clear_allocation_stats(&mut self);
+
+    /// Sets the amount of fuel available for the following execution, unused fuel is discarded.
+    /// Most Wasm instructions consume one unit of fuel, execution traps when fuel is exhausted.
+    /// `None` makes the fuel unlimited.
+    /// # Errors:
+    ///     Returns `RuntimeError::FuelNotSupported` if the backend doesn't support fuel metering
+    ///     or it is not enabled in the backend config.
+    fn set_fuel(&mut self, fuel: Option<u64>) -> RuntimeResult<()>;
+
+    /// Returns the total amount of fuel consumed by this store,
+    /// or `None` if fuel metering is not supported or not enabled.
+    fn consumed_fuel(&self) -> Option<u64>;
 }
 
 /// A temporary immutable handle to store
 pub trait Context<WB: WasmBackend>: AsContext<WB> + Send {}
 
 /// A temporary mutable handle to store
-pub trait ContextMut<WB: WasmBackend>: AsContextMut<WB> + Send {}
+pub trait ContextMut<WB: WasmBackend>: AsContextMut<WB> + Send {
+    /// Sets the memory limit of the module which code is executed now, `None` removes it.
+    /// Growing a memory beyond the limit fails the same way as exceeding the total memory limit.
+    /// Returns the previous limit, so it could be restored when the execution leaves the module.
+    fn set_module_memory_limit(
+        &mut self,
+        limit: Option<ModuleMemoryLimit>,
+    ) -> Option<ModuleMemoryLimit>;
+}
 
 pub trait AsContext<WB: WasmBackend>: Send {
     fn as_context(&self) -> <WB as WasmBackend>::Context<'_>;
@@ -50,4 +71,14 @@ pub trait AsContextMut<WB: WasmBackend>: AsContext<WB> {
 #[derive(Default, Clone, Debug)]
 pub struct MemoryAllocationStats {
     pub allocation_rejects: u32,
+
+    /// The last module which allocation was rejected because of its own memory limit.
+    pub module_limit_exceeded: Option<String>,
+}
+
+/// Maximum size of a module memory in bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleMemoryLimit {
+    pub module_name: String,
+    pub max_memory: u64,
 }
@@ -14,11 +14,18 @@
  * limitations under the License.
  */
 
+use crate::AsContext;
+use crate::MemoryDir;
 use crate::WasiError;
 use crate::WasmBackend;
 
 use std::path::PathBuf;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Total size of files in an in-memory directory, if its policy doesn't set `max_bytes`.
+pub const DEFAULT_MEMORY_DIR_MAX_BYTES: u64 = 64 * 1024 * 1024;
 
 /// A type that provides WASI functionality to the given Wasm backend.
 pub trait WasiImplementation<WB: WasmBackend> {
@@ -31,20 +38,216 @@ pub trait WasiImplementation<WB: WasmBackend> {
         config: WasiParameters,
     ) -> Result<(), WasiError>;
 
-    /// Optional API for getting current WASI state.
-    /// Returns None if not supported by current backend.
+    /// Optional API for getting current WASI state of the instance.
+    /// Backends that don't support it return an empty state.
     fn get_wasi_state<'s>(
+        store: &impl AsContext<WB>,
         instance: &'s mut <WB as WasmBackend>::Instance,
     ) -> Box<dyn WasiState + 's>;
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct WasiParameters {
     pub args: Vec<String>,
     pub envs: HashMap<String, String>,
     pub mapped_dirs: HashMap<String, PathBuf>,
+    /// Directories kept in memory, mapped by guest paths like `mapped_dirs`.
+    pub memory_dirs: HashMap<String, MemoryDir>,
+    /// Access mode and quotas of host or in-memory directories by their guest paths,
+    /// host directories without a policy are writable without limits, and in-memory ones
+    /// are limited by `DEFAULT_MEMORY_DIR_MAX_BYTES`. Quotas of a directory mapped
+    /// by several modules are checked against its total usage.
+    pub dir_policies: HashMap<String, MappedDirPolicy>,
+    /// If set, the module observes the virtual time and randomness from it instead of the host ones.
+    pub deterministic_env: Option<DeterministicEnv>,
+    /// If set, stdout and stderr of the module are written to it instead of the host ones.
+    pub output_capture: Option<OutputCapture>,
+}
+
+/// Virtual clock and random seed provided to WASI in the deterministic mode.
+/// It's shared by all modules of a service and reset before each call,
+/// so equal calls observe equal time and random bytes on any host.
+#[derive(Default, Clone, Debug)]
+pub struct DeterministicEnv {
+    seed: Arc<Mutex<DeterministicSeed>>,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct DeterministicSeed {
+    /// Virtual time in nanoseconds since the Unix epoch, it doesn't change during a call.
+    pub timestamp_ns: u64,
+
+    /// Bytes the random generator is seeded with.
+    pub random_seed: Vec<u8>,
+
+    /// Incremented on each reset, so random generators could restart their sequences.
+    pub generation: u64,
+}
+
+impl DeterministicEnv {
+    /// Sets the virtual time and the random seed for the next call.
+    pub fn reset(&self, timestamp_ns: u64, random_seed: Vec<u8>) {
+        let mut seed = self.seed.lock().unwrap_or_else(|e| e.into_inner());
+        seed.timestamp_ns = timestamp_ns;
+        seed.random_seed = random_seed;
+        seed.generation += 1;
+    }
+
+    pub fn seed(&self) -> DeterministicSeed {
+        self.seed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+/// Bounded in-memory buffers for stdout and stderr of a module, shared with its WASI context.
+/// Output beyond the limit is dropped without notifying the module.
+#[derive(Clone, Debug)]
+pub struct OutputCapture {
+    output: Arc<Mutex<CapturedOutput>>,
+    max_size: usize,
+}
+
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct CapturedOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+
+    /// Set if a part of the output was dropped because of the size limit.
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+impl OutputCapture {
+    /// Creates buffers holding up to `max_size` bytes of stdout and of stderr each.
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            output: <_>::default(),
+            max_size,
+        }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub fn write(&self, stream: OutputStream, data: &[u8]) {
+        let mut output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        let output = &mut *output;
+        let buffer = match stream {
+            OutputStream::Stdout => &mut output.stdout,
+            OutputStream::Stderr => &mut output.stderr,
+        };
+
+        let available = self.max_size.saturating_sub(buffer.len());
+        if data.len() > available {
+            output.truncated = true;
+        }
+        buffer.extend_from_slice(&data[..data.len().min(available)]);
+    }
+
+    /// Returns the output written since the previous call and empties the buffers.
+    pub fn take(&self) -> CapturedOutput {
+        std::mem::take(&mut *self.output.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl CapturedOutput {
+    pub fn is_empty(&self) -> bool {
+        self.stdout.is_empty() && self.stderr.is_empty() && !self.truncated
+    }
 }
 
 pub trait WasiState {
+    /// Environment variables in the `NAME=VALUE` form.
     fn envs(&self) -> &[Vec<u8>];
+
+    /// Command line arguments.
+    fn args(&self) -> &[String];
+
+    /// Directories opened before the module start, in the order of their file descriptors.
+    fn preopened_dirs(&self) -> &[PreopenedDir];
+
+    /// File descriptors currently open by the module, sorted by number.
+    fn open_fds(&self) -> &[OpenFd];
+}
+
+/// A directory available to a module under the guest path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreopenedDir {
+    pub fd: u32,
+    pub guest_path: String,
+    /// A host directory, or `None` for directories kept in memory.
+    pub host_path: Option<PathBuf>,
+    pub policy: MappedDirPolicy,
+    /// Space taken in the directory, it's tracked only for directories with quotas.
+    pub usage: Option<DirUsage>,
+}
+
+/// Restrictions a WASI implementation applies to a mapped directory.
+/// Violations are reported to the module as `EROFS` and `EDQUOT` errors.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MappedDirPolicy {
+    pub read_only: bool,
+    /// Maximum total size of files in bytes.
+    pub max_bytes: Option<u64>,
+    /// Maximum number of files and directories, the mapped directory itself isn't counted.
+    pub max_files: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirUsage {
+    pub bytes: u64,
+    pub files: u64,
+}
+
+impl MappedDirPolicy {
+    pub fn has_quota(&self) -> bool {
+        self.max_bytes.is_some() || self.max_files.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenFd {
+    pub fd: u32,
+    pub kind: OpenFdKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpenFdKind {
+    /// One of stdin, stdout and stderr.
+    Stdio,
+
+    /// A directory from `WasiState::preopened_dirs`.
+    PreopenedDir,
+
+    /// A file or a directory opened by the module itself.
+    Opened,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OutputCapture;
+    use super::OutputStream;
+
+    #[test]
+    fn output_capture_is_bounded() {
+        let capture = OutputCapture::new(4);
+        capture.write(OutputStream::Stdout, b"abc");
+        capture.write(OutputStream::Stdout, b"def");
+        capture.write(OutputStream::Stderr, b"gh");
+
+        let output = capture.take();
+        assert_eq!(output.stdout, b"abcd");
+        assert_eq!(output.stderr, b"gh");
+        assert!(output.truncated);
+
+        assert!(capture.take().is_empty());
+    }
 }
@@ -0,0 +1,162 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_wasm_backend_traits::DeterministicEnv;
+
+use cap_std::time::Instant;
+use cap_std::time::SystemTime;
+use sha2::Digest;
+use wasi_common::clocks::WasiMonotonicOffsetClock;
+use wasi_common::RngCore;
+use wasi_common::WasiClocks;
+use wasi_common::WasiMonotonicClock;
+use wasi_common::WasiSystemClock;
+
+use std::time::Duration;
+
+/// Size of a block of random bytes, it's the SHA-256 output size.
+const RANDOM_BLOCK_SIZE: usize = 32;
+
+/// Returns clocks which report the virtual time of the environment.
+pub(crate) fn virtual_clocks(env: DeterministicEnv) -> WasiClocks {
+    let base = Instant::from_std(std::time::Instant::now());
+    let monotonic = WasiMonotonicOffsetClock {
+        // the monotonic time is counted from the epoch like the system one,
+        // so it doesn't depend on when the module was loaded
+        creation_time: base,
+        abs_clock: Box::new(VirtualMonotonicClock {
+            env: env.clone(),
+            base,
+        }),
+    };
+
+    WasiClocks {
+        system: Some(Box::new(VirtualSystemClock { env })),
+        monotonic: Some(monotonic),
+    }
+}
+
+struct VirtualSystemClock {
+    env: DeterministicEnv,
+}
+
+impl WasiSystemClock for VirtualSystemClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self, _precision: Duration) -> SystemTime {
+        let elapsed = Duration::from_nanos(self.env.seed().timestamp_ns);
+        SystemTime::from_std(std::time::UNIX_EPOCH + elapsed)
+    }
+}
+
+struct VirtualMonotonicClock {
+    env: DeterministicEnv,
+    base: Instant,
+}
+
+impl WasiMonotonicClock for VirtualMonotonicClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self, _precision: Duration) -> Instant {
+        self.base + Duration::from_nanos(self.env.seed().timestamp_ns)
+    }
+}
+
+/// Generates blocks of random bytes as SHA-256 of the seed and the block number,
+/// the sequence restarts after each reset of the environment.
+pub(crate) struct VirtualRng {
+    env: DeterministicEnv,
+    generation: Option<u64>,
+    random_seed: Vec<u8>,
+    block_id: u64,
+    block: [u8; RANDOM_BLOCK_SIZE],
+    used_bytes: usize,
+}
+
+impl VirtualRng {
+    pub(crate) fn new(env: DeterministicEnv) -> Self {
+        Self {
+            env,
+            generation: None,
+            random_seed: Vec::new(),
+            block_id: 0,
+            block: [0; RANDOM_BLOCK_SIZE],
+            used_bytes: RANDOM_BLOCK_SIZE,
+        }
+    }
+
+    fn sync_with_env(&mut self) {
+        let seed = self.env.seed();
+        if self.generation == Some(seed.generation) {
+            return;
+        }
+
+        self.generation = Some(seed.generation);
+        self.random_seed = seed.random_seed;
+        self.block_id = 0;
+        self.used_bytes = RANDOM_BLOCK_SIZE;
+    }
+
+    fn next_block(&mut self) {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(&self.random_seed);
+        hasher.update(self.block_id.to_le_bytes());
+
+        self.block = hasher.finalize().into();
+        self.block_id += 1;
+        self.used_bytes = 0;
+    }
+}
+
+impl RngCore for VirtualRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.sync_with_env();
+
+        let mut filled = 0;
+        while filled < dest.len() {
+            if self.used_bytes == RANDOM_BLOCK_SIZE {
+                self.next_block();
+            }
+
+            let count = std::cmp::min(dest.len() - filled, RANDOM_BLOCK_SIZE - self.used_bytes);
+            dest[filled..filled + count]
+                .copy_from_slice(&self.block[self.used_bytes..self.used_bytes + count]);
+            filled += count;
+            self.used_bytes += count;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), cap_rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
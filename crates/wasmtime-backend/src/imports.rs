@@ -24,12 +24,16 @@ use marine_wasm_backend_traits::prelude::*;
 #[derive(Clone)]
 pub struct WasmtimeImports {
     pub(crate) linker: wasmtime::Linker<StoreState>,
+
+    /// Index of the WASI context in the store state, if WASI was registered.
+    pub(crate) wasi_id: Option<usize>,
 }
 
 impl Imports<WasmtimeWasmBackend> for WasmtimeImports {
     fn new(store: &mut WasmtimeStore) -> Self {
         Self {
             linker: wasmtime::Linker::new(store.inner.engine()),
+            wasi_id: None,
         }
     }
 
@@ -18,12 +18,15 @@ use crate::WasmtimeContextMut;
 use crate::WasmtimeFunction;
 use crate::WasmtimeMemory;
 use crate::WasmtimeWasmBackend;
+use crate::val_to_wvalue;
+use crate::wvalue_to_val;
 
 use marine_wasm_backend_traits::prelude::*;
 
 #[derive(Clone)]
 pub struct WasmtimeInstance {
     pub(crate) inner: wasmtime::Instance,
+    pub(crate) wasi_id: Option<usize>,
 }
 
 impl Instance<WasmtimeWasmBackend> for WasmtimeInstance {
@@ -90,4 +93,58 @@ impl Instance<WasmtimeWasmBackend> for WasmtimeInstance {
 
         Ok(WasmtimeFunction { inner: func })
     }
+
+    fn mutable_globals(
+        &self,
+        store: &mut impl AsContextMut<WasmtimeWasmBackend>,
+    ) -> Option<Vec<(String, WValue)>> {
+        let mut store = store.as_context_mut();
+        let globals = self
+            .inner
+            .exports(&mut store.inner)
+            .filter_map(|export| {
+                let name = export.name().to_string();
+                export.into_global().map(|global| (name, global))
+            })
+            .collect::<Vec<_>>();
+
+        let globals = globals
+            .into_iter()
+            .filter_map(|(name, global)| {
+                if global.ty(&store.inner).mutability() != wasmtime::Mutability::Var {
+                    return None;
+                }
+
+                // globals of unsupported types (v128, references) can't be represented as WValue
+                val_to_wvalue(&global.get(&mut store.inner))
+                    .ok()
+                    .map(|value| (name, value))
+            })
+            .collect();
+
+        Some(globals)
+    }
+
+    fn set_global(
+        &self,
+        store: &mut impl AsContextMut<WasmtimeWasmBackend>,
+        name: &str,
+        value: WValue,
+    ) -> ResolveResult<()> {
+        let mut store = store.as_context_mut();
+        let global = self
+            .inner
+            .get_export(&mut store.inner, name)
+            .ok_or_else(|| ResolveError::ExportNotFound(name.to_string()))
+            .and_then(|e| {
+                e.into_global().ok_or(ResolveError::ExportTypeMismatch {
+                    expected: "global",
+                    actual: "other",
+                })
+            })?;
+
+        global
+            .set(&mut store.inner, wvalue_to_val(&value))
+            .map_err(ResolveError::Other)
+    }
 }
@@ -23,6 +23,10 @@ mod wasi;
 mod function;
 mod imports;
 mod memory;
+mod module_cache;
+mod deterministic;
+mod memory_dir;
+mod limited_dir;
 
 use store::*;
 use caller::*;
@@ -33,10 +37,16 @@ use function::*;
 use memory::*;
 use imports::*;
 use utils::*;
+use module_cache::ModuleCache;
+use limited_dir::SharedDirUsage;
+
+pub use module_cache::ModuleCacheLocation;
+pub use module_cache::ModuleCacheStats;
 
 use marine_wasm_backend_traits::prelude::*;
 
-use wasmtime_wasi::WasiCtx;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 const MB: usize = 1024 * 1024;
 
@@ -46,6 +56,8 @@ pub const DEFAULT_WASM_STACK_SIZE: usize = 2 * MB;
 #[derive(Clone)]
 pub struct WasmtimeWasmBackend {
     engine: wasmtime::Engine,
+    module_cache: Option<Arc<ModuleCache>>,
+    deterministic: bool,
 }
 
 impl WasmBackend for WasmtimeWasmBackend {
@@ -65,6 +77,16 @@ impl WasmBackend for WasmtimeWasmBackend {
     fn new_async() -> WasmBackendResult<Self> {
         Self::new(WasmtimeConfig::default())
     }
+
+    fn with_shared_module_cache(&self, max_modules: usize) -> Self {
+        let mut backend = self.clone();
+        if backend.module_cache.is_none() {
+            let location = ModuleCacheLocation::InMemory { max_modules };
+            backend.module_cache = Some(Arc::new(ModuleCache::new(location, &backend.engine)));
+        }
+
+        backend
+    }
 }
 
 impl WasmtimeWasmBackend {
@@ -72,23 +94,39 @@ impl WasmtimeWasmBackend {
         self.engine.increment_epoch()
     }
 
+    /// Returns usage counters of the module cache, or `None` if the cache isn't configured.
+    pub fn module_cache_stats(&self) -> Option<ModuleCacheStats> {
+        self.module_cache.as_ref().map(|cache| cache.stats())
+    }
+
     pub fn new(config: WasmtimeConfig) -> WasmBackendResult<Self> {
         let engine =
             wasmtime::Engine::new(&config.config).map_err(WasmBackendError::InitializationError)?;
-
-        Ok(Self { engine })
+        let module_cache = config
+            .module_cache
+            .map(|location| Arc::new(ModuleCache::new(location, &engine)));
+
+        Ok(Self {
+            engine,
+            module_cache,
+            deterministic: config.deterministic,
+        })
     }
 }
 
 #[derive(Default)]
 pub struct StoreState {
-    wasi: Vec<WasiCtx>, // wasmtime store does not release memory until drop, so do we
+    wasi: Vec<WasiContext>, // wasmtime store does not release memory until drop, so do we
+    dir_usages: HashMap<MappedDirKey, SharedDirUsage>,
     limits: MemoryLimiter,
+    deterministic: bool,
 }
 
 #[derive(Clone)]
 pub struct WasmtimeConfig {
     config: wasmtime::Config,
+    module_cache: Option<ModuleCacheLocation>,
+    deterministic: bool,
 }
 
 impl Default for WasmtimeConfig {
@@ -101,7 +139,11 @@ impl Default for WasmtimeConfig {
             .epoch_interruption(true)
             .wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
 
-        Self { config }
+        Self {
+            config,
+            module_cache: None,
+            deterministic: false,
+        }
     }
 }
 
@@ -110,7 +152,11 @@ impl WasmtimeConfig {
     /// It forcefully enables async support, because the backend does not work with sync configs.
     pub fn from_raw(mut config: wasmtime::Config) -> Self {
         config.async_support(true);
-        Self { config }
+        Self {
+            config,
+            module_cache: None,
+            deterministic: false,
+        }
     }
 
     /// Configures whether DWARF debug information will be emitted during
@@ -130,6 +176,40 @@ impl WasmtimeConfig {
         self
     }
 
+    /// Enables fuel metering, which is required to limit calls by fuel.
+    /// Instrumented code is slower, so it should be enabled only when needed.
+    ///
+    /// By default this option is `false`.
+    pub fn consume_fuel(&mut self, enable: bool) -> &mut Self {
+        self.config.consume_fuel(enable);
+        self
+    }
+
+    /// Enables caching of compiled modules, so the same Wasm bytes are compiled only once
+    /// per engine config. Used when modules are created with `Module::new_cached`.
+    ///
+    /// By default modules are not cached.
+    pub fn module_cache(&mut self, location: ModuleCacheLocation) -> &mut Self {
+        self.module_cache = Some(location);
+        self
+    }
+
+    /// Makes execution reproducible on any host: NaNs are canonicalized, nondeterministic
+    /// Wasm features are disabled and WASI gets a virtual clock and random generator,
+    /// which are seeded with `WasiParameters::deterministic_env` or with zeroes if it's absent.
+    ///
+    /// By default this option is `false`.
+    pub fn deterministic(&mut self, enable: bool) -> &mut Self {
+        self.config
+            .cranelift_nan_canonicalization(enable)
+            .relaxed_simd_deterministic(enable);
+        if enable {
+            self.config.wasm_threads(false).wasm_relaxed_simd(false);
+        }
+        self.deterministic = enable;
+        self
+    }
+
     /// Configures the maximum amount of stack space available for
     /// executing WebAssembly code.
     ///
@@ -0,0 +1,469 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_wasm_backend_traits::DirUsage;
+use marine_wasm_backend_traits::MappedDirPolicy;
+
+use wasi_common::dir::OpenResult;
+use wasi_common::dir::ReaddirCursor;
+use wasi_common::dir::ReaddirEntity;
+use wasi_common::file::Advice;
+use wasi_common::file::FdFlags;
+use wasi_common::file::FileType;
+use wasi_common::file::Filestat;
+use wasi_common::file::OFlags;
+use wasi_common::snapshots::preview_1::error::Errno;
+use wasi_common::Error;
+use wasi_common::SystemTimeSpec;
+use wasi_common::WasiDir;
+use wasi_common::WasiFile;
+
+use std::any::Any;
+use std::io::IoSlice;
+use std::io::IoSliceMut;
+use std::io::SeekFrom;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// Space taken in a directory, shared by all its mappings in a store, so modules mapping
+/// the same directory are limited together. It's `None` until a mapping with a quota counts it,
+/// because walking a host dir is slow.
+pub(crate) type SharedDirUsage = Arc<Mutex<Option<DirUsage>>>;
+
+/// Policy of a mapped directory with its usage, shared by all handles opened inside it.
+pub(crate) struct DirQuota {
+    policy: MappedDirPolicy,
+    usage: SharedDirUsage,
+}
+
+/// A mapped directory or its subdirectory that applies `MappedDirPolicy` on top of another one.
+pub(crate) struct LimitedDir {
+    inner: Box<dyn WasiDir>,
+    quota: Arc<DirQuota>,
+}
+
+/// A file opened in `LimitedDir`.
+pub(crate) struct LimitedFile {
+    inner: Box<dyn WasiFile>,
+    quota: Arc<DirQuota>,
+}
+
+impl DirQuota {
+    pub(crate) fn new(policy: MappedDirPolicy, usage: SharedDirUsage) -> Arc<Self> {
+        Arc::new(Self { policy, usage })
+    }
+
+    pub(crate) fn usage(&self) -> DirUsage {
+        self.lock().unwrap_or_default()
+    }
+
+    fn check_writable(&self) -> Result<(), Error> {
+        match self.policy.read_only {
+            true => Err(Errno::Rofs.into()),
+            false => Ok(()),
+        }
+    }
+
+    fn check(&self, bytes: u64, files: u64) -> Result<(), Error> {
+        let usage = self.usage();
+        let exceeds = |used: u64, added: u64, max: Option<u64>| {
+            added > 0 && max.is_some_and(|max| used.saturating_add(added) > max)
+        };
+
+        if exceeds(usage.bytes, bytes, self.policy.max_bytes)
+            || exceeds(usage.files, files, self.policy.max_files)
+        {
+            return Err(Errno::Dquot.into());
+        }
+
+        Ok(())
+    }
+
+    fn grow(&self, bytes: u64, files: u64) {
+        if let Some(usage) = self.lock().as_mut() {
+            usage.bytes = usage.bytes.saturating_add(bytes);
+            usage.files = usage.files.saturating_add(files);
+        }
+    }
+
+    fn shrink(&self, bytes: u64, files: u64) {
+        if let Some(usage) = self.lock().as_mut() {
+            usage.bytes = usage.bytes.saturating_sub(bytes);
+            usage.files = usage.files.saturating_sub(files);
+        }
+    }
+
+    /// Accounts a change of a file size.
+    fn resize(&self, old_size: u64, new_size: u64) {
+        match new_size >= old_size {
+            true => self.grow(new_size - old_size, 0),
+            false => self.shrink(old_size - new_size, 0),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<DirUsage>> {
+        self.usage.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Returns the total size of files and the number of entries in a host directory tree,
+/// symlinks are counted, but not followed.
+pub(crate) fn host_dir_usage(path: &Path) -> std::io::Result<DirUsage> {
+    let mut usage = DirUsage::default();
+    let mut dirs: Vec<PathBuf> = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let metadata = entry.path().symlink_metadata()?;
+            usage.files += 1;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else if metadata.is_file() {
+                usage.bytes += metadata.len();
+            }
+        }
+    }
+
+    Ok(usage)
+}
+
+impl LimitedDir {
+    pub(crate) fn new(inner: Box<dyn WasiDir>, quota: Arc<DirQuota>) -> Self {
+        Self { inner, quota }
+    }
+
+    /// Returns the inner directory of the destination, if it belongs to the same mapped directory.
+    fn same_mapping<'d>(&self, dir: &'d dyn WasiDir) -> Result<&'d dyn WasiDir, Error> {
+        dir.as_any()
+            .downcast_ref::<Self>()
+            .filter(|dir| Arc::ptr_eq(&dir.quota, &self.quota))
+            .map(|dir| dir.inner.as_ref())
+            .ok_or_else(|| Errno::Xdev.into())
+    }
+
+    async fn stat(&self, path: &str) -> Option<Filestat> {
+        self.inner.get_path_filestat(path, false).await.ok()
+    }
+}
+
+/// Size counted in the quota for a directory entry.
+fn counted_size(stat: &Filestat) -> u64 {
+    match stat.filetype {
+        FileType::RegularFile => stat.size,
+        _ => 0,
+    }
+}
+
+#[async_trait::async_trait]
+impl WasiDir for LimitedDir {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn open_file(
+        &self,
+        symlink_follow: bool,
+        path: &str,
+        oflags: OFlags,
+        read: bool,
+        write: bool,
+        fdflags: FdFlags,
+    ) -> Result<OpenResult, Error> {
+        if write || oflags.intersects(OFlags::CREATE | OFlags::TRUNCATE) {
+            self.quota.check_writable()?;
+        }
+
+        let existing = self
+            .inner
+            .get_path_filestat(path, symlink_follow)
+            .await
+            .ok();
+        let creates = existing.is_none() && oflags.contains(OFlags::CREATE);
+        if creates {
+            self.quota.check(0, 1)?;
+        }
+
+        let opened = self
+            .inner
+            .open_file(symlink_follow, path, oflags, read, write, fdflags)
+            .await?;
+
+        if creates {
+            self.quota.grow(0, 1);
+        }
+        if let Some(stat) = existing.filter(|_| oflags.contains(OFlags::TRUNCATE)) {
+            self.quota.shrink(counted_size(&stat), 0);
+        }
+
+        let quota = self.quota.clone();
+        let opened = match opened {
+            OpenResult::File(inner) => OpenResult::File(Box::new(LimitedFile { inner, quota })),
+            OpenResult::Dir(inner) => OpenResult::Dir(Box::new(LimitedDir { inner, quota })),
+        };
+
+        Ok(opened)
+    }
+
+    async fn create_dir(&self, path: &str) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        self.quota.check(0, 1)?;
+        self.inner.create_dir(path).await?;
+        self.quota.grow(0, 1);
+        Ok(())
+    }
+
+    async fn readdir(
+        &self,
+        cursor: ReaddirCursor,
+    ) -> Result<Box<dyn Iterator<Item = Result<ReaddirEntity, Error>> + Send>, Error> {
+        self.inner.readdir(cursor).await
+    }
+
+    async fn symlink(&self, old_path: &str, new_path: &str) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        self.quota.check(0, 1)?;
+        self.inner.symlink(old_path, new_path).await?;
+        self.quota.grow(0, 1);
+        Ok(())
+    }
+
+    async fn remove_dir(&self, path: &str) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        self.inner.remove_dir(path).await?;
+        self.quota.shrink(0, 1);
+        Ok(())
+    }
+
+    async fn unlink_file(&self, path: &str) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        let size = self.stat(path).await.map_or(0, |stat| counted_size(&stat));
+        self.inner.unlink_file(path).await?;
+        self.quota.shrink(size, 1);
+        Ok(())
+    }
+
+    async fn read_link(&self, path: &str) -> Result<PathBuf, Error> {
+        self.inner.read_link(path).await
+    }
+
+    async fn get_filestat(&self) -> Result<Filestat, Error> {
+        self.inner.get_filestat().await
+    }
+
+    async fn get_path_filestat(
+        &self,
+        path: &str,
+        follow_symlinks: bool,
+    ) -> Result<Filestat, Error> {
+        self.inner.get_path_filestat(path, follow_symlinks).await
+    }
+
+    async fn rename(
+        &self,
+        path: &str,
+        dest_dir: &dyn WasiDir,
+        dest_path: &str,
+    ) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        let dest_inner = self.same_mapping(dest_dir)?;
+        let source = self.stat(path).await;
+        let replaced = dest_inner.get_path_filestat(dest_path, false).await.ok();
+
+        self.inner.rename(path, dest_inner, dest_path).await?;
+
+        // renaming onto the same file doesn't free anything
+        let replaced = replaced.filter(|replaced| {
+            source.as_ref().map_or(true, |source| {
+                (source.device_id, source.inode) != (replaced.device_id, replaced.inode)
+            })
+        });
+        if let Some(replaced) = replaced {
+            self.quota.shrink(counted_size(&replaced), 1);
+        }
+
+        Ok(())
+    }
+
+    async fn hard_link(
+        &self,
+        path: &str,
+        target_dir: &dyn WasiDir,
+        target_path: &str,
+    ) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        let target_inner = self.same_mapping(target_dir)?;
+        // each link is counted as a separate file, because unlinking is accounted so
+        let size = self.stat(path).await.map_or(0, |stat| counted_size(&stat));
+        self.quota.check(size, 1)?;
+        self.inner
+            .hard_link(path, target_inner, target_path)
+            .await?;
+        self.quota.grow(size, 1);
+        Ok(())
+    }
+
+    async fn set_times(
+        &self,
+        path: &str,
+        atime: Option<SystemTimeSpec>,
+        mtime: Option<SystemTimeSpec>,
+        follow_symlinks: bool,
+    ) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        self.inner
+            .set_times(path, atime, mtime, follow_symlinks)
+            .await
+    }
+}
+
+impl LimitedFile {
+    async fn size(&self) -> Result<u64, Error> {
+        Ok(self.inner.get_filestat().await?.size)
+    }
+
+    /// Fails with `EDQUOT` if writing `bufs` at `offset` would exceed the quota.
+    fn check_write(&self, size: u64, offset: u64, bufs: &[IoSlice<'_>]) -> Result<(), Error> {
+        let len = bufs.iter().map(|buf| buf.len() as u64).sum::<u64>();
+        let growth = offset.saturating_add(len).saturating_sub(size);
+        self.quota.check(growth, 0)
+    }
+
+    async fn account_write(&self, old_size: u64) {
+        if let Ok(new_size) = self.size().await {
+            self.quota.resize(old_size, new_size);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl WasiFile for LimitedFile {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn get_filetype(&self) -> Result<FileType, Error> {
+        self.inner.get_filetype().await
+    }
+
+    #[cfg(unix)]
+    fn pollable(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        self.inner.pollable()
+    }
+
+    fn isatty(&self) -> bool {
+        self.inner.isatty()
+    }
+
+    async fn datasync(&self) -> Result<(), Error> {
+        self.inner.datasync().await
+    }
+
+    async fn sync(&self) -> Result<(), Error> {
+        self.inner.sync().await
+    }
+
+    async fn get_fdflags(&self) -> Result<FdFlags, Error> {
+        self.inner.get_fdflags().await
+    }
+
+    async fn set_fdflags(&mut self, flags: FdFlags) -> Result<(), Error> {
+        self.inner.set_fdflags(flags).await
+    }
+
+    async fn get_filestat(&self) -> Result<Filestat, Error> {
+        self.inner.get_filestat().await
+    }
+
+    async fn set_filestat_size(&self, new_size: u64) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        let size = self.size().await?;
+        self.quota.check(new_size.saturating_sub(size), 0)?;
+        self.inner.set_filestat_size(new_size).await?;
+        self.account_write(size).await;
+        Ok(())
+    }
+
+    async fn advise(&self, offset: u64, len: u64, advice: Advice) -> Result<(), Error> {
+        self.inner.advise(offset, len, advice).await
+    }
+
+    async fn set_times(
+        &self,
+        atime: Option<SystemTimeSpec>,
+        mtime: Option<SystemTimeSpec>,
+    ) -> Result<(), Error> {
+        self.quota.check_writable()?;
+        self.inner.set_times(atime, mtime).await
+    }
+
+    async fn read_vectored<'a>(&self, bufs: &mut [IoSliceMut<'a>]) -> Result<u64, Error> {
+        self.inner.read_vectored(bufs).await
+    }
+
+    async fn read_vectored_at<'a>(
+        &self,
+        bufs: &mut [IoSliceMut<'a>],
+        offset: u64,
+    ) -> Result<u64, Error> {
+        self.inner.read_vectored_at(bufs, offset).await
+    }
+
+    async fn write_vectored<'a>(&self, bufs: &[IoSlice<'a>]) -> Result<u64, Error> {
+        self.quota.check_writable()?;
+        let size = self.size().await?;
+        let position = match self.inner.get_fdflags().await?.contains(FdFlags::APPEND) {
+            true => size,
+            false => self.inner.seek(SeekFrom::Current(0)).await?,
+        };
+        self.check_write(size, position, bufs)?;
+
+        let written = self.inner.write_vectored(bufs).await;
+        self.account_write(size).await;
+        written
+    }
+
+    async fn write_vectored_at<'a>(&self, bufs: &[IoSlice<'a>], offset: u64) -> Result<u64, Error> {
+        self.quota.check_writable()?;
+        let size = self.size().await?;
+        self.check_write(size, offset, bufs)?;
+
+        let written = self.inner.write_vectored_at(bufs, offset).await;
+        self.account_write(size).await;
+        written
+    }
+
+    async fn seek(&self, pos: SeekFrom) -> Result<u64, Error> {
+        self.inner.seek(pos).await
+    }
+
+    async fn peek(&self, buf: &mut [u8]) -> Result<u64, Error> {
+        self.inner.peek(buf).await
+    }
+
+    fn num_ready_bytes(&self) -> Result<u64, Error> {
+        self.inner.num_ready_bytes()
+    }
+
+    async fn readable(&self) -> Result<(), Error> {
+        self.inner.readable().await
+    }
+
+    async fn writable(&self) -> Result<(), Error> {
+        self.inner.writable().await
+    }
+}
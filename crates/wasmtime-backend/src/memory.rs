@@ -19,6 +19,8 @@ use crate::WasmtimeWasmBackend;
 
 use marine_wasm_backend_traits::DelayedContextLifetime;
 use marine_wasm_backend_traits::Memory;
+use marine_wasm_backend_traits::RuntimeError;
+use marine_wasm_backend_traits::RuntimeResult;
 
 use it_memory_traits::MemoryAccessError;
 
@@ -50,6 +52,13 @@ impl Memory<WasmtimeWasmBackend> for WasmtimeMemory {
     fn size(&self, store: &mut WasmtimeContextMut<'_>) -> usize {
         self.memory.data_size(store)
     }
+
+    fn grow(&self, store: &mut WasmtimeContextMut<'_>, delta_pages: u32) -> RuntimeResult<()> {
+        self.memory
+            .grow(&mut store.inner, delta_pages as u64)
+            .map(|_| ())
+            .map_err(RuntimeError::Other)
+    }
 }
 
 impl it_memory_traits::MemoryReadable<DelayedContextLifetime<WasmtimeWasmBackend>>
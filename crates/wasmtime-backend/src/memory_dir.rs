@@ -0,0 +1,479 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine_wasm_backend_traits::MemoryDir;
+use marine_wasm_backend_traits::MemoryFile;
+use marine_wasm_backend_traits::MemoryFileError;
+use marine_wasm_backend_traits::MemoryNode;
+
+use wasi_common::dir::OpenResult;
+use wasi_common::dir::ReaddirCursor;
+use wasi_common::dir::ReaddirEntity;
+use wasi_common::file::Advice;
+use wasi_common::file::FdFlags;
+use wasi_common::file::FileType;
+use wasi_common::file::Filestat;
+use wasi_common::file::OFlags;
+use wasi_common::snapshots::preview_1::error::Errno;
+use wasi_common::Error;
+use wasi_common::ErrorExt;
+use wasi_common::SystemTimeSpec;
+use wasi_common::WasiDir;
+use wasi_common::WasiFile;
+
+use std::any::Any;
+use std::io::IoSlice;
+use std::io::IoSliceMut;
+use std::io::SeekFrom;
+use std::sync::Mutex;
+
+/// A directory from `MemoryDir` opened by a module.
+/// It keeps the whole chain of directories from the mapped one to resolve `..` in paths,
+/// while paths leading outside of the mapped directory are rejected.
+pub(crate) struct MemoryDirHandle {
+    dirs: Vec<MemoryDir>,
+}
+
+/// A file from `MemoryDir` opened by a module, each handle has its own position.
+pub(crate) struct MemoryFileHandle {
+    file: MemoryFile,
+    position: Mutex<u64>,
+    fdflags: FdFlags,
+}
+
+impl MemoryDirHandle {
+    pub(crate) fn new(root: MemoryDir) -> Self {
+        Self { dirs: vec![root] }
+    }
+
+    fn current(&self) -> &MemoryDir {
+        // the chain always contains at least the mapped directory
+        &self.dirs[self.dirs.len() - 1]
+    }
+
+    /// Returns directories up to the parent of the last path component and the component name,
+    /// or directories up to the target one if the path ends with a directory reference.
+    fn resolve(&self, path: &str) -> Result<(Vec<MemoryDir>, Option<String>), Error> {
+        if path.starts_with('/') {
+            return Err(Error::perm());
+        }
+
+        let mut dirs = self.dirs.clone();
+        let mut components = path
+            .split('/')
+            .filter(|component| !component.is_empty() && *component != ".")
+            .peekable();
+
+        while let Some(component) = components.next() {
+            if component == ".." {
+                if dirs.len() == 1 {
+                    return Err(Error::perm());
+                }
+                dirs.pop();
+                continue;
+            }
+
+            if components.peek().is_none() {
+                return Ok((dirs, Some(component.to_string())));
+            }
+
+            let next = match dirs[dirs.len() - 1].get(component) {
+                Some(MemoryNode::Dir(dir)) => dir,
+                Some(MemoryNode::File(_)) => return Err(Error::not_dir()),
+                None => return Err(Error::not_found()),
+            };
+            dirs.push(next);
+        }
+
+        Ok((dirs, None))
+    }
+}
+
+#[async_trait::async_trait]
+impl WasiDir for MemoryDirHandle {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn open_file(
+        &self,
+        _symlink_follow: bool,
+        path: &str,
+        oflags: OFlags,
+        _read: bool,
+        _write: bool,
+        fdflags: FdFlags,
+    ) -> Result<OpenResult, Error> {
+        let exclusive = oflags.contains(OFlags::CREATE | OFlags::EXCLUSIVE);
+        let (mut dirs, name) = self.resolve(path)?;
+        let name = match name {
+            Some(name) => name,
+            None if exclusive => return Err(Error::exist()),
+            None => return Ok(OpenResult::Dir(Box::new(Self { dirs }))),
+        };
+
+        let parent = &dirs[dirs.len() - 1];
+        match parent.get(&name) {
+            Some(_) if exclusive => Err(Error::exist()),
+            Some(MemoryNode::Dir(_)) if oflags.contains(OFlags::TRUNCATE) => {
+                Err(Errno::Isdir.into())
+            }
+            Some(MemoryNode::Dir(dir)) => {
+                dirs.push(dir);
+                Ok(OpenResult::Dir(Box::new(Self { dirs })))
+            }
+            Some(MemoryNode::File(_)) if oflags.contains(OFlags::DIRECTORY) => {
+                Err(Error::not_dir())
+            }
+            Some(MemoryNode::File(file)) => {
+                if oflags.contains(OFlags::TRUNCATE) {
+                    file.set_len(0).map_err(file_error)?;
+                }
+                Ok(OpenResult::File(Box::new(MemoryFileHandle::new(
+                    file, fdflags,
+                ))))
+            }
+            None if !oflags.contains(OFlags::CREATE) || oflags.contains(OFlags::DIRECTORY) => {
+                Err(Error::not_found())
+            }
+            None => {
+                let file = MemoryFile::default();
+                parent.insert(name, MemoryNode::File(file.clone()));
+                Ok(OpenResult::File(Box::new(MemoryFileHandle::new(
+                    file, fdflags,
+                ))))
+            }
+        }
+    }
+
+    async fn create_dir(&self, path: &str) -> Result<(), Error> {
+        let (dirs, name) = self.resolve(path)?;
+        let name = name.ok_or_else(Error::exist)?;
+        let parent = &dirs[dirs.len() - 1];
+        if parent.get(&name).is_some() {
+            return Err(Error::exist());
+        }
+
+        parent.insert(name, MemoryNode::Dir(MemoryDir::default()));
+        Ok(())
+    }
+
+    async fn readdir(
+        &self,
+        cursor: ReaddirCursor,
+    ) -> Result<Box<dyn Iterator<Item = Result<ReaddirEntity, Error>> + Send>, Error> {
+        let current = self.current();
+        let parent = match self.dirs.len() {
+            1 => current,
+            len => &self.dirs[len - 2],
+        };
+
+        let mut entries = vec![
+            (String::from("."), current.id(), FileType::Directory),
+            (String::from(".."), parent.id(), FileType::Directory),
+        ];
+        entries.extend(current.entries().into_iter().map(|(name, node)| {
+            let stat = node_filestat(&node);
+            (name, stat.inode, stat.filetype)
+        }));
+
+        let cursor = u64::from(cursor) as usize;
+        let entries = entries
+            .into_iter()
+            .enumerate()
+            .skip(cursor)
+            .map(|(id, (name, inode, filetype))| {
+                Ok(ReaddirEntity {
+                    next: ReaddirCursor::from(id as u64 + 1),
+                    inode,
+                    name,
+                    filetype,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    async fn remove_dir(&self, path: &str) -> Result<(), Error> {
+        let (dirs, name) = self.resolve(path)?;
+        let name = name.ok_or_else(Error::invalid_argument)?;
+        let parent = &dirs[dirs.len() - 1];
+        match parent.get(&name) {
+            Some(MemoryNode::Dir(dir)) if !dir.is_empty() => Err(Errno::Notempty.into()),
+            Some(MemoryNode::Dir(_)) => {
+                parent.remove(&name);
+                Ok(())
+            }
+            Some(MemoryNode::File(_)) => Err(Error::not_dir()),
+            None => Err(Error::not_found()),
+        }
+    }
+
+    async fn unlink_file(&self, path: &str) -> Result<(), Error> {
+        let (dirs, name) = self.resolve(path)?;
+        let name = name.ok_or_else(|| Error::from(Errno::Isdir))?;
+        let parent = &dirs[dirs.len() - 1];
+        match parent.get(&name) {
+            Some(MemoryNode::File(_)) => {
+                parent.remove(&name);
+                Ok(())
+            }
+            Some(MemoryNode::Dir(_)) => Err(Errno::Isdir.into()),
+            None => Err(Error::not_found()),
+        }
+    }
+
+    async fn get_filestat(&self) -> Result<Filestat, Error> {
+        Ok(node_filestat(&MemoryNode::Dir(self.current().clone())))
+    }
+
+    async fn get_path_filestat(
+        &self,
+        path: &str,
+        _follow_symlinks: bool,
+    ) -> Result<Filestat, Error> {
+        let (dirs, name) = self.resolve(path)?;
+        let parent = &dirs[dirs.len() - 1];
+        match name {
+            Some(name) => parent
+                .get(&name)
+                .map(|node| node_filestat(&node))
+                .ok_or_else(Error::not_found),
+            None => Ok(node_filestat(&MemoryNode::Dir(parent.clone()))),
+        }
+    }
+
+    async fn rename(
+        &self,
+        path: &str,
+        dest_dir: &dyn WasiDir,
+        dest_path: &str,
+    ) -> Result<(), Error> {
+        // moving between different mapped directories is like moving between devices
+        let dest_dir = dest_dir
+            .as_any()
+            .downcast_ref::<Self>()
+            .filter(|dest_dir| dest_dir.dirs[0].same_as(&self.dirs[0]))
+            .ok_or_else(|| Error::from(Errno::Xdev))?;
+
+        let (src_dirs, src_name) = self.resolve(path)?;
+        let src_name = src_name.ok_or_else(Error::invalid_argument)?;
+        let src_parent = &src_dirs[src_dirs.len() - 1];
+        let node = src_parent.get(&src_name).ok_or_else(Error::not_found)?;
+
+        let (dest_dirs, dest_name) = dest_dir.resolve(dest_path)?;
+        let dest_name = dest_name.ok_or_else(Error::invalid_argument)?;
+        let dest_parent = &dest_dirs[dest_dirs.len() - 1];
+
+        if let MemoryNode::Dir(dir) = &node {
+            // a directory can't be moved inside itself
+            if dest_dirs.iter().any(|parent| parent.same_as(dir)) {
+                return Err(Error::invalid_argument());
+            }
+        }
+
+        match (&node, dest_parent.get(&dest_name)) {
+            (MemoryNode::Dir(_), Some(MemoryNode::File(_))) => return Err(Error::not_dir()),
+            (MemoryNode::File(_), Some(MemoryNode::Dir(_))) => return Err(Errno::Isdir.into()),
+            (MemoryNode::Dir(_), Some(MemoryNode::Dir(dir))) if !dir.is_empty() => {
+                return Err(Errno::Notempty.into())
+            }
+            _ => {}
+        }
+
+        if src_parent.same_as(dest_parent) && src_name == dest_name {
+            return Ok(());
+        }
+
+        src_parent.remove(&src_name);
+        dest_parent.insert(dest_name, node);
+        Ok(())
+    }
+
+    async fn set_times(
+        &self,
+        _path: &str,
+        _atime: Option<SystemTimeSpec>,
+        _mtime: Option<SystemTimeSpec>,
+        _follow_symlinks: bool,
+    ) -> Result<(), Error> {
+        // timestamps aren't tracked in memory
+        Ok(())
+    }
+}
+
+impl MemoryFileHandle {
+    fn new(file: MemoryFile, fdflags: FdFlags) -> Self {
+        Self {
+            file,
+            position: Mutex::new(0),
+            fdflags,
+        }
+    }
+
+    fn position(&self) -> std::sync::MutexGuard<'_, u64> {
+        self.position.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn read_at(&self, bufs: &mut [IoSliceMut<'_>], mut offset: u64) -> u64 {
+        let start = offset;
+        for buf in bufs {
+            let read = self.file.read_at(offset, buf);
+            offset += read as u64;
+            if read < buf.len() {
+                break;
+            }
+        }
+
+        offset - start
+    }
+
+    fn write_at(&self, bufs: &[IoSlice<'_>], mut offset: u64) -> Result<u64, Error> {
+        let start = offset;
+        for buf in bufs {
+            self.file.write_at(offset, buf).map_err(file_error)?;
+            offset += buf.len() as u64;
+        }
+
+        Ok(offset - start)
+    }
+}
+
+#[async_trait::async_trait]
+impl WasiFile for MemoryFileHandle {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn get_filetype(&self) -> Result<FileType, Error> {
+        Ok(FileType::RegularFile)
+    }
+
+    async fn get_fdflags(&self) -> Result<FdFlags, Error> {
+        Ok(self.fdflags)
+    }
+
+    async fn set_fdflags(&mut self, fdflags: FdFlags) -> Result<(), Error> {
+        self.fdflags = fdflags;
+        Ok(())
+    }
+
+    async fn get_filestat(&self) -> Result<Filestat, Error> {
+        Ok(node_filestat(&MemoryNode::File(self.file.clone())))
+    }
+
+    async fn set_filestat_size(&self, size: u64) -> Result<(), Error> {
+        self.file.set_len(size).map_err(file_error)
+    }
+
+    async fn advise(&self, _offset: u64, _len: u64, _advice: Advice) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn set_times(
+        &self,
+        _atime: Option<SystemTimeSpec>,
+        _mtime: Option<SystemTimeSpec>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn read_vectored<'a>(&self, bufs: &mut [IoSliceMut<'a>]) -> Result<u64, Error> {
+        let mut position = self.position();
+        let read = self.read_at(bufs, *position);
+        *position += read;
+        Ok(read)
+    }
+
+    async fn read_vectored_at<'a>(
+        &self,
+        bufs: &mut [IoSliceMut<'a>],
+        offset: u64,
+    ) -> Result<u64, Error> {
+        Ok(self.read_at(bufs, offset))
+    }
+
+    async fn write_vectored<'a>(&self, bufs: &[IoSlice<'a>]) -> Result<u64, Error> {
+        let mut position = self.position();
+        if self.fdflags.contains(FdFlags::APPEND) {
+            *position = self.file.len();
+        }
+        let written = self.write_at(bufs, *position)?;
+        *position += written;
+        Ok(written)
+    }
+
+    async fn write_vectored_at<'a>(&self, bufs: &[IoSlice<'a>], offset: u64) -> Result<u64, Error> {
+        self.write_at(bufs, offset)
+    }
+
+    async fn seek(&self, pos: SeekFrom) -> Result<u64, Error> {
+        let mut position = self.position();
+        let new_position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(delta) => position.checked_add_signed(delta),
+            SeekFrom::End(delta) => self.file.len().checked_add_signed(delta),
+        };
+
+        *position = new_position.ok_or_else(Error::invalid_argument)?;
+        Ok(*position)
+    }
+
+    async fn peek(&self, buf: &mut [u8]) -> Result<u64, Error> {
+        let position = self.position();
+        Ok(self.file.read_at(*position, buf) as u64)
+    }
+
+    fn num_ready_bytes(&self) -> Result<u64, Error> {
+        let position = self.position();
+        Ok(self.file.len().saturating_sub(*position))
+    }
+
+    async fn readable(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn writable(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Reports a file exceeding the size limit as `EFBIG` and a failed allocation as `ENOSPC`.
+fn file_error(error: MemoryFileError) -> Error {
+    match error {
+        MemoryFileError::TooBig(_) => Error::too_big(),
+        MemoryFileError::OutOfMemory(_) => Errno::Nospc.into(),
+    }
+}
+
+fn node_filestat(node: &MemoryNode) -> Filestat {
+    let (inode, filetype, size) = match node {
+        MemoryNode::File(file) => (file.id(), FileType::RegularFile, file.len()),
+        MemoryNode::Dir(dir) => (dir.id(), FileType::Directory, 0),
+    };
+
+    Filestat {
+        device_id: 0,
+        inode,
+        filetype,
+        nlink: 1,
+        size,
+        atim: None,
+        mtim: None,
+        ctim: None,
+    }
+}
@@ -32,10 +32,8 @@ pub struct WasmtimeModule {
     pub(crate) inner: wasmtime::Module,
 }
 
-impl Module<WasmtimeWasmBackend> for WasmtimeModule {
-    fn new(store: &mut WasmtimeStore, wasm: &[u8]) -> ModuleCreationResult<Self> {
-        let module = wasmtime::Module::new(store.inner.engine(), wasm)
-            .map_err(ModuleCreationError::FailedToCompileWasm)?;
+impl WasmtimeModule {
+    fn from_compiled(module: wasmtime::Module, wasm: &[u8]) -> ModuleCreationResult<Self> {
         let custom_sections =
             custom_sections(wasm) // TODO: avoid double module parsing
                 .map_err(ModuleCreationError::FailedToExtractCustomSections)?;
@@ -45,6 +43,28 @@ impl Module<WasmtimeWasmBackend> for WasmtimeModule {
             inner: module,
         })
     }
+}
+
+impl Module<WasmtimeWasmBackend> for WasmtimeModule {
+    fn new(store: &mut WasmtimeStore, wasm: &[u8]) -> ModuleCreationResult<Self> {
+        let module = wasmtime::Module::new(store.inner.engine(), wasm)
+            .map_err(ModuleCreationError::FailedToCompileWasm)?;
+
+        Self::from_compiled(module, wasm)
+    }
+
+    fn new_cached(store: &mut WasmtimeStore, wasm: &[u8]) -> ModuleCreationResult<Self> {
+        let module_cache = match &store.module_cache {
+            Some(module_cache) => module_cache,
+            None => return Self::new(store, wasm),
+        };
+
+        let module = module_cache
+            .get_or_compile(store.inner.engine(), wasm)
+            .map_err(ModuleCreationError::FailedToCompileWasm)?;
+
+        Self::from_compiled(module, wasm)
+    }
 
     fn custom_sections(&self, name: &str) -> &[Vec<u8>] {
         self.custom_sections
@@ -65,7 +85,10 @@ impl Module<WasmtimeWasmBackend> for WasmtimeModule {
                 .instantiate_async(&mut store.inner, &self.inner)
                 .await
                 .map_err(inspect_instantiation_error)?; // TODO add detail
-            Ok(WasmtimeInstance { inner: instance })
+            Ok(WasmtimeInstance {
+                inner: instance,
+                wasi_id: imports.wasi_id,
+            })
         }
         .boxed()
     }
@@ -0,0 +1,256 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use sha2::Digest;
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+/// Where compiled modules are kept between compilations.
+#[derive(Clone, Debug)]
+pub enum ModuleCacheLocation {
+    /// Compiled modules are kept in memory and shared by all stores created from the same backend.
+    /// At most `max_modules` modules are kept, the least recently used one is evicted first.
+    InMemory { max_modules: usize },
+
+    /// Compiled modules are serialized into files in the given directory.
+    /// The directory must be trusted: its files are loaded as native code without validation.
+    Directory(PathBuf),
+}
+
+/// Counters of the module cache usage since the backend was created.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    /// Number of modules taken from the cache instead of being compiled.
+    pub hits: u64,
+    /// Number of modules compiled because they weren't found in the cache.
+    pub misses: u64,
+    /// Number of modules evicted from the in-memory cache to free a place for others.
+    pub evictions: u64,
+    /// Number of modules currently kept in the in-memory cache, always 0 for a directory.
+    pub cached_modules: usize,
+}
+
+/// A cache of compiled modules keyed by the hash of Wasm bytes.
+/// Keys also contain a fingerprint of the engine config, so a module compiled
+/// with different settings is never reused.
+pub(crate) struct ModuleCache {
+    storage: CacheStorage,
+    engine_fingerprint: String,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+enum CacheStorage {
+    InMemory(Mutex<LruModules>),
+    Directory(PathBuf),
+}
+
+/// Compiled modules with the logical time of their last use.
+struct LruModules {
+    modules: HashMap<String, (wasmtime::Module, u64)>,
+    max_modules: usize,
+    clock: u64,
+    evictions: u64,
+}
+
+impl ModuleCache {
+    pub(crate) fn new(location: ModuleCacheLocation, engine: &wasmtime::Engine) -> Self {
+        let storage = match location {
+            ModuleCacheLocation::InMemory { max_modules } => {
+                CacheStorage::InMemory(Mutex::new(LruModules::new(max_modules)))
+            }
+            ModuleCacheLocation::Directory(path) => CacheStorage::Directory(path),
+        };
+
+        Self {
+            storage,
+            engine_fingerprint: engine_fingerprint(engine),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ModuleCacheStats {
+        let (evictions, cached_modules) = match &self.storage {
+            CacheStorage::InMemory(modules) => {
+                let modules = lock(modules);
+                (modules.evictions, modules.modules.len())
+            }
+            CacheStorage::Directory(_) => (0, 0),
+        };
+
+        ModuleCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions,
+            cached_modules,
+        }
+    }
+
+    /// Returns a compiled module from the cache, or compiles and caches it if there is no such.
+    /// Cache failures aren't fatal: the module is just compiled again.
+    pub(crate) fn get_or_compile(
+        &self,
+        engine: &wasmtime::Engine,
+        wasm: &[u8],
+    ) -> wasmtime::Result<wasmtime::Module> {
+        let key = self.cache_key(wasm);
+
+        match &self.storage {
+            CacheStorage::InMemory(modules) => {
+                if let Some(module) = lock(modules).get(&key) {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(module.clone());
+                }
+
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                let module = wasmtime::Module::new(engine, wasm)?;
+                lock(modules).insert(key, module.clone());
+                Ok(module)
+            }
+            CacheStorage::Directory(dir) => {
+                let path = dir.join(format!("{key}.cwasm"));
+                if path.exists() {
+                    // Safety: files in the cache directory are produced by Module::serialize,
+                    // the directory is expected to be trusted.
+                    match unsafe { wasmtime::Module::deserialize_file(engine, &path) } {
+                        Ok(module) => {
+                            self.hits.fetch_add(1, Ordering::Relaxed);
+                            return Ok(module);
+                        }
+                        Err(e) => log::warn!("failed to load cached module {:?}: {}", path, e),
+                    }
+                }
+
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                let module = wasmtime::Module::new(engine, wasm)?;
+                if let Err(e) = store_module(dir, &path, &module) {
+                    log::warn!("failed to cache compiled module to {:?}: {}", path, e);
+                }
+
+                Ok(module)
+            }
+        }
+    }
+
+    fn cache_key(&self, wasm: &[u8]) -> String {
+        let wasm_hash = hex::encode(sha2::Sha256::digest(wasm));
+        format!("{}-{}", wasm_hash, self.engine_fingerprint)
+    }
+}
+
+impl LruModules {
+    fn new(max_modules: usize) -> Self {
+        Self {
+            modules: HashMap::new(),
+            max_modules,
+            clock: 0,
+            evictions: 0,
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<wasmtime::Module> {
+        self.clock += 1;
+        let (module, last_used) = self.modules.get_mut(key)?;
+        *last_used = self.clock;
+
+        Some(module.clone())
+    }
+
+    fn insert(&mut self, key: String, module: wasmtime::Module) {
+        if self.max_modules == 0 {
+            return;
+        }
+
+        while self.modules.len() >= self.max_modules && !self.modules.contains_key(&key) {
+            let least_recently_used = self
+                .modules
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone());
+
+            match least_recently_used {
+                Some(evicted_key) => self.modules.remove(&evicted_key),
+                None => break,
+            };
+            self.evictions += 1;
+        }
+
+        self.clock += 1;
+        self.modules.insert(key, (module, self.clock));
+    }
+}
+
+fn store_module(dir: &Path, path: &Path, module: &wasmtime::Module) -> anyhow::Result<()> {
+    let bytes = module.serialize()?;
+    std::fs::create_dir_all(dir)?;
+
+    // write to a temporary file first, so a concurrent reader never sees a partial file
+    let mut tmp_file = tempfile::NamedTempFile::new_in(dir)?;
+    std::io::Write::write_all(&mut tmp_file, &bytes)?;
+    tmp_file.persist(path)?;
+
+    Ok(())
+}
+
+/// Hashes everything that affects compiled code: the compiler settings and the wasmtime version.
+/// SHA-256 is used, because unlike `DefaultHasher` it's stable across Rust releases,
+/// so a directory cache stays valid after the host is rebuilt.
+fn engine_fingerprint(engine: &wasmtime::Engine) -> String {
+    let mut hasher = Sha256Hasher(sha2::Sha256::new());
+    engine.precompile_compatibility_hash().hash(&mut hasher);
+
+    // the compatibility hash doesn't cover the wasmtime version, but precompiled artifacts do
+    match engine.precompile_module(EMPTY_MODULE) {
+        Ok(artifact) => hasher.0.update(artifact),
+        Err(e) => log::warn!("failed to precompile the fingerprint module: {}", e),
+    }
+
+    hex::encode(hasher.0.finalize())
+}
+
+/// The smallest valid Wasm module: a magic number and a version.
+const EMPTY_MODULE: &[u8] = b"\0asm\x01\0\0\0";
+
+/// Feeds `Hash` implementations into SHA-256.
+struct Sha256Hasher(sha2::Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn finish(&self) -> u64 {
+        let digest = self.0.clone().finalize();
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        u64::from_le_bytes(prefix)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    // the cache stays consistent even if a thread panicked while holding the lock
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
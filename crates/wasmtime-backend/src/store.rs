@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use crate::ModuleCache;
 use crate::StoreState;
 use crate::WasmtimeWasmBackend;
 
@@ -26,12 +27,17 @@ use wasmtime::AsContext as WasmtimeAsContext;
 use wasmtime::AsContextMut as WasmtimeAsContextMut;
 
 use std::default::Default;
+use std::sync::Arc;
+
+/// Amount of fuel injected at once when a store has unlimited fuel.
+const UNLIMITED_FUEL_CHUNK: u64 = u32::MAX as u64;
 
 /// A type that is used to store resources allocated by runtime. It includes memories, functions,
 /// tables, globals and so on. More information here: https://webassembly.github.io/spec/core/exec/runtime.html#store.
 /// Because of that, most of the methods in API require a handle to store to function.
 pub struct WasmtimeStore {
     pub(crate) inner: wasmtime::Store<StoreState>,
+    pub(crate) module_cache: Option<Arc<ModuleCache>>,
 }
 
 /// Temporary immutable handle to `Store`, used to interact with stored data.
@@ -48,13 +54,22 @@ pub struct WasmtimeContextMut<'s> {
 pub struct MemoryLimiter {
     remaining_memory: u64,
     allocation_stats: MemoryAllocationStats,
+    /// Limit of the module which code is executed now.
+    module_limit: Option<ModuleMemoryLimit>,
 }
 
 impl Store<WasmtimeWasmBackend> for WasmtimeStore {
     fn new(backend: &WasmtimeWasmBackend) -> Self {
-        let mut store = wasmtime::Store::new(&backend.engine, <_>::default());
+        let state = StoreState {
+            deterministic: backend.deterministic,
+            ..<_>::default()
+        };
+        let mut store = wasmtime::Store::new(&backend.engine, state);
         store.epoch_deadline_async_yield_and_update(1);
-        Self { inner: store }
+        Self {
+            inner: store,
+            module_cache: backend.module_cache.clone(),
+        }
     }
 
     fn set_total_memory_limit(&mut self, total_memory_limit: u64) {
@@ -70,6 +85,40 @@ impl Store<WasmtimeWasmBackend> for WasmtimeStore {
     fn clear_allocation_stats(&mut self) {
         self.inner.data_mut().limits.allocation_stats = MemoryAllocationStats::default();
     }
+
+    fn set_fuel(&mut self, fuel: Option<u64>) -> RuntimeResult<()> {
+        if self.inner.fuel_consumed().is_none() {
+            return Err(RuntimeError::FuelNotSupported);
+        }
+
+        // Wasmtime only allows adding fuel, so the unused fuel is consumed first.
+        // consume_fuel(0) fails only if the fuel is already overdrawn after a trap.
+        if let Ok(remaining) = self.inner.consume_fuel(0) {
+            self.inner
+                .consume_fuel(remaining)
+                .map_err(RuntimeError::Other)?;
+        }
+
+        let fuel = match fuel {
+            Some(fuel) => {
+                self.inner.out_of_fuel_trap();
+                fuel
+            }
+            None => {
+                // Adding a huge amount of fuel at once overflows the Wasmtime fuel counters,
+                // so the unlimited fuel is injected by chunks instead.
+                self.inner
+                    .out_of_fuel_async_yield(u64::MAX, UNLIMITED_FUEL_CHUNK);
+                UNLIMITED_FUEL_CHUNK
+            }
+        };
+
+        self.inner.add_fuel(fuel).map_err(RuntimeError::Other)
+    }
+
+    fn consumed_fuel(&self) -> Option<u64> {
+        self.inner.fuel_consumed()
+    }
 }
 
 impl MemoryLimiter {
@@ -77,6 +126,7 @@ impl MemoryLimiter {
         Self {
             remaining_memory: max_total_memory,
             allocation_stats: <_>::default(),
+            module_limit: None,
         }
     }
 
@@ -102,6 +152,15 @@ impl ResourceLimiter for MemoryLimiter {
         desired: usize,
         _maximum: Option<usize>,
     ) -> wasmtime::Result<bool> {
+        if let Some(limit) = &self.module_limit {
+            // desired is the new size of the whole memory, and a module has only one memory
+            if desired as u64 > limit.max_memory {
+                self.allocation_stats.module_limit_exceeded = Some(limit.module_name.clone());
+                self.count_allocation_reject();
+                return Ok(false);
+            }
+        }
+
         let grow_size = (desired - current) as u64;
         Ok(self.try_alloc(grow_size))
     }
@@ -119,7 +178,14 @@ impl ResourceLimiter for MemoryLimiter {
 
 impl<'c> Context<WasmtimeWasmBackend> for WasmtimeContext<'c> {}
 
-impl<'c> ContextMut<WasmtimeWasmBackend> for WasmtimeContextMut<'c> {}
+impl<'c> ContextMut<WasmtimeWasmBackend> for WasmtimeContextMut<'c> {
+    fn set_module_memory_limit(
+        &mut self,
+        limit: Option<ModuleMemoryLimit>,
+    ) -> Option<ModuleMemoryLimit> {
+        std::mem::replace(&mut self.inner.data_mut().limits.module_limit, limit)
+    }
+}
 
 impl AsContext<WasmtimeWasmBackend> for WasmtimeStore {
     fn as_context(&self) -> WasmtimeContext<'_> {
@@ -86,8 +86,11 @@ pub(crate) fn fn_ty_to_sig(ty: &wasmtime::FuncType) -> FuncSig {
 }
 
 pub(crate) fn inspect_call_error(e: anyhow::Error) -> RuntimeError {
-    if e.downcast_ref::<wasmtime::Trap>().is_some() {
-        RuntimeError::Trap(e)
+    if let Some(trap) = e.downcast_ref::<wasmtime::Trap>() {
+        match trap {
+            wasmtime::Trap::OutOfFuel => RuntimeError::OutOfFuel,
+            _ => RuntimeError::Trap(e),
+        }
     } else {
         match e.downcast::<UserError>() {
             Ok(e) => RuntimeError::UserError(e),
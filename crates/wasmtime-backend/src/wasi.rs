@@ -21,16 +21,63 @@ use crate::WasmtimeWasmBackend;
 
 use marine_wasm_backend_traits::prelude::*;
 
+use crate::deterministic::virtual_clocks;
+use crate::deterministic::VirtualRng;
+use crate::memory_dir::MemoryDirHandle;
+use crate::limited_dir::host_dir_usage;
+use crate::limited_dir::DirQuota;
+use crate::limited_dir::LimitedDir;
+use crate::limited_dir::SharedDirUsage;
+
 use wasmtime_wasi::ambient_authority;
-use wasmtime_wasi::WasiCtxBuilder;
+use wasmtime_wasi::WasiCtx;
+use wasi_common::pipe::WritePipe;
+use wasi_common::table::Table;
+use wasi_common::WasiDir;
 use anyhow::anyhow;
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
-use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Preopened directories get file descriptors right after stdin, stdout and stderr.
+const FIRST_PREOPENED_FD: u32 = 3;
+
+/// WASI context doesn't expose the biggest open file descriptor, so descriptors are looked up
+/// until this many absent ones in a row are met.
+const FD_LOOKUP_WINDOW: u32 = 1024;
 
 pub struct WasmtimeWasi {}
 
+/// WASI context of one module with the parameters it was built from,
+/// because the context itself doesn't allow to read them back.
+pub(crate) struct WasiContext {
+    pub(crate) ctx: wasmtime_wasi::WasiCtx,
+    args: Vec<String>,
+    envs: Vec<Vec<u8>>,
+    preopened_dirs: Vec<MappedDir>,
+}
+
+/// A preopened directory with the quota tracking its usage.
+struct MappedDir {
+    info: PreopenedDir,
+    quota: Arc<DirQuota>,
+}
+
+/// Identifies a directory mapped by several modules of a store to share its usage.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum MappedDirKey {
+    /// A canonical host path.
+    Host(PathBuf),
+    Memory(u64),
+}
+
+enum MappedDirSource {
+    Host(PathBuf),
+    Memory(MemoryDir),
+}
+
 impl WasiImplementation<WasmtimeWasmBackend> for WasmtimeWasi {
     fn register_in_linker(
         store: &mut WasmtimeContextMut<'_>,
@@ -41,42 +88,137 @@ impl WasiImplementation<WasmtimeWasmBackend> for WasmtimeWasi {
             args,
             envs,
             mapped_dirs,
+            memory_dirs,
+            dir_policies,
+            deterministic_env,
+            output_capture,
         } = parameters;
 
-        let wasi_ctx_builder = WasiCtxBuilder::new();
+        let envs = envs.into_iter().collect::<Vec<_>>();
+        let mapped_dirs = mapped_dirs
+            .into_iter()
+            .map(|(guest_path, host_path)| (guest_path, MappedDirSource::Host(host_path)))
+            // in-memory directories go after the host ones
+            .chain(
+                memory_dirs
+                    .into_iter()
+                    .map(|(guest_path, dir)| (guest_path, MappedDirSource::Memory(dir))),
+            )
+            .collect::<Vec<_>>();
+
+        let deterministic = store.inner.data().deterministic;
+        let mut ctx = match (deterministic, deterministic_env) {
+            // a module mustn't observe the host time and randomness, even if it's not seeded
+            (true, env) => new_deterministic_ctx(env.unwrap_or_default()),
+            (false, None) => new_host_ctx(),
+            (false, Some(_)) => return Err(WasiError::NondeterministicBackend),
+        };
         // process and add CLI arguments to wasi context
-        let wasi_ctx_builder = populate_args(wasi_ctx_builder, args)?;
+        populate_args(&mut ctx, &args)?;
         // process and add environment variables to wasi context
-        let wasi_ctx_builder = populate_envs(wasi_ctx_builder, envs)?;
+        populate_envs(&mut ctx, &envs)?;
         // add mapped directories to wasi context, do not create dirs
-        let wasi_ctx_builder = populate_mapped_dirs(wasi_ctx_builder, mapped_dirs)?;
-        // give access to runner's stdout and stderr, but not stdin
-        let mut wasi_ctx_builder = populate_stdio(wasi_ctx_builder);
+        let dir_usages = &mut store.inner.data_mut().dir_usages;
+        let preopened_dirs = populate_mapped_dirs(&ctx, mapped_dirs, &dir_policies, dir_usages)?;
+        // give access to runner's stdout and stderr or to the capture buffers, but not stdin
+        populate_stdio(&ctx, output_capture);
 
-        let wasi_ctx = wasi_ctx_builder.build();
+        let wasi_ctx = WasiContext {
+            ctx,
+            args,
+            envs: envs
+                .into_iter()
+                .map(|(name, value)| format!("{name}={value}").into_bytes())
+                .collect(),
+            preopened_dirs,
+        };
         add_wasi_to_linker(store, linker, wasi_ctx)
     }
 
     fn get_wasi_state<'s>(
-        _instance: &'s mut <WasmtimeWasmBackend as WasmBackend>::Instance,
+        store: &impl AsContext<WasmtimeWasmBackend>,
+        instance: &'s mut <WasmtimeWasmBackend as WasmBackend>::Instance,
     ) -> Box<dyn WasiState + 's> {
-        // TODO give actual state
-        Box::new(WasmtimeWasiState {})
+        let store = store.as_context();
+        let state = instance
+            .wasi_id
+            .map(|id| WasmtimeWasiState::new(&store.inner.data().wasi[id]))
+            .unwrap_or_default();
+
+        Box::new(state)
     }
 }
 
-pub struct WasmtimeWasiState {}
+#[derive(Default)]
+pub struct WasmtimeWasiState {
+    args: Vec<String>,
+    envs: Vec<Vec<u8>>,
+    preopened_dirs: Vec<PreopenedDir>,
+    open_fds: Vec<OpenFd>,
+}
+
+impl WasmtimeWasiState {
+    fn new(context: &WasiContext) -> Self {
+        let table = context.ctx.table();
+        let preopened_fds =
+            FIRST_PREOPENED_FD..FIRST_PREOPENED_FD + context.preopened_dirs.len() as u32;
+
+        let mut open_fds = Vec::new();
+        let mut absent_in_row = 0;
+        let mut fd = 0;
+        while absent_in_row < FD_LOOKUP_WINDOW {
+            if table.contains_key(fd) {
+                let kind = match fd {
+                    0..=2 => OpenFdKind::Stdio,
+                    fd if preopened_fds.contains(&fd) => OpenFdKind::PreopenedDir,
+                    _ => OpenFdKind::Opened,
+                };
+                open_fds.push(OpenFd { fd, kind });
+                absent_in_row = 0;
+            } else {
+                absent_in_row += 1;
+            }
+            fd += 1;
+        }
+
+        Self {
+            args: context.args.clone(),
+            envs: context.envs.clone(),
+            preopened_dirs: context
+                .preopened_dirs
+                .iter()
+                .map(|dir| PreopenedDir {
+                    usage: dir.info.policy.has_quota().then(|| dir.quota.usage()),
+                    ..dir.info.clone()
+                })
+                .collect(),
+            open_fds,
+        }
+    }
+}
 
 impl WasiState for WasmtimeWasiState {
     fn envs(&self) -> &[Vec<u8>] {
-        &[]
+        &self.envs
+    }
+
+    fn args(&self) -> &[String] {
+        &self.args
+    }
+
+    fn preopened_dirs(&self) -> &[PreopenedDir] {
+        &self.preopened_dirs
+    }
+
+    fn open_fds(&self) -> &[OpenFd] {
+        &self.open_fds
     }
 }
 
 fn add_wasi_to_linker(
     store: &mut WasmtimeContextMut<'_>,
     linker: &mut WasmtimeImports,
-    wasi_ctx: wasmtime_wasi::WasiCtx,
+    wasi_ctx: WasiContext,
 ) -> Result<(), WasiError> {
     // wasmtime-wasi gets its context from ImportCallContext<T>, which can hold any user info
     // the only convenient method is to be provided with a closure that extracts context
@@ -84,59 +226,149 @@ fn add_wasi_to_linker(
     // So, here each module has its own wasi context which is stored in a vector in store.
     let id = store.inner.data().wasi.len();
     wasmtime_wasi::add_to_linker(&mut linker.linker, move |s: &mut StoreState| {
-        &mut s.wasi[id]
+        &mut s.wasi[id].ctx
     })
     .map_err(|e| WasiError::EngineWasiError(anyhow!(e)))?;
 
     store.inner.data_mut().wasi.push(wasi_ctx);
+    linker.wasi_id = Some(id);
 
     Ok(())
 }
 
-fn populate_args(
-    mut builder: WasiCtxBuilder,
-    args: Vec<String>,
-) -> Result<WasiCtxBuilder, WasiError> {
-    builder
-        .args(&args)
-        .map_err(|_| WasiError::TooLargeArgsArray)?;
+fn new_host_ctx() -> WasiCtx {
+    WasiCtx::new(
+        wasmtime_wasi::random_ctx(),
+        wasmtime_wasi::clocks_ctx(),
+        wasmtime_wasi::sched_ctx(),
+        Table::new(),
+    )
+}
 
-    Ok(builder)
+fn new_deterministic_ctx(env: DeterministicEnv) -> WasiCtx {
+    WasiCtx::new(
+        Box::new(VirtualRng::new(env.clone())),
+        virtual_clocks(env),
+        wasmtime_wasi::sched_ctx(),
+        Table::new(),
+    )
+}
+
+fn populate_args(ctx: &mut WasiCtx, args: &[String]) -> Result<(), WasiError> {
+    for arg in args {
+        ctx.push_arg(arg)
+            .map_err(|_| WasiError::TooLargeArgsArray)?;
+    }
+
+    Ok(())
 }
 
 fn populate_mapped_dirs(
-    builder: WasiCtxBuilder,
-    mapped_dirs: HashMap<String, PathBuf>,
-) -> Result<WasiCtxBuilder, WasiError> {
-    mapped_dirs.iter().try_fold(
-        builder,
-        |mut builder, (guest_name, host_path)| -> Result<_, WasiError> {
-            let host_dir = wasmtime_wasi::Dir::open_ambient_dir(host_path, ambient_authority())?;
-            let guest_path = Path::new(&guest_name);
-            builder
-                .preopened_dir(host_dir, guest_path)
-                .map_err(|e| WasiError::EngineWasiError(anyhow!(e)))?;
-
-            Ok(builder)
-        },
-    )
+    ctx: &WasiCtx,
+    mapped_dirs: Vec<(String, MappedDirSource)>,
+    dir_policies: &HashMap<String, MappedDirPolicy>,
+    dir_usages: &mut HashMap<MappedDirKey, SharedDirUsage>,
+) -> Result<Vec<MappedDir>, WasiError> {
+    let mut preopened_dirs = Vec::with_capacity(mapped_dirs.len());
+    for ((guest_path, source), fd) in mapped_dirs.into_iter().zip(FIRST_PREOPENED_FD..) {
+        let mut policy = dir_policies.get(&guest_path).cloned().unwrap_or_default();
+        let (dir, key): (Box<dyn WasiDir>, _) = match &source {
+            MappedDirSource::Host(host_path) => {
+                let host_dir =
+                    wasmtime_wasi::Dir::open_ambient_dir(host_path, ambient_authority())?;
+                let key = MappedDirKey::Host(std::fs::canonicalize(host_path)?);
+                (
+                    Box::new(wasmtime_wasi::dir::Dir::from_cap_std(host_dir)),
+                    key,
+                )
+            }
+            MappedDirSource::Memory(dir) => {
+                // in-memory directories take the host memory, so they are always limited
+                policy.max_bytes = policy.max_bytes.or(Some(DEFAULT_MEMORY_DIR_MAX_BYTES));
+                let key = MappedDirKey::Memory(dir.id());
+                (Box::new(MemoryDirHandle::new(dir.clone())), key)
+            }
+        };
+
+        // every mapping tracks changes of the usage, but only ones with quotas count it first
+        let usage = dir_usages.entry(key).or_default().clone();
+        if policy.has_quota() {
+            let mut usage = usage.lock().unwrap_or_else(|e| e.into_inner());
+            if usage.is_none() {
+                *usage = Some(match &source {
+                    MappedDirSource::Host(host_path) => host_dir_usage(host_path)?,
+                    MappedDirSource::Memory(dir) => dir.usage(),
+                });
+            }
+        }
+
+        let quota = DirQuota::new(policy.clone(), usage);
+        let dir = LimitedDir::new(dir, quota.clone());
+        ctx.push_preopened_dir(Box::new(dir), Path::new(&guest_path))
+            .map_err(|e| WasiError::EngineWasiError(anyhow!(e)))?;
+
+        let host_path = match source {
+            MappedDirSource::Host(host_path) => Some(host_path),
+            MappedDirSource::Memory(_) => None,
+        };
+        preopened_dirs.push(MappedDir {
+            info: PreopenedDir {
+                fd,
+                guest_path,
+                host_path,
+                policy,
+                usage: None,
+            },
+            quota,
+        });
+    }
+
+    Ok(preopened_dirs)
 }
 
-fn populate_envs(
-    mut builder: WasiCtxBuilder,
-    envs: HashMap<String, String>,
-) -> Result<WasiCtxBuilder, WasiError> {
-    let envs = envs.into_iter().collect::<Vec<_>>();
+fn populate_envs(ctx: &mut WasiCtx, envs: &[(String, String)]) -> Result<(), WasiError> {
+    for (name, value) in envs {
+        ctx.push_env(name, value)
+            .map_err(|_| WasiError::TooLargeEnvsArray)?;
+    }
 
-    builder
-        .envs(&envs)
-        .map_err(|_| WasiError::TooLargeEnvsArray)?;
+    Ok(())
+}
+
+fn populate_stdio(ctx: &WasiCtx, output_capture: Option<OutputCapture>) {
+    match output_capture {
+        Some(capture) => {
+            ctx.set_stdout(Box::new(WritePipe::new(CaptureWriter {
+                capture: capture.clone(),
+                stream: OutputStream::Stdout,
+            })));
+            ctx.set_stderr(Box::new(WritePipe::new(CaptureWriter {
+                capture,
+                stream: OutputStream::Stderr,
+            })));
+        }
+        None => {
+            ctx.set_stdout(Box::new(wasmtime_wasi::stdio::stdout()));
+            ctx.set_stderr(Box::new(wasmtime_wasi::stdio::stderr()));
+        }
+    }
+}
 
-    Ok(builder)
+/// Writes one of the module output streams to the capture buffers.
+struct CaptureWriter {
+    capture: OutputCapture,
+    stream: OutputStream,
 }
 
-fn populate_stdio(mut builder: WasiCtxBuilder) -> WasiCtxBuilder {
-    builder.inherit_stdout().inherit_stderr();
+impl std::io::Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        // the whole buffer is reported as written even if it's truncated,
+        // so the module doesn't retry or fail on full buffers
+        self.capture.write(self.stream, buf);
+        Ok(buf.len())
+    }
 
-    builder
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
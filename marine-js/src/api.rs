@@ -74,6 +74,8 @@ impl From<ApiWasiConfig> for MarineWASIConfig {
         Self {
             envs: value.envs,
             mapped_dirs,
+            memory_dirs: HashMap::new(),
+            dir_policies: HashMap::new(),
         }
     }
 }
@@ -85,6 +87,11 @@ impl From<ApiModuleConfig> for MarineModuleConfig<JsWasmBackend> {
             host_imports: Default::default(),
             wasi: value.wasi.map(Into::into),
             logging_mask: value.logging_mask,
+            // memory limits are not supported by JS backend
+            max_memory: None,
+            enforce_effects: false,
+            // output capture is not supported by JS backend
+            output: Default::default(),
         }
     }
 }
@@ -113,6 +120,10 @@ impl From<ApiServiceConfig> for MarineConfig<JsWasmBackend> {
             total_memory_limit: None,
             modules_config,
             default_modules_config: value.default_modules_config.map(Into::into),
+            facade: None,
+            trusted_keys: None,
+            deterministic: false,
+            snapshots: false,
         }
     }
 }
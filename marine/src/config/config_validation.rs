@@ -0,0 +1,380 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use super::TomlMarineConfig;
+use super::TomlMarineNamedModuleConfig;
+use super::TomlMountedBinary;
+use super::ConfigContext;
+use super::marine_config::mounted_binary_policy;
+use crate::module_loading::load_order;
+use crate::module_loading::LoadOrder;
+use crate::module_loading::ModuleDependencyIssue;
+
+use marine_it_parser::interface::FunctionSignature;
+use marine_it_parser::interface::ImportFunctionSignature;
+use marine_it_parser::interface::ModuleInterface;
+use marine_module_info_parser::effects;
+use marine_module_info_parser::effects::WasmEffect;
+use thiserror::Error as ThisError;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+
+/// A problem found by [`TomlMarineConfig::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// Config entry the problem relates to, e.g. `module "facade", mounted_binaries.curl`.
+    pub location: String,
+    pub kind: ConfigIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ThisError)]
+pub enum ConfigIssueKind {
+    #[error("module with such name is already listed")]
+    DuplicateModule,
+
+    #[error("{0}")]
+    InvalidModulePath(String),
+
+    #[error("Wasm file {0:?} not found")]
+    ModuleNotFound(PathBuf),
+
+    #[error("Wasm file {path:?} can't be parsed: {reason}")]
+    InvalidModule { path: PathBuf, reason: String },
+
+    #[error(r#"function "{function}" is imported from module "{namespace}" which isn't listed in the config"#)]
+    UnknownImportedModule { namespace: String, function: String },
+
+    #[error("modules import functions from each other: {}", .0.join(" -> "))]
+    ImportCycle(Vec<String>),
+
+    #[error(r#"facade module "{0}" isn't listed in the config"#)]
+    UnknownFacade(String),
+
+    #[error(
+        r#"function "{function}" is imported from module "{namespace}" which doesn't export it"#
+    )]
+    MissingExport { namespace: String, function: String },
+
+    #[error(r#"function is imported from module "{namespace}" as `{import}`, but exported as `{export}`"#)]
+    ImportSignatureMismatch {
+        namespace: String,
+        import: String,
+        export: String,
+    },
+
+    #[error(r#"module uses mounted binary "{0}" which isn't configured"#)]
+    MissingMountedBinary(String),
+
+    #[error("mounted binary {0:?} not found")]
+    MountedBinaryNotFound(PathBuf),
+
+    #[error("{0}")]
+    InvalidMountedBinary(String),
+
+    #[error("mounted binary isn't used by the module")]
+    UnusedMountedBinary,
+}
+
+struct ModuleInfo {
+    interface: ModuleInterface,
+    imports: Vec<ImportFunctionSignature>,
+    effects: Vec<WasmEffect>,
+}
+
+impl TomlMarineConfig {
+    /// Checks the config without instantiating modules: loads interfaces and effects of all
+    /// modules, resolves their imports in the loading order and checks mounted binaries.
+    /// Returns all found problems, an empty vector means that the config is valid.
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+        let mut report = |location: &str, kind| {
+            issues.push(ConfigIssue {
+                location: location.to_string(),
+                kind,
+            })
+        };
+
+        if let Some(facade) = &self.facade {
+            if !self.module.iter().any(|module| &module.name == facade) {
+                report("facade", ConfigIssueKind::UnknownFacade(facade.clone()));
+            }
+        }
+
+        let mut listed_modules = HashSet::new();
+        let mut modules = Vec::new();
+        for module in self.module.iter() {
+            let location = format!(r#"module "{}""#, module.name);
+
+            if !listed_modules.insert(module.name.as_str()) {
+                report(&location, ConfigIssueKind::DuplicateModule);
+                continue;
+            }
+
+            let module_info = match self.module_info(module) {
+                Ok(module_info) => Some(module_info),
+                Err(kind) => {
+                    report(&location, kind);
+                    None
+                }
+            };
+
+            let used_binaries = match &module_info {
+                Some(module_info) => module_info
+                    .effects
+                    .iter()
+                    .filter_map(|effect| match effect {
+                        WasmEffect::MountedBinary(name) => Some(name.as_str()),
+                        WasmEffect::Logger => None,
+                    })
+                    .collect::<HashSet<_>>(),
+                None => HashSet::new(),
+            };
+
+            let mounted_binaries = module.config.mounted_binaries.clone().unwrap_or_default();
+
+            for (name, mounted_binary) in mounted_binaries {
+                let location = format!("{}, mounted_binaries.{}", location, name);
+                if let Err(kind) = self.check_mounted_binary(&name, mounted_binary) {
+                    report(&location, kind);
+                }
+
+                if module_info.is_some() && !used_binaries.contains(name.as_str()) {
+                    report(&location, ConfigIssueKind::UnusedMountedBinary);
+                }
+            }
+
+            let configured_binaries = module.config.mounted_binaries.as_ref();
+            for name in used_binaries {
+                if !configured_binaries.map_or(false, |binaries| binaries.contains_key(name)) {
+                    report(
+                        &location,
+                        ConfigIssueKind::MissingMountedBinary(name.to_string()),
+                    );
+                }
+            }
+
+            modules.push((module.name.as_str(), module_info));
+        }
+
+        let imported_modules = modules
+            .iter()
+            .map(|(name, module_info)| {
+                let namespaces = module_info
+                    .iter()
+                    .flat_map(|module_info| module_info.imports.iter())
+                    .map(|import| import.namespace.clone())
+                    .collect();
+                (*name, namespaces)
+            })
+            .collect::<Vec<_>>();
+        let LoadOrder {
+            order,
+            issues: dependency_issues,
+        } = load_order(&imported_modules);
+
+        // exports of already checked modules, in the loading order
+        let mut loaded_modules = HashMap::<&str, Option<&ModuleInterface>>::new();
+        for index in order {
+            let (name, module_info) = &modules[index];
+            if let Some(module_info) = module_info {
+                let location = format!(r#"module "{}""#, name);
+                for import in module_info.imports.iter() {
+                    if let Some(kind) = check_import(import, &loaded_modules) {
+                        report(&location, kind);
+                    }
+                }
+            }
+
+            let exports = module_info
+                .as_ref()
+                .map(|module_info| &module_info.interface);
+            loaded_modules.insert(name, exports);
+        }
+
+        // missing providers are reported above for each import
+        for issue in dependency_issues {
+            if let ModuleDependencyIssue::ImportCycle(cycle) = issue {
+                let location = format!(r#"module "{}""#, cycle[0]);
+                report(&location, ConfigIssueKind::ImportCycle(cycle));
+            }
+        }
+
+        issues
+    }
+
+    fn module_info(
+        &self,
+        module: &TomlMarineNamedModuleConfig,
+    ) -> Result<ModuleInfo, ConfigIssueKind> {
+        let path = self
+            .module_path(module)
+            .map_err(|e| ConfigIssueKind::InvalidModulePath(e.to_string()))?;
+
+        if !path.is_file() {
+            return Err(ConfigIssueKind::ModuleNotFound(path));
+        }
+
+        let invalid_module = |reason: String| ConfigIssueKind::InvalidModule {
+            path: path.clone(),
+            reason,
+        };
+
+        let api = marine_it_parser::module_api(&path).map_err(|e| invalid_module(e.to_string()))?;
+        let effects =
+            effects::extract_from_path(&path).map_err(|e| invalid_module(e.to_string()))?;
+
+        Ok(ModuleInfo {
+            interface: api.interface,
+            imports: api.imports,
+            effects,
+        })
+    }
+
+    fn check_mounted_binary(
+        &self,
+        name: &str,
+        mounted_binary: toml::Value,
+    ) -> Result<(), ConfigIssueKind> {
+        let mounted_binary = mounted_binary
+            .try_into::<TomlMountedBinary>()
+            .map_err(|e| ConfigIssueKind::InvalidMountedBinary(e.to_string()))?;
+
+        let path = match &mounted_binary {
+            TomlMountedBinary::Path(path) => path,
+            TomlMountedBinary::WithPolicy(policy) => &policy.path,
+        };
+
+        let path = self.base_path.join(path);
+        if !path.exists() {
+            return Err(ConfigIssueKind::MountedBinaryNotFound(path));
+        }
+
+        let context = ConfigContext {
+            base_path: Some(self.base_path.clone()),
+        };
+        mounted_binary_policy(&context, name, mounted_binary)
+            .map_err(|e| ConfigIssueKind::InvalidMountedBinary(e.to_string()))?;
+
+        Ok(())
+    }
+}
+
+fn check_import(
+    import: &ImportFunctionSignature,
+    loaded_modules: &HashMap<&str, Option<&ModuleInterface>>,
+) -> Option<ConfigIssueKind> {
+    let namespace = import.namespace.clone();
+    let function = import.signature.name.clone();
+
+    // mounted binaries are checked by effects
+    if effects::is_host_import(&import.namespace) {
+        return None;
+    }
+
+    let exports = match loaded_modules.get(import.namespace.as_str()) {
+        // the imported module is invalid, it has already been reported
+        Some(None) => return None,
+        Some(Some(exports)) => exports,
+        None => {
+            return Some(ConfigIssueKind::UnknownImportedModule {
+                namespace,
+                function,
+            })
+        }
+    };
+
+    let export = exports
+        .function_signatures
+        .iter()
+        .find(|export| export.name == import.signature.name);
+
+    match export {
+        None => Some(ConfigIssueKind::MissingExport {
+            namespace,
+            function,
+        }),
+        Some(export) if !signature_types_match(&import.signature, export) => {
+            Some(ConfigIssueKind::ImportSignatureMismatch {
+                namespace,
+                import: import.signature.to_string().trim_end().to_string(),
+                export: export.to_string().trim_end().to_string(),
+            })
+        }
+        Some(_) => None,
+    }
+}
+
+/// Checks that functions have the same argument and output types, argument names
+/// don't matter for calls between modules, as at runtime.
+fn signature_types_match(import: &FunctionSignature, export: &FunctionSignature) -> bool {
+    import.arguments.len() == export.arguments.len()
+        && import
+            .arguments
+            .iter()
+            .zip(export.arguments.iter())
+            .all(|((_, import_type), (_, export_type))| import_type == export_type)
+        && import.output_types == export.output_types
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::signature_types_match;
+
+    use marine_it_parser::interface::FunctionSignature;
+
+    fn function(arguments: &[(&str, &str)], output: &[&str]) -> FunctionSignature {
+        FunctionSignature {
+            name: "greeting".to_string(),
+            arguments: arguments
+                .iter()
+                .map(|(name, ty)| (name.to_string(), ty.to_string()))
+                .collect(),
+            output_types: output.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn argument_names_are_ignored() {
+        let import = function(&[("name", "string")], &["string"]);
+        let export = function(&[("user", "string")], &["string"]);
+
+        assert!(signature_types_match(&import, &export));
+    }
+
+    #[test]
+    fn types_are_compared() {
+        let import = function(&[("name", "string")], &["string"]);
+
+        assert!(!signature_types_match(
+            &import,
+            &function(&[("name", "u8")], &["string"])
+        ));
+        assert!(!signature_types_match(
+            &import,
+            &function(&[("name", "string")], &[])
+        ));
+        assert!(!signature_types_match(&import, &function(&[], &["string"])));
+    }
+}
@@ -17,6 +17,9 @@
 use marine_wasm_backend_traits::WasmBackend;
 use marine_core::generic::HostImportDescriptor;
 use marine_core::HostAPIVersion;
+use marine_core::MappedDirPolicy;
+use marine_core::MemoryDir;
+use marine_module_info_parser::signature::VerifyingKey;
 
 use std::collections::HashMap;
 use std::path::Path;
@@ -52,20 +55,36 @@ pub struct ModuleDescriptor<WB: WasmBackend> {
 
 impl<WB: WasmBackend> ModuleDescriptor<WB> {
     pub fn get_path(&self, modules_dir: &Option<PathBuf>) -> Result<PathBuf, MarineError> {
-        match &self.load_from {
-            None => match modules_dir {
-                Some(dir) => Ok(dir.join(Path::new(&self.file_name))),
-                None => Err(MarineError::InvalidConfig(format!(
-                    r#""modules_dir" field is not defined, but it is required to load module "{}""#,
-                    self.import_name
-                ))),
-            },
-            Some(path) => {
-                if path.is_file() {
-                    Ok(path.clone())
-                } else {
-                    Ok(path.join(Path::new(&self.file_name)))
-                }
+        resolve_module_path(
+            self.load_from.as_deref(),
+            &self.file_name,
+            &self.import_name,
+            modules_dir.as_deref(),
+        )
+    }
+}
+
+/// Returns a path to the Wasm file of a module: `load_from` is either the file itself
+/// or a directory containing `file_name`, without it the file is looked up in `modules_dir`.
+pub(crate) fn resolve_module_path(
+    load_from: Option<&Path>,
+    file_name: &str,
+    import_name: &str,
+    modules_dir: Option<&Path>,
+) -> Result<PathBuf, MarineError> {
+    match load_from {
+        None => match modules_dir {
+            Some(dir) => Ok(dir.join(Path::new(file_name))),
+            None => Err(MarineError::InvalidConfig(format!(
+                r#""modules_dir" field is not defined, but it is required to load module "{}""#,
+                import_name
+            ))),
+        },
+        Some(path) => {
+            if path.is_file() {
+                Ok(path.to_path_buf())
+            } else {
+                Ok(path.join(Path::new(file_name)))
             }
         }
     }
@@ -84,6 +103,18 @@ pub struct MarineConfig<WB: WasmBackend> {
 
     /// Settings for a module that name's not been found in modules_config.
     pub default_modules_config: Option<MarineModuleConfig<WB>>,
+
+    /// Name of the facade module, the last module from `modules_config` is used if it's absent.
+    pub facade: Option<String>,
+
+    /// If set, only modules signed with one of these keys are loaded.
+    pub trusted_keys: Option<Vec<VerifyingKey>>,
+
+    /// If set, modules observe the virtual time and randomness seeded from call parameters.
+    pub deterministic: bool,
+
+    /// If set, the state of modules could be saved with `Marine::snapshot`.
+    pub snapshots: bool,
 }
 
 // Manual implementation because #[derive(Default)] does not allow direct usage of non-Default wasm backend.
@@ -94,6 +125,10 @@ impl<WB: WasmBackend> Default for MarineConfig<WB> {
             total_memory_limit: <_>::default(),
             modules_config: <_>::default(),
             default_modules_config: <_>::default(),
+            facade: <_>::default(),
+            trusted_keys: <_>::default(),
+            deterministic: <_>::default(),
+            snapshots: <_>::default(),
         }
     }
 }
@@ -113,6 +148,34 @@ pub struct MarineModuleConfig<WB: WasmBackend> {
 
     /// Mask used to filter logs, for details see `log_utf8_string`
     pub logging_mask: i32,
+
+    /// Maximum size of the module memory in bytes, applied in addition to `total_memory_limit`.
+    pub max_memory: Option<u64>,
+
+    /// Refuse to load the module if it imports the logger or host functions (e.g. mounted
+    /// binaries) not granted by this config.
+    pub enforce_effects: bool,
+
+    /// Where stdout and stderr of the module go.
+    pub output: ModuleOutput,
+}
+
+/// Destination of stdout and stderr of a module.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModuleOutput {
+    /// Written to stdout and stderr of the host process.
+    #[default]
+    Inherit,
+    /// Kept in memory up to `max_size` bytes per stream and per call,
+    /// it's returned by `Marine::last_call_output`.
+    Capture { max_size: usize },
+    /// Kept in memory like with `Capture`, and written to the `log` crate after each call
+    /// with the module name as the target.
+    Log { max_size: usize },
+}
+
+impl ModuleOutput {
+    pub const DEFAULT_MAX_SIZE: usize = 64 * 1024;
 }
 
 impl<WB: WasmBackend> MarineModuleConfig<WB> {
@@ -122,7 +185,7 @@ impl<WB: WasmBackend> MarineModuleConfig<WB> {
             w @ None => {
                 *w = Some(MarineWASIConfig {
                     envs: new_envs,
-                    mapped_dirs: HashMap::new(),
+                    ..<_>::default()
                 })
             }
         };
@@ -147,16 +210,54 @@ pub struct MarineWASIConfig {
 
     /// Mapping from a usually short to full file name.
     pub mapped_dirs: HashMap<String, PathBuf>,
+
+    /// Directories kept in memory, they are created from seeds on each module load.
+    pub memory_dirs: HashMap<String, MemoryDirSeed>,
+
+    /// Access mode and quotas of mapped and in-memory directories by their guest paths.
+    pub dir_policies: HashMap<String, MappedDirPolicy>,
+}
+
+/// Initial content of an in-memory directory.
+#[derive(Debug, Clone, Default)]
+pub enum MemoryDirSeed {
+    #[default]
+    Empty,
+    /// Content is copied from a host directory.
+    HostDir(PathBuf),
+    /// Content is unpacked from a tar archive.
+    Tarball(PathBuf),
+}
+
+impl MemoryDirSeed {
+    pub fn create_dir(&self) -> std::io::Result<MemoryDir> {
+        match self {
+            Self::Empty => Ok(MemoryDir::default()),
+            Self::HostDir(path) => MemoryDir::from_host_dir(path),
+            Self::Tarball(path) => {
+                MemoryDir::from_tar(std::io::BufReader::new(std::fs::File::open(path)?))
+            }
+        }
+    }
 }
 
 use super::TomlMarineConfig;
 use super::TomlMarineModuleConfig;
 use super::TomlWASIConfig;
 use super::TomlMarineNamedModuleConfig;
+use super::TomlMountedBinary;
+use super::TomlMappedDir;
+use super::TomlMappedDirKind;
+use super::TomlMappedDirAccess;
+use super::TomlModuleOutput;
+use crate::host_imports::MountedBinaryPolicy;
 use crate::MarineError;
 use crate::MarineResult;
 use crate::config::as_relative_to_base;
-use crate::config::raw_marine_config::MemoryLimit;
+use crate::config::MemoryLimit;
+
+use marine_module_info_parser::signature;
+use bytesize::ByteSize;
 
 use std::convert::TryFrom;
 use std::convert::TryInto;
@@ -191,11 +292,27 @@ impl<WB: WasmBackend> TryFrom<TomlMarineConfig> for MarineConfig<WB> {
             MemoryLimit::Value(bytesize) => Some(bytesize.as_u64()),
         };
 
+        let trusted_keys = toml_config
+            .trusted_keys
+            .map(|keys| {
+                keys.iter()
+                    .map(|key| {
+                        signature::parse_verifying_key(key)
+                            .map_err(|e| MarineError::InvalidConfig(format!("trusted_keys: {}", e)))
+                    })
+                    .collect::<MarineResult<Vec<_>>>()
+            })
+            .transpose()?;
+
         Ok(MarineConfig {
             modules_dir,
             total_memory_limit,
             modules_config,
             default_modules_config,
+            facade: toml_config.facade,
+            trusted_keys,
+            deterministic: toml_config.deterministic.unwrap_or(false),
+            snapshots: toml_config.snapshots.unwrap_or(false),
         })
     }
 }
@@ -241,7 +358,7 @@ impl<'c, WB: WasmBackend> TryFrom<WithContext<'c, TomlMarineModuleConfig>>
         let mounted_binaries = mounted_binaries
             .into_iter()
             .map(|(import_func_name, host_cmd)| {
-                let host_cmd = host_cmd.try_into::<PathBuf>()?;
+                let host_cmd = host_cmd.try_into::<TomlMountedBinary>()?;
                 Ok((import_func_name, host_cmd))
             })
             .collect::<Result<Vec<_>, Self::Error>>()?;
@@ -253,30 +370,135 @@ impl<'c, WB: WasmBackend> TryFrom<WithContext<'c, TomlMarineModuleConfig>>
             (HostAPIVersion::V3, HashMap::new()),
         ]);
         for (import_name, host_cmd) in mounted_binaries {
-            let host_cmd = as_relative_to_base(context.base_path.as_deref(), &host_cmd)?;
+            let (host_cmd, policy) = mounted_binary_policy(context, &import_name, host_cmd)?;
             for (_, host_cli_imports) in &mut host_imports {
                 host_cli_imports.insert(
                     import_name.clone(),
-                    crate::host_imports::create_mounted_binary_import(host_cmd.clone()),
+                    crate::host_imports::create_mounted_binary_import(
+                        host_cmd.clone(),
+                        policy.clone(),
+                    ),
                 );
             }
         }
 
-        let wasi = toml_config.wasi.map(|w| w.try_into()).transpose()?;
+        let wasi = toml_config
+            .wasi
+            .map(|w| context.wrapped(w).try_into())
+            .transpose()?;
+
+        let max_memory = match toml_config.max_memory {
+            None | Some(MemoryLimit::Infinity) => None,
+            Some(MemoryLimit::Value(bytesize)) => Some(bytesize.as_u64()),
+        };
 
         Ok(MarineModuleConfig {
             logger_enabled: toml_config.logger_enabled.unwrap_or(true),
             host_imports,
             wasi,
             logging_mask: toml_config.logging_mask.unwrap_or(i32::max_value()),
+            max_memory,
+            enforce_effects: toml_config.enforce_effects.unwrap_or(false),
+            output: module_output(toml_config.output, toml_config.max_output_size)?,
         })
     }
 }
 
-impl TryFrom<TomlWASIConfig> for MarineWASIConfig {
+pub(crate) fn mounted_binary_policy(
+    context: &ConfigContext,
+    import_name: &str,
+    mounted_binary: TomlMountedBinary,
+) -> MarineResult<(PathBuf, MountedBinaryPolicy)> {
+    let base_path = context.base_path.as_deref();
+    let config = match mounted_binary {
+        TomlMountedBinary::Path(path) => {
+            let path = as_relative_to_base(base_path, &path)?;
+            return Ok((path, MountedBinaryPolicy::default()));
+        }
+        TomlMountedBinary::WithPolicy(config) => config,
+    };
+
+    let invalid_policy = |reason: String| {
+        MarineError::InvalidConfig(format!(
+            "mounted binary `{}` has invalid policy: {}",
+            import_name, reason
+        ))
+    };
+
+    let allowed_args = config
+        .allowed_args
+        .map(|patterns| MountedBinaryPolicy::compile_allowed_args(&patterns))
+        .transpose()
+        .map_err(|e| invalid_policy(e.to_string()))?;
+
+    let timeout = config
+        .timeout
+        .map(|timeout| humantime::parse_duration(&timeout))
+        .transpose()
+        .map_err(|e| invalid_policy(format!("timeout {}", e)))?;
+
+    let max_output_size = config
+        .max_output_size
+        .map(|size| usize::try_from(size.as_u64()))
+        .transpose()
+        .map_err(|e| invalid_policy(format!("max_output_size {}", e)))?;
+
+    let working_dir = config
+        .working_dir
+        .map(|dir| as_relative_to_base(base_path, &dir))
+        .transpose()?;
+
+    let policy = MountedBinaryPolicy {
+        allowed_args,
+        timeout,
+        max_output_size,
+        allowed_envs: config.allowed_envs,
+        working_dir,
+    };
+
+    Ok((as_relative_to_base(base_path, &config.path)?, policy))
+}
+
+fn module_output(
+    output: Option<TomlModuleOutput>,
+    max_size: Option<ByteSize>,
+) -> MarineResult<ModuleOutput> {
+    let max_size = max_size
+        .map(|size| usize::try_from(size.as_u64()))
+        .transpose()
+        .map_err(|e| MarineError::InvalidConfig(format!("max_output_size {}", e)))?
+        .unwrap_or(ModuleOutput::DEFAULT_MAX_SIZE);
+
+    let output = match output.unwrap_or_default() {
+        TomlModuleOutput::Inherit => ModuleOutput::Inherit,
+        TomlModuleOutput::Capture => ModuleOutput::Capture { max_size },
+        TomlModuleOutput::Log => ModuleOutput::Log { max_size },
+    };
+
+    Ok(output)
+}
+
+fn memory_dir_seed(context: &ConfigContext, seed: Option<PathBuf>) -> MarineResult<MemoryDirSeed> {
+    let seed = match seed {
+        Some(seed) => as_relative_to_base(context.base_path.as_deref(), &seed)?,
+        None => return Ok(MemoryDirSeed::Empty),
+    };
+
+    match seed.is_dir() {
+        true => Ok(MemoryDirSeed::HostDir(seed)),
+        false => Ok(MemoryDirSeed::Tarball(seed)),
+    }
+}
+
+impl<'c> TryFrom<WithContext<'c, TomlWASIConfig>> for MarineWASIConfig {
     type Error = MarineError;
 
-    fn try_from(toml_config: TomlWASIConfig) -> Result<Self, Self::Error> {
+    fn try_from(toml_config: WithContext<'c, TomlWASIConfig>) -> Result<Self, Self::Error> {
+        let WithContext {
+            context,
+            data: toml_config,
+        } = toml_config;
+
         let to_string = |elem: (String, toml::Value)| -> Result<(String, String), Self::Error> {
             let to = elem
                 .1
@@ -285,27 +507,66 @@ impl TryFrom<TomlWASIConfig> for MarineWASIConfig {
             Ok((elem.0, to))
         };
 
-        let to_path = |elem: (String, toml::Value)| -> Result<(String, PathBuf), Self::Error> {
-            let to = elem
-                .1
-                .try_into::<PathBuf>()
-                .map_err(MarineError::ParseConfigError)?;
-
-            Ok((elem.0, to))
-        };
-
         let envs = toml_config.envs.unwrap_or_default();
         let envs = envs
             .into_iter()
             .map(to_string)
             .collect::<Result<HashMap<_, _>, _>>()?;
 
-        let mapped_dirs = toml_config.mapped_dirs.unwrap_or_default();
-        let mapped_dirs = mapped_dirs
-            .into_iter()
-            .map(to_path)
-            .collect::<Result<HashMap<_, _>, _>>()?;
+        let mut mapped_dirs = HashMap::new();
+        let mut memory_dirs = HashMap::new();
+        let mut dir_policies = HashMap::new();
+        for (guest_path, mapped_dir) in toml_config.mapped_dirs.unwrap_or_default() {
+            let mapped_dir = mapped_dir
+                .try_into::<TomlMappedDir>()
+                .map_err(MarineError::ParseConfigError)?;
+
+            let invalid_mapping = |reason: &str| {
+                MarineError::InvalidConfig(format!("mapped directory `{}` {}", guest_path, reason))
+            };
+
+            let config = match mapped_dir {
+                TomlMappedDir::Path(path) => {
+                    mapped_dirs.insert(guest_path, path);
+                    continue;
+                }
+                TomlMappedDir::WithKind(config) => config,
+            };
+
+            let policy = MappedDirPolicy {
+                read_only: config.access == Some(TomlMappedDirAccess::ReadOnly),
+                max_bytes: config.max_size.map(|size| size.as_u64()),
+                max_files: config.max_files,
+            };
+            if policy != MappedDirPolicy::default() {
+                dir_policies.insert(guest_path.clone(), policy);
+            }
+
+            match config.kind {
+                TomlMappedDirKind::Host => {
+                    if config.seed.is_some() {
+                        return Err(invalid_mapping("of the host kind can't have a seed"));
+                    }
+                    let path = config
+                        .path
+                        .ok_or_else(|| invalid_mapping("of the host kind must have a path"))?;
+                    mapped_dirs.insert(guest_path, path);
+                }
+                TomlMappedDirKind::Memory => {
+                    if config.path.is_some() {
+                        return Err(invalid_mapping("of the memory kind can't have a path"));
+                    }
+                    let seed = memory_dir_seed(context, config.seed)?;
+                    memory_dirs.insert(guest_path, seed);
+                }
+            }
+        }
 
-        Ok(MarineWASIConfig { envs, mapped_dirs })
+        Ok(MarineWASIConfig {
+            envs,
+            mapped_dirs,
+            memory_dirs,
+            dir_policies,
+        })
     }
 }
@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+mod config_validation;
 mod raw_marine_config;
 mod to_marine_config;
 mod marine_config;
@@ -24,12 +25,25 @@ pub use marine_config::WithContext;
 pub use marine_config::MarineModuleConfig;
 pub use marine_config::MarineConfig;
 pub use marine_config::MarineWASIConfig;
+pub use marine_config::MemoryDirSeed;
+pub use marine_config::ModuleOutput;
 pub use marine_config::ModuleDescriptor;
 
+pub use config_validation::ConfigIssue;
+pub use config_validation::ConfigIssueKind;
+
 pub use raw_marine_config::TomlMarineNamedModuleConfig;
 pub use raw_marine_config::TomlWASIConfig;
 pub use raw_marine_config::TomlMarineConfig;
 pub use raw_marine_config::TomlMarineModuleConfig;
+pub use raw_marine_config::MemoryLimit;
+pub use raw_marine_config::TomlMountedBinary;
+pub use raw_marine_config::TomlMountedBinaryPolicy;
+pub use raw_marine_config::TomlMappedDir;
+pub use raw_marine_config::TomlMappedDirConfig;
+pub use raw_marine_config::TomlMappedDirKind;
+pub use raw_marine_config::TomlMappedDirAccess;
+pub use raw_marine_config::TomlModuleOutput;
 
 // reexport toml types, so users don't have to directly depend on the same version of toml crate
 pub use toml::Value as TomlValue;
@@ -37,3 +51,4 @@ pub use toml::value::Table as TomlValueTable;
 
 pub(crate) use to_marine_config::make_marine_config;
 pub(crate) use path_utils::as_relative_to_base;
+pub(crate) use marine_config::resolve_module_path;
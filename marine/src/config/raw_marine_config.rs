@@ -16,6 +16,8 @@
 
 use crate::MarineError;
 use crate::MarineResult;
+use crate::config::as_relative_to_base;
+use crate::config::resolve_module_path;
 
 use bytesize::ByteSize;
 use serde_derive::Serialize;
@@ -30,22 +32,41 @@ use std::path::PathBuf;
 An example of the config:
 
 modules_dir = "wasm/artifacts/wasm_modules"
+facade = "ipfs_node.wasm"
+trusted_keys = ["d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"]
+deterministic = true
+snapshots = true
 
 [[module]]
     name = "ipfs_node.wasm"
-    mem_pages_count = 100
+    max_memory = "100 MiB"
     logger_enabled = true
+    enforce_effects = true
+    output = "capture"
+    max_output_size = "64 KiB"
 
     [module.mounted_binaries]
     mysql = "/usr/bin/mysql"
     ipfs = "/usr/local/bin/ipfs"
 
+    [module.mounted_binaries.curl]
+    path = "/usr/bin/curl"
+    allowed_args = ["-s", "https://.*"]
+    timeout = "10s"
+    max_output_size = "1 MiB"
+    allowed_envs = ["HOME"]
+    working_dir = "/tmp"
+
     [module.wasi]
     envs = { "IPFS_ADDR" = "/dns4/relay02.fluence.dev/tcp/15001" }
-    mapped_dirs = {"tmp" = "/Users/user/tmp"}
+
+    [module.wasi.mapped_dirs]
+    tmp = "/Users/user/tmp"
+    data = { path = "/Users/user/data", access = "read-only" }
+    scratch = { kind = "memory", seed = "fixtures.tar", max_size = "100 MiB", max_files = 1000 }
 
 [default]
-    mem_pages_count = 100
+    max_memory = "100 MiB"
     logger_enabled = true
 
     [default.mounted_binaries]
@@ -61,6 +82,20 @@ modules_dir = "wasm/artifacts/wasm_modules"
 pub struct TomlMarineConfig {
     pub modules_dir: Option<PathBuf>,
     pub total_memory_limit: MemoryLimit,
+    /// Name of the module whose functions are exposed as the service API,
+    /// the last listed module is used if it's absent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub facade: Option<String>,
+    /// Hex-encoded ed25519 public keys, if set only modules signed with one of them are loaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_keys: Option<Vec<String>>,
+    /// Give modules the virtual time and randomness seeded from call parameters instead of
+    /// the host ones, the Wasm backend must be configured for deterministic execution as well.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deterministic: Option<bool>,
+    /// Prepare modules for snapshots of the service state, it slows down module loading.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshots: Option<bool>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub module: Vec<TomlMarineNamedModuleConfig>,
     pub default: Option<TomlMarineModuleConfig>,
@@ -101,6 +136,32 @@ impl TomlMarineConfig {
 
         Ok(config)
     }
+
+    /// Returns a path to the Wasm file of the module, resolved the same way as while loading it.
+    pub fn module_path(&self, module: &TomlMarineNamedModuleConfig) -> MarineResult<PathBuf> {
+        let base_path = Some(self.base_path.as_path());
+        let file_name = module
+            .file_name
+            .clone()
+            .unwrap_or_else(|| format!("{}.wasm", module.name));
+        let load_from = module
+            .load_from
+            .as_ref()
+            .map(|path| as_relative_to_base(base_path, path))
+            .transpose()?;
+        let modules_dir = self
+            .modules_dir
+            .as_ref()
+            .map(|dir| as_relative_to_base(base_path, dir))
+            .transpose()?;
+
+        resolve_module_path(
+            load_from.as_deref(),
+            &file_name,
+            &module.name,
+            modules_dir.as_deref(),
+        )
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -119,8 +180,50 @@ pub struct TomlMarineNamedModuleConfig {
 pub struct TomlMarineModuleConfig {
     pub logger_enabled: Option<bool>,
     pub logging_mask: Option<i32>,
+    /// Where stdout and stderr of the module go, they're inherited from the host by default.
+    pub output: Option<TomlModuleOutput>,
+    /// Limit for captured stdout and for stderr per call, e.g. "64 KiB".
+    pub max_output_size: Option<ByteSize>,
     pub wasi: Option<TomlWASIConfig>,
     pub mounted_binaries: Option<toml::value::Table>,
+    pub max_memory: Option<MemoryLimit>,
+    /// Refuse to load the module if it uses the logger or mounted binaries not granted here.
+    pub enforce_effects: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TomlModuleOutput {
+    /// Written to stdout and stderr of the host process.
+    #[default]
+    Inherit,
+    /// Kept in memory and returned after each call.
+    Capture,
+    /// Kept in memory and written to the host log after each call.
+    Log,
+}
+
+/// A mounted binary, given either by a bare path or by a path with a sandboxing policy.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum TomlMountedBinary {
+    Path(PathBuf),
+    WithPolicy(TomlMountedBinaryPolicy),
+}
+
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct TomlMountedBinaryPolicy {
+    pub path: PathBuf,
+    /// Regular expressions, each argument must fully match at least one of them.
+    pub allowed_args: Option<Vec<String>>,
+    /// Wall clock timeout in the humantime format, e.g. "10s" or "1m 30s", 1 minute if omitted.
+    pub timeout: Option<String>,
+    /// Limit for stdout and for stderr, e.g. "1 MiB".
+    pub max_output_size: Option<ByteSize>,
+    /// Host environment variables passed to the binary, all of them are passed if omitted.
+    pub allowed_envs: Option<Vec<String>>,
+    pub working_dir: Option<PathBuf>,
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, Default)]
@@ -129,6 +232,50 @@ pub struct TomlWASIConfig {
     pub mapped_dirs: Option<toml::value::Table>,
 }
 
+/// A mapped directory, given either by a bare host path or by a table with its kind.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum TomlMappedDir {
+    Path(PathBuf),
+    WithKind(TomlMappedDirConfig),
+}
+
+#[skip_serializing_none]
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TomlMappedDirConfig {
+    #[serde(default)]
+    pub kind: TomlMappedDirKind,
+    /// A host directory, required for the `host` kind.
+    pub path: Option<PathBuf>,
+    /// A directory or a tar archive copied into a `memory` directory on each module load.
+    pub seed: Option<PathBuf>,
+    /// `read-only` or `read-write`, directories are writable by default.
+    pub access: Option<TomlMappedDirAccess>,
+    /// Limit for the total size of files in the directory, e.g. "100 MiB".
+    pub max_size: Option<ByteSize>,
+    /// Limit for the number of files and subdirectories in the directory.
+    pub max_files: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TomlMappedDirAccess {
+    #[serde(rename = "read-only", alias = "ro")]
+    ReadOnly,
+    #[serde(rename = "read-write", alias = "rw")]
+    ReadWrite,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TomlMappedDirKind {
+    /// A host directory, it's created on disk by the app service if it doesn't exist.
+    #[default]
+    Host,
+    /// A directory kept in memory, it's dropped with the module.
+    Memory,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
 #[serde_as]
 pub enum MemoryLimit {
@@ -144,6 +291,13 @@ mod tests {
     use super::TomlMarineNamedModuleConfig;
     use super::TomlMarineModuleConfig;
     use super::TomlWASIConfig;
+    use super::TomlMountedBinary;
+    use super::TomlMappedDir;
+    use super::TomlMappedDirKind;
+    use super::TomlMappedDirAccess;
+    use super::TomlModuleOutput;
+
+    use std::path::Path;
 
     #[test]
     fn serialize_marine_named_module_config() {
@@ -160,14 +314,117 @@ mod tests {
             config: TomlMarineModuleConfig {
                 logger_enabled: Some(false),
                 logging_mask: Some(1),
+                output: Some(TomlModuleOutput::Capture),
+                max_output_size: None,
                 wasi: Some(TomlWASIConfig {
                     envs: None,
                     mapped_dirs: None,
                 }),
                 mounted_binaries: Some(mounted_binaries),
+                max_memory: None,
+                enforce_effects: None,
             },
         };
 
         assert!(toml::to_string(&config).is_ok())
     }
+
+    #[test]
+    fn deserialize_mounted_binaries() {
+        let config = r#"
+            [mounted_binaries]
+            ipfs = "/usr/local/bin/ipfs"
+
+            [mounted_binaries.curl]
+            path = "/usr/bin/curl"
+            allowed_args = ["-s", "https://.*"]
+            timeout = "10s"
+            max_output_size = "1 MiB"
+        "#;
+
+        let config: TomlMarineModuleConfig = toml::from_str(config).unwrap();
+        let mounted_binaries = config.mounted_binaries.unwrap();
+
+        let ipfs = mounted_binaries["ipfs"].clone().try_into().unwrap();
+        assert!(
+            matches!(ipfs, TomlMountedBinary::Path(path) if path == Path::new("/usr/local/bin/ipfs"))
+        );
+
+        let curl = mounted_binaries["curl"].clone().try_into().unwrap();
+        let policy = match curl {
+            TomlMountedBinary::WithPolicy(policy) => policy,
+            TomlMountedBinary::Path(_) => panic!("curl is expected to have a policy"),
+        };
+        assert_eq!(policy.path, Path::new("/usr/bin/curl"));
+        assert_eq!(policy.allowed_args.unwrap().len(), 2);
+        assert_eq!(policy.timeout.as_deref(), Some("10s"));
+        assert_eq!(policy.max_output_size, Some(bytesize::ByteSize::mib(1)));
+        assert!(policy.allowed_envs.is_none());
+    }
+
+    #[test]
+    fn deserialize_mapped_dirs() {
+        let wasi: TomlWASIConfig = toml::from_str(
+            r#"
+            mapped_dirs = { "tmp" = "./tmp", "scratch" = { kind = "memory", seed = "fixtures" } }
+            "#,
+        )
+        .unwrap();
+        let mapped_dirs = wasi.mapped_dirs.unwrap();
+
+        let tmp = mapped_dirs["tmp"].clone().try_into().unwrap();
+        assert!(matches!(tmp, TomlMappedDir::Path(path) if path == Path::new("./tmp")));
+
+        let scratch = mapped_dirs["scratch"].clone().try_into().unwrap();
+        let config = match scratch {
+            TomlMappedDir::WithKind(config) => config,
+            TomlMappedDir::Path(_) => panic!("scratch is expected to have a kind"),
+        };
+        assert_eq!(config.kind, TomlMappedDirKind::Memory);
+        assert_eq!(config.seed.as_deref(), Some(Path::new("fixtures")));
+        assert!(config.path.is_none());
+    }
+
+    #[test]
+    fn deserialize_mapped_dir_limits() {
+        let wasi: TomlWASIConfig = toml::from_str(
+            r#"
+            [mapped_dirs]
+            "input" = { path = "./input", access = "ro" }
+            "output" = { path = "./output", access = "read-write", max_size = "1 MiB", max_files = 10 }
+            "#,
+        )
+        .unwrap();
+        let mapped_dirs = wasi.mapped_dirs.unwrap();
+
+        let config = |name: &str| match mapped_dirs[name].clone().try_into().unwrap() {
+            TomlMappedDir::WithKind(config) => config,
+            TomlMappedDir::Path(_) => panic!("{} is expected to be a table", name),
+        };
+
+        let input = config("input");
+        assert_eq!(input.kind, TomlMappedDirKind::Host);
+        assert_eq!(input.access, Some(TomlMappedDirAccess::ReadOnly));
+        assert!(input.max_size.is_none());
+
+        let output = config("output");
+        assert_eq!(output.access, Some(TomlMappedDirAccess::ReadWrite));
+        assert_eq!(output.max_size, Some(bytesize::ByteSize::mib(1)));
+        assert_eq!(output.max_files, Some(10));
+    }
+
+    #[test]
+    fn deserialize_module_output() {
+        let config = r#"
+            output = "log"
+            max_output_size = "1 KiB"
+        "#;
+
+        let config: TomlMarineModuleConfig = toml::from_str(config).unwrap();
+        assert_eq!(config.output, Some(TomlModuleOutput::Log));
+        assert_eq!(config.max_output_size, Some(bytesize::ByteSize::kib(1)));
+
+        let config: TomlMarineModuleConfig = toml::from_str("").unwrap();
+        assert!(config.output.is_none());
+    }
 }
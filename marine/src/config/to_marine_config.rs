@@ -15,6 +15,7 @@
  */
 
 use crate::MarineWASIConfig;
+use crate::MarineError;
 use crate::MarineResult;
 use crate::config::MarineModuleConfig;
 use crate::host_imports::logger::log_utf8_string_closure;
@@ -67,6 +68,10 @@ impl<WB: WasmBackend> MModuleConfigBuilder<WB> {
             host_imports,
             wasi,
             logging_mask,
+            max_memory,
+            enforce_effects: _,
+            // the output is redirected by Marine, which drains it after each call
+            output: _,
         } = marine_module_config;
 
         let config = self
@@ -79,6 +84,7 @@ impl<WB: WasmBackend> MModuleConfigBuilder<WB> {
                 call_parameters_v3,
             )
             .populate_wasi(wasi)?
+            .populate_max_memory(max_memory)
             .into_config();
 
         Ok(config)
@@ -93,6 +99,7 @@ impl<WB: WasmBackend> MModuleConfigBuilder<WB> {
         self.config.wasi_parameters.envs = wasi.envs;
 
         self.config.wasi_parameters.mapped_dirs = wasi.mapped_dirs;
+        self.config.wasi_parameters.dir_policies = wasi.dir_policies;
 
         // create environment variables for all mapped directories
         let mapped_dirs = self
@@ -105,6 +112,25 @@ impl<WB: WasmBackend> MModuleConfigBuilder<WB> {
 
         self.config.wasi_parameters.envs.extend(mapped_dirs);
 
+        // each load gets its own copy of the seed, so modules never share in-memory directories
+        for (guest_path, seed) in wasi.memory_dirs {
+            let dir = seed.create_dir().map_err(|e| {
+                MarineError::IOError(format!(
+                    "failed to fill in-memory directory {} from {:?}: {}",
+                    guest_path, seed, e
+                ))
+            })?;
+
+            self.config
+                .wasi_parameters
+                .envs
+                .insert(guest_path.clone(), guest_path.clone());
+            self.config
+                .wasi_parameters
+                .memory_dirs
+                .insert(guest_path, dir);
+        }
+
         Ok(self)
     }
 
@@ -183,6 +209,11 @@ impl<WB: WasmBackend> MModuleConfigBuilder<WB> {
         self
     }
 
+    fn populate_max_memory(mut self, max_memory: Option<u64>) -> Self {
+        self.config.max_memory = max_memory;
+        self
+    }
+
     fn into_config(self) -> MModuleConfig<WB> {
         self.config
     }
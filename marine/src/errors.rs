@@ -17,6 +17,10 @@
 use marine_core::MError;
 use marine_wasm_backend_traits::MemoryAllocationStats;
 use it_json_serde::ITJsonSeDeError;
+use it_msgpack_serde::ITMsgPackSeDeError;
+use marine_module_info_parser::ModuleInfoError;
+use marine_module_info_parser::signature::SignatureError;
+use crate::module_loading::ModuleDependencyIssue;
 
 use thiserror::Error;
 
@@ -71,6 +75,51 @@ pub enum MarineError {
         error: ITJsonSeDeError,
     },
 
+    /// Provided MessagePack arguments aren't compatible with a called function signature.
+    #[error(r#"arguments from msgpack deserialization error in module "{module_name}", function "{function_name}": {error}"#)]
+    MsgPackArgumentsDeserializationError {
+        module_name: String,
+        function_name: String,
+        error: ITMsgPackSeDeError,
+    },
+
+    /// Returned outputs can't be encoded to MessagePack according to a called function signature.
+    #[error(r#"output to msgpack serialization error in module "{module_name}", function "{function_name}": {error}"#)]
+    MsgPackOutputSerializationError {
+        module_name: String,
+        function_name: String,
+        error: ITMsgPackSeDeError,
+    },
+
+    /// A module uses the logger or host imports not granted by its config,
+    /// returned only if effects are enforced for the module.
+    #[error(r#"module "{module_name}" uses effects not granted by its config: {}"#, .effects.join(", "))]
+    UngrantedEffects {
+        module_name: String,
+        effects: Vec<String>,
+    },
+
+    /// Effects of a module can't be extracted from its Wasm bytes.
+    #[error(r#"failed to extract effects of module "{module_name}": {error}"#)]
+    EffectsExtractionError {
+        module_name: String,
+        error: ModuleInfoError,
+    },
+
+    /// Modules from config can't be loaded in an order satisfying their imports.
+    #[error(
+        "modules can't be loaded in an order satisfying their imports: {}",
+        .0.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    UnresolvableModuleImports(Vec<ModuleDependencyIssue>),
+
+    /// A module isn't signed with one of the trusted keys from config.
+    #[error(r#"module "{module_name}" is refused: {error}"#)]
+    UntrustedModule {
+        module_name: String,
+        error: SignatureError,
+    },
+
     /// Errors related to invalid config.
     #[error("parsing config error: {0}")]
     ParseConfigError(#[from] toml::de::Error),
@@ -79,17 +128,34 @@ pub enum MarineError {
     #[error("engine error: {0}")]
     EngineError(#[from] MError),
 
+    /// A call consumed all the fuel it was given.
+    #[error(r#"function "{function_name}" of module "{module_name}" exhausted its fuel limit of {fuel_limit} units"#)]
+    FuelExhausted {
+        module_name: String,
+        function_name: String,
+        fuel_limit: u64,
+    },
+
     /// When marine returned an error and there was a rejected allocation,
     /// the most probable cause is OOM. Otherwise this error is the same as EngineError.
     /// This error is on marine-runtime level,
     /// because otherwise it is impossible to check allocation stats after a failed instantiation.
-    #[error("Engine error when OOM suspected ({0} failed allocations), original error: {original_error}", .allocation_stats.allocation_rejects)]
+    #[error("Engine error when OOM suspected ({0} failed allocations{1}), original error: {original_error}", .allocation_stats.allocation_rejects, module_limit_note(.module_name))]
     HighProbabilityOOM {
         original_error: MError,
         allocation_stats: MemoryAllocationStats,
+        /// Module that hit its own `max_memory`, None if the total memory limit was hit.
+        module_name: Option<String>,
     },
 }
 
+fn module_limit_note(module_name: &Option<String>) -> String {
+    match module_name {
+        Some(module_name) => format!(r#", module "{module_name}" exceeded its max_memory"#),
+        None => String::new(),
+    }
+}
+
 impl From<std::convert::Infallible> for MarineError {
     fn from(_: std::convert::Infallible) -> Self {
         unreachable!()
@@ -115,3 +181,25 @@ macro_rules! json_to_marine_err {
         })
     };
 }
+
+#[macro_export]
+macro_rules! msgpack_to_marine_err {
+    ($msgpack_expr:expr, $module_name:expr, $function_name:expr) => {
+        $msgpack_expr.map_err(|e| match e {
+            it_msgpack_serde::ITMsgPackSeDeError::Se(_) => {
+                MarineError::MsgPackOutputSerializationError {
+                    module_name: $module_name,
+                    function_name: $function_name,
+                    error: e,
+                }
+            }
+            it_msgpack_serde::ITMsgPackSeDeError::De(_) => {
+                MarineError::MsgPackArgumentsDeserializationError {
+                    module_name: $module_name,
+                    function_name: $function_name,
+                    error: e,
+                }
+            }
+        })
+    };
+}
@@ -0,0 +1,146 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use parking_lot::Mutex;
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Runs blocking parts of host imports, like waiting for mounted binaries, off the executor.
+///
+/// Every host import owns its pool, so imports hanging in one service don't hold up
+/// the others. Threads are spawned on demand up to `max_threads`, further jobs wait
+/// in a queue, and the threads exit once the pool is dropped and the queue is drained.
+pub(crate) struct BlockingPool {
+    name: String,
+    max_threads: usize,
+    workers: Mutex<Workers>,
+    idle_threads: Arc<AtomicUsize>,
+}
+
+struct Workers {
+    sender: mpsc::Sender<Job>,
+    receiver: Arc<Mutex<mpsc::Receiver<Job>>>,
+    threads_count: usize,
+}
+
+impl BlockingPool {
+    pub(crate) fn new(name: impl Into<String>, max_threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let workers = Workers {
+            sender,
+            receiver: Arc::new(Mutex::new(receiver)),
+            threads_count: 0,
+        };
+
+        Self {
+            name: name.into(),
+            max_threads,
+            workers: Mutex::new(workers),
+            idle_threads: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Queues the job, returns an error if there is no thread to run it.
+    pub(crate) fn spawn(&self, job: impl FnOnce() + Send + 'static) -> std::io::Result<()> {
+        let mut workers = self.workers.lock();
+
+        if self.idle_threads.load(Ordering::Acquire) == 0
+            && workers.threads_count < self.max_threads
+        {
+            match self.spawn_thread(&workers, workers.threads_count) {
+                Ok(()) => workers.threads_count += 1,
+                // the job could still be run by one of the already running threads
+                Err(e) if workers.threads_count > 0 => {
+                    log::warn!("failed to spawn a thread for `{}`: {}", self.name, e)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        // the receiver is kept by the pool, so sending can't fail
+        workers
+            .sender
+            .send(Box::new(job))
+            .map_err(|_| std::io::Error::other("the blocking pool is closed"))
+    }
+
+    fn spawn_thread(&self, workers: &Workers, index: usize) -> std::io::Result<()> {
+        let receiver = workers.receiver.clone();
+        let idle_threads = self.idle_threads.clone();
+
+        std::thread::Builder::new()
+            .name(format!("{}-{}", self.name, index))
+            .spawn(move || loop {
+                idle_threads.fetch_add(1, Ordering::AcqRel);
+                // the lock is released before the job is run
+                let job = receiver.lock().recv();
+                idle_threads.fetch_sub(1, Ordering::AcqRel);
+
+                match job {
+                    // a panicking job must not take the thread down with it
+                    Ok(job) => {
+                        let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                    }
+                    // the pool is dropped
+                    Err(_) => return,
+                }
+            })
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlockingPool;
+
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn jobs_wait_for_a_free_thread() {
+        let pool = BlockingPool::new("test-pool", 1);
+        let (sender, receiver) = mpsc::channel();
+
+        for index in 0..3 {
+            let sender = sender.clone();
+            pool.spawn(move || {
+                std::thread::sleep(Duration::from_millis(10));
+                sender.send(index).unwrap();
+            })
+            .unwrap();
+        }
+
+        let results = receiver.iter().take(3).collect::<Vec<_>>();
+        assert_eq!(results, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn panicking_job_keeps_the_thread() {
+        let pool = BlockingPool::new("test-pool", 1);
+        let (sender, receiver) = mpsc::channel();
+
+        pool.spawn(|| panic!("job panicked")).unwrap();
+        pool.spawn(move || sender.send(()).unwrap()).unwrap();
+
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
@@ -44,12 +44,7 @@ pub(crate) fn create_call_parameters_import<WB: WasmBackend, CP: Serialize + Sen
         Some(result)
     };
 
-    HostImportDescriptor {
-        host_exported_func: Box::new(call_parameters_closure),
-        argument_types: vec![],
-        output_type: Some(IType::Record(0)),
-        error_handler: None,
-    }
+    HostImportDescriptor::new(call_parameters_closure, vec![], Some(IType::Record(0)))
 }
 
 pub(crate) fn call_parameters_v3_to_v0(
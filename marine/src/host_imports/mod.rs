@@ -15,6 +15,7 @@
  */
 
 pub(crate) mod logger;
+mod blocking_pool;
 mod call_parameters;
 mod mounted_binaries;
 
@@ -23,3 +24,4 @@ pub(crate) use call_parameters::call_parameters_v3_to_v0;
 pub(crate) use call_parameters::call_parameters_v3_to_v1;
 pub(crate) use call_parameters::call_parameters_v3_to_v2;
 pub(crate) use mounted_binaries::create_mounted_binary_import;
+pub(crate) use mounted_binaries::MountedBinaryPolicy;
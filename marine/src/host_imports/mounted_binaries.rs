@@ -14,6 +14,8 @@
  * limitations under the License.
  */
 
+use super::blocking_pool::BlockingPool;
+
 use marine_wasm_backend_traits::WasmBackend;
 
 use marine_core::generic::HostImportDescriptor;
@@ -21,74 +23,418 @@ use marine_rs_sdk::MountedBinaryResult;
 
 use wasmer_it::IValue;
 use wasmer_it::IType;
+use regex::Regex;
+use futures::channel::oneshot;
+use futures::FutureExt;
 
+use std::io::Read;
 use std::path::Path;
 use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+const TERMINATED_BY_SIGNAL_CODE: i32 = 100000;
+const COMMAND_ERROR_CODE: i32 = 100001;
+const ARGUMENT_NOT_ALLOWED_CODE: i32 = 100005;
+const TIMEOUT_EXCEEDED_CODE: i32 = 100006;
+const OUTPUT_LIMIT_EXCEEDED_CODE: i32 = 100007;
+
+/// How often a running binary is checked against the timeout and the output limit.
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Size of a chunk read from the binary output at once.
+const PIPE_CHUNK_SIZE: usize = 8 * 1024;
+
+/// Wall clock time after which a binary is killed, if the policy doesn't set another one.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Maximum number of binaries run at once by one import. Calls to a module are sequential,
+/// so more than one binary runs only when a call is cancelled and its binary is left
+/// running until the timeout.
+const MAX_RUNNING_BINARIES: usize = 4;
+
+/// Restrictions applied to a mounted binary. Every restriction is optional,
+/// an empty policy only limits the run time of the binary with `DEFAULT_TIMEOUT`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct MountedBinaryPolicy {
+    /// Each argument must fully match at least one of these patterns.
+    pub(crate) allowed_args: Option<Vec<Regex>>,
+    /// Wall clock time after which the binary is killed, `DEFAULT_TIMEOUT` if not set.
+    pub(crate) timeout: Option<Duration>,
+    /// Maximum size of stdout and of stderr, the binary is killed once any of them exceeds it.
+    pub(crate) max_output_size: Option<usize>,
+    /// Names of host environment variables passed to the binary, others are cleared.
+    pub(crate) allowed_envs: Option<Vec<String>>,
+    /// Working directory of the binary.
+    pub(crate) working_dir: Option<PathBuf>,
+}
+
+impl MountedBinaryPolicy {
+    /// Compiles argument patterns so that each of them has to match a whole argument.
+    pub(crate) fn compile_allowed_args(patterns: &[String]) -> Result<Vec<Regex>, regex::Error> {
+        patterns
+            .iter()
+            .map(|pattern| Regex::new(&format!("^(?:{})$", pattern)))
+            .collect()
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(DEFAULT_TIMEOUT)
+    }
+
+    fn check_args(&self, args: &[String]) -> Result<(), MountedBinaryResult> {
+        let allowed_args = match &self.allowed_args {
+            Some(allowed_args) => allowed_args,
+            None => return Ok(()),
+        };
+
+        match args
+            .iter()
+            .find(|arg| !allowed_args.iter().any(|regex| regex.is_match(arg)))
+        {
+            Some(arg) => Err(MountedBinaryResult::from_error(
+                ARGUMENT_NOT_ALLOWED_CODE,
+                format!(
+                    "argument `{}` is not allowed by the mounted binary policy",
+                    arg
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+}
 
 pub(crate) fn create_mounted_binary_import<WB: WasmBackend>(
     mounted_binary_path: PathBuf,
+    policy: MountedBinaryPolicy,
 ) -> HostImportDescriptor<WB> {
+    let mounted_binary_path: Arc<Path> = mounted_binary_path.into();
+    let policy = Arc::new(policy);
+    // every import has its own threads, so hanging binaries of one service
+    // don't hold up binaries of other services
+    let pool = Arc::new(BlockingPool::new(
+        "marine-mounted-binary",
+        MAX_RUNNING_BINARIES,
+    ));
+
     let host_cmd_closure = move |_ctx: &mut <WB as WasmBackend>::ImportCallContext<'_>,
                                  raw_args: Vec<IValue>| {
-        let result =
-            mounted_binary_import_impl(&mounted_binary_path, raw_args).unwrap_or_else(Into::into);
+        let mounted_binary_path = mounted_binary_path.clone();
+        let policy = policy.clone();
+        let (result_sender, result_receiver) = oneshot::channel();
+
+        // the binary is waited on a separate thread to not block the executor
+        let spawn_result = pool.spawn(move || {
+            let result = mounted_binary_import_impl(&mounted_binary_path, &policy, raw_args)
+                .unwrap_or_else(Into::into);
+            let _ = result_sender.send(result);
+        });
+
+        async move {
+            let result = match spawn_result {
+                Ok(()) => result_receiver.await.unwrap_or_else(|_| {
+                    MountedBinaryResult::from_error(
+                        COMMAND_ERROR_CODE,
+                        "mounted binary thread terminated unexpectedly",
+                    )
+                }),
+                Err(e) => MountedBinaryResult::from_error(
+                    COMMAND_ERROR_CODE,
+                    format!("no thread is available to run the mounted binary: {}", e),
+                ),
+            };
 
-        let raw_result = crate::to_interface_value(&result).unwrap();
+            let raw_result = crate::to_interface_value(&result).unwrap();
 
-        Some(raw_result)
+            Some(raw_result)
+        }
+        .boxed()
     };
 
-    HostImportDescriptor {
-        host_exported_func: Box::new(host_cmd_closure),
-        argument_types: vec![IType::Array(Box::new(IType::String))],
-        output_type: Some(IType::Record(0)),
-        error_handler: None,
-    }
+    HostImportDescriptor::new_async(
+        host_cmd_closure,
+        vec![IType::Array(Box::new(IType::String))],
+        Some(IType::Record(0)),
+    )
 }
 
 fn mounted_binary_import_impl(
     mounted_binary_path: &Path,
+    policy: &MountedBinaryPolicy,
     raw_args: Vec<IValue>,
 ) -> Result<MountedBinaryResult, MountedBinaryResult> {
     let args = parse_args(raw_args)?;
+    policy.check_args(&args)?;
 
-    let result = std::process::Command::new(mounted_binary_path)
-        .args(&args)
-        .output();
+    let result = run_binary(mounted_binary_path, &args, policy);
+    if let Err(e) = &result {
+        log::error!(
+            "error occurred on `{} {:?}`: {} ",
+            mounted_binary_path.display(),
+            args,
+            e.error
+        );
+    }
 
-    let result = match result {
-        Ok(output) => {
-            const TERMINATED_BY_SIGNAL_CODE: i32 = 100000;
-            let ret_code = output.status.code().unwrap_or(TERMINATED_BY_SIGNAL_CODE);
+    result
+}
 
-            MountedBinaryResult {
-                ret_code,
-                error: String::new(),
-                stdout: output.stdout,
-                stderr: output.stderr,
+fn run_binary(
+    mounted_binary_path: &Path,
+    args: &[String],
+    policy: &MountedBinaryPolicy,
+) -> Result<MountedBinaryResult, MountedBinaryResult> {
+    let mut command = Command::new(mounted_binary_path);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    if let Some(allowed_envs) = &policy.allowed_envs {
+        command.env_clear();
+        for name in allowed_envs {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
             }
         }
-        Err(e) => {
-            const COMMAND_ERROR_CODE: i32 = 100001;
-            let error = format!("{}", e);
-
-            log::error!(
-                "error occurred on `{} {:?}`: {} ",
-                mounted_binary_path.display(),
-                args,
-                e
-            );
-
-            MountedBinaryResult {
-                ret_code: COMMAND_ERROR_CODE,
-                error,
-                stdout: Vec::new(),
-                stderr: Vec::new(),
+    }
+
+    if let Some(working_dir) = &policy.working_dir {
+        command.current_dir(working_dir);
+    }
+
+    // processes spawned by the binary join its group, so they could be killed together with it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+
+    let mut child = command.spawn().map_err(command_error)?;
+
+    let deadline = Instant::now() + policy.timeout();
+    let result = wait_with_policy(&mut child, policy, deadline);
+    if result.is_err() {
+        kill_process_group(&mut child);
+    }
+
+    result
+}
+
+/// Waits for the binary to exit and to close its output pipes, collecting the output meanwhile.
+/// Processes spawned by the binary could keep its pipes open after it exited,
+/// so they are waited for too, until the timeout is reached.
+fn wait_with_policy(
+    child: &mut Child,
+    policy: &MountedBinaryPolicy,
+    deadline: Instant,
+) -> Result<MountedBinaryResult, MountedBinaryResult> {
+    let stdout = child.stdout.take().map(NonBlockingPipe::new).transpose();
+    let mut stdout = OutputPipe::new(stdout.map_err(command_error)?, policy.max_output_size);
+    let stderr = child.stderr.take().map(NonBlockingPipe::new).transpose();
+    let mut stderr = OutputPipe::new(stderr.map_err(command_error)?, policy.max_output_size);
+
+    let mut status = None;
+    loop {
+        // both pipes are read on every iteration, so a full one doesn't stall the binary
+        let has_read = stdout.read_available() | stderr.read_available();
+
+        // checked before the status, because a binary whose output is cut off may exit on its own
+        if stdout.is_exceeded() || stderr.is_exceeded() {
+            return Err(output_limit_exceeded());
+        }
+
+        if status.is_none() {
+            status = child.try_wait().map_err(command_error)?;
+        }
+
+        if let Some(status) = status {
+            if stdout.is_closed() && stderr.is_closed() {
+                return Ok(MountedBinaryResult {
+                    ret_code: status.code().unwrap_or(TERMINATED_BY_SIGNAL_CODE),
+                    error: String::new(),
+                    stdout: stdout.output,
+                    stderr: stderr.output,
+                });
             }
         }
-    };
 
-    Ok(result)
+        if Instant::now() >= deadline {
+            return Err(timeout_exceeded(policy));
+        }
+
+        if !has_read {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+/// Kills the binary and all processes it spawned, which are in its process group.
+fn kill_process_group(child: &mut Child) {
+    #[cfg(unix)]
+    // Safety: killpg doesn't access memory, the group id is the pid of a not yet reaped child
+    // or of a group kept alive by its remaining members, so it can't belong to another process
+    unsafe {
+        libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+    }
+
+    // the child could have already exited, so errors here are ignored
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn command_error(error: std::io::Error) -> MountedBinaryResult {
+    MountedBinaryResult::from_error(COMMAND_ERROR_CODE, error.to_string())
+}
+
+fn timeout_exceeded(policy: &MountedBinaryPolicy) -> MountedBinaryResult {
+    MountedBinaryResult::from_error(
+        TIMEOUT_EXCEEDED_CODE,
+        format!(
+            "mounted binary didn't finish in {:?} set by the policy",
+            policy.timeout()
+        ),
+    )
+}
+
+fn output_limit_exceeded() -> MountedBinaryResult {
+    MountedBinaryResult::from_error(
+        OUTPUT_LIMIT_EXCEEDED_CODE,
+        "mounted binary output exceeded the limit set by the policy",
+    )
+}
+
+/// Output of the binary collected from one of its pipes.
+struct OutputPipe {
+    /// `None` once the pipe is closed or the output limit is exceeded.
+    pipe: Option<NonBlockingPipe>,
+    output: Vec<u8>,
+    max_output_size: Option<usize>,
+}
+
+enum PipeChunk {
+    Data(Vec<u8>),
+    Pending,
+    Closed,
+}
+
+impl OutputPipe {
+    fn new(pipe: Option<NonBlockingPipe>, max_output_size: Option<usize>) -> Self {
+        Self {
+            pipe,
+            output: Vec::new(),
+            max_output_size,
+        }
+    }
+
+    /// Reads everything written to the pipe so far, returns true if anything was read.
+    fn read_available(&mut self) -> bool {
+        let mut has_read = false;
+        while let Some(pipe) = &mut self.pipe {
+            match pipe.read_chunk() {
+                PipeChunk::Data(chunk) => {
+                    self.output.extend_from_slice(&chunk);
+                    has_read = true;
+                }
+                PipeChunk::Pending => break,
+                PipeChunk::Closed => self.pipe = None,
+            }
+
+            if self.is_exceeded() {
+                self.pipe = None;
+            }
+        }
+
+        has_read
+    }
+
+    fn is_closed(&self) -> bool {
+        self.pipe.is_none()
+    }
+
+    fn is_exceeded(&self) -> bool {
+        self.max_output_size
+            .map_or(false, |max_output_size| self.output.len() > max_output_size)
+    }
+}
+
+/// A pipe that is read without blocking, so a single thread could watch the binary
+/// and both of its pipes.
+#[cfg(unix)]
+struct NonBlockingPipe(std::fs::File);
+
+#[cfg(unix)]
+impl NonBlockingPipe {
+    fn new(pipe: impl Into<std::os::fd::OwnedFd>) -> std::io::Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        let file = std::fs::File::from(pipe.into());
+        let fd = file.as_raw_fd();
+        // Safety: fcntl doesn't access memory, the descriptor is owned by the file
+        let is_set = unsafe {
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            flags >= 0 && libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) >= 0
+        };
+        if !is_set {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(Self(file))
+    }
+
+    fn read_chunk(&mut self) -> PipeChunk {
+        let mut buffer = [0u8; PIPE_CHUNK_SIZE];
+        loop {
+            match self.0.read(&mut buffer) {
+                Ok(0) => return PipeChunk::Closed,
+                Ok(size) => return PipeChunk::Data(buffer[..size].to_vec()),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return PipeChunk::Pending,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => return PipeChunk::Closed,
+            }
+        }
+    }
+}
+
+/// Pipes can't be made non-blocking on this platform, so a pipe is read by a separate thread,
+/// which exits once the pipe is closed or dropped.
+#[cfg(not(unix))]
+struct NonBlockingPipe(std::sync::mpsc::Receiver<Vec<u8>>);
+
+#[cfg(not(unix))]
+impl NonBlockingPipe {
+    fn new(mut pipe: impl Read + Send + 'static) -> std::io::Result<Self> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::Builder::new()
+            .name(String::from("marine-pipe-reader"))
+            .spawn(move || {
+                let mut buffer = [0u8; PIPE_CHUNK_SIZE];
+                loop {
+                    match pipe.read(&mut buffer) {
+                        Ok(0) => return,
+                        Ok(size) => {
+                            if sender.send(buffer[..size].to_vec()).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                        Err(_) => return,
+                    }
+                }
+            })?;
+
+        Ok(Self(receiver))
+    }
+
+    fn read_chunk(&mut self) -> PipeChunk {
+        match self.0.try_recv() {
+            Ok(chunk) => PipeChunk::Data(chunk),
+            Err(std::sync::mpsc::TryRecvError::Empty) => PipeChunk::Pending,
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => PipeChunk::Closed,
+        }
+    }
 }
 
 fn parse_args(mut raw_args: Vec<IValue>) -> Result<Vec<String>, MountedBinaryResult> {
@@ -119,12 +465,141 @@ fn parse_args(mut raw_args: Vec<IValue>) -> Result<Vec<String>, MountedBinaryRes
 #[cfg(test)]
 mod tests {
     use super::mounted_binary_import_impl;
+    use super::MountedBinaryPolicy;
+
+    use wasmer_it::IValue;
+
+    use std::path::Path;
+    use std::time::Duration;
+
+    fn args(args: &[&str]) -> Vec<IValue> {
+        let args = args
+            .iter()
+            .map(|arg| IValue::String(arg.to_string()))
+            .collect::<Vec<_>>();
+        vec![IValue::Array(args)]
+    }
 
     #[test]
     fn call_non_existent_binary() {
-        let path = std::path::Path::new("____non_existent_path____");
-        let actual = mounted_binary_import_impl(path, vec![]).unwrap_err();
+        let path = Path::new("____non_existent_path____");
+        let policy = MountedBinaryPolicy::default();
+        let actual = mounted_binary_import_impl(path, &policy, vec![]).unwrap_err();
 
         assert_eq!(actual.ret_code, 100002);
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn disallowed_argument() {
+        let allowed_args = vec!["-n".to_string(), "[a-z]+".to_string()];
+        let policy = MountedBinaryPolicy {
+            allowed_args: Some(MountedBinaryPolicy::compile_allowed_args(&allowed_args).unwrap()),
+            ..<_>::default()
+        };
+        let path = Path::new("echo");
+
+        let actual = mounted_binary_import_impl(path, &policy, args(&["-n", "abc"])).unwrap();
+        assert_eq!(actual.ret_code, 0);
+        assert_eq!(actual.stdout, b"abc");
+
+        // patterns have to match a whole argument
+        let actual = mounted_binary_import_impl(path, &policy, args(&["abc;1"])).unwrap_err();
+        assert_eq!(actual.ret_code, 100005);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout_exceeded() {
+        let policy = MountedBinaryPolicy {
+            timeout: Some(Duration::from_millis(100)),
+            ..<_>::default()
+        };
+        let path = Path::new("sleep");
+
+        let actual = mounted_binary_import_impl(path, &policy, args(&["10"])).unwrap_err();
+        assert_eq!(actual.ret_code, 100006);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn timeout_kills_spawned_processes() {
+        let policy = MountedBinaryPolicy {
+            timeout: Some(Duration::from_millis(100)),
+            ..<_>::default()
+        };
+        let path = Path::new("sh");
+
+        // the background process inherits the pipes and outlives the shell
+        let started = std::time::Instant::now();
+        let actual =
+            mounted_binary_import_impl(path, &policy, args(&["-c", "sleep 10 & echo started"]))
+                .unwrap_err();
+        assert_eq!(actual.ret_code, 100006);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn output_limit_exceeded() {
+        let policy = MountedBinaryPolicy {
+            max_output_size: Some(1024),
+            ..<_>::default()
+        };
+
+        // `yes` never stops on its own
+        let actual = mounted_binary_import_impl(Path::new("yes"), &policy, args(&[])).unwrap_err();
+        assert_eq!(actual.ret_code, 100007);
+
+        let actual =
+            mounted_binary_import_impl(Path::new("echo"), &policy, args(&["abc"])).unwrap();
+        assert_eq!(actual.ret_code, 0);
+        assert_eq!(actual.stdout, b"abc\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn large_output() {
+        let policy = MountedBinaryPolicy::default();
+
+        // more than a pipe buffer, so the binary waits until its output is read
+        let actual = mounted_binary_import_impl(
+            Path::new("head"),
+            &policy,
+            args(&["-c", "1000000", "/dev/zero"]),
+        )
+        .unwrap();
+        assert_eq!(actual.ret_code, 0);
+        assert_eq!(actual.stdout.len(), 1000000);
+        assert!(actual.stderr.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn env_whitelist() {
+        std::env::set_var("MOUNTED_BINARY_ALLOWED_ENV", "allowed");
+        std::env::set_var("MOUNTED_BINARY_DENIED_ENV", "denied");
+        let policy = MountedBinaryPolicy {
+            allowed_envs: Some(vec!["MOUNTED_BINARY_ALLOWED_ENV".to_string()]),
+            ..<_>::default()
+        };
+
+        let actual =
+            mounted_binary_import_impl(Path::new("/usr/bin/env"), &policy, args(&[])).unwrap();
+        assert_eq!(actual.stdout, b"MOUNTED_BINARY_ALLOWED_ENV=allowed\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn working_dir() {
+        let working_dir = std::env::temp_dir().canonicalize().unwrap();
+        let policy = MountedBinaryPolicy {
+            working_dir: Some(working_dir.clone()),
+            ..<_>::default()
+        };
+
+        let actual = mounted_binary_import_impl(Path::new("pwd"), &policy, args(&["-P"])).unwrap();
+        let expected = format!("{}\n", working_dir.display());
+        assert_eq!(actual.stdout, expected.as_bytes());
+    }
 }
@@ -34,19 +34,34 @@ mod module_loading;
 pub(crate) type MarineResult<T> = std::result::Result<T, MarineError>;
 
 pub use marine_interface::MarineInterface;
+pub use marine::FuelMeteredResult;
+pub use marine::ModuleCallOutput;
 
 pub use config::ConfigContext;
 pub use config::WithContext;
 pub use config::MarineWASIConfig;
+pub use config::MemoryDirSeed;
+pub use config::ModuleOutput;
 
 pub use config::TomlMarineConfig;
+pub use config::ConfigIssue;
+pub use config::ConfigIssueKind;
 pub use config::TomlMarineModuleConfig;
 pub use config::TomlMarineNamedModuleConfig;
 pub use config::TomlWASIConfig;
+pub use config::MemoryLimit;
+pub use config::TomlMountedBinary;
+pub use config::TomlMountedBinaryPolicy;
+pub use config::TomlMappedDir;
+pub use config::TomlMappedDirConfig;
+pub use config::TomlMappedDirKind;
+pub use config::TomlMappedDirAccess;
+pub use config::TomlModuleOutput;
 pub use config::TomlValue;
 pub use config::TomlValueTable;
 
 pub use errors::MarineError;
+pub use module_loading::ModuleDependencyIssue;
 
 // Re-exports from Marine
 pub use marine_core::IValue;
@@ -55,6 +70,7 @@ pub use marine_core::IFunctionArg;
 pub use marine_core::IType;
 pub use marine_core::MModuleInterface as MarineModuleInterface;
 pub use marine_core::MError;
+pub use marine_core::ReplacementIncompatibility;
 pub use marine_core::MFunctionSignature as MarineFunctionSignature;
 pub use marine_core::MemoryStats;
 pub use marine_core::ModuleMemoryStat;
@@ -63,6 +79,17 @@ pub use marine_core::HostImportError;
 pub use marine_core::to_interface_value;
 pub use marine_core::from_interface_values;
 pub use marine_core::ne_vec;
+pub use marine_core::MarineCoreSnapshot as MarineSnapshot;
+pub use marine_core::ModuleSnapshot;
+pub use marine_core::SnapshotError;
+pub use marine_core::CallTrace;
+pub use marine_core::CallSpan;
+pub use marine_core::CapturedOutput;
+pub use marine_core::DirUsage;
+pub use marine_core::MappedDirPolicy;
+pub use marine_core::MemoryDir;
+pub use marine_core::MemoryFile;
+pub use marine_core::MemoryNode;
 
 pub use marine_module_interface::interface::itype_text_view;
 
@@ -89,6 +116,7 @@ pub mod wasmtime {
     pub type MarineConfig = crate::config::MarineConfig<WasmBackend>;
 
     pub use marine_core::wasmtime::HostExportedFunc;
+    pub use marine_core::wasmtime::AsyncHostExportedFunc;
     pub use marine_core::wasmtime::HostImportDescriptor;
 }
 
@@ -15,31 +15,43 @@
  */
 
 use crate::config::MarineConfig;
+use crate::config::ModuleOutput;
 use crate::marine_interface::MarineInterface;
 use crate::MarineError;
 use crate::MarineResult;
 use crate::IValue;
 use crate::IType;
 use crate::MemoryStats;
+use crate::module_loading::check_effects;
+use crate::module_loading::check_signature;
 use crate::module_loading::load_modules_from_fs;
+use crate::module_loading::sort_by_imports;
 use crate::host_imports::logger::LoggerFilter;
 use crate::host_imports::logger::WASM_LOG_ENV_NAME;
 use crate::host_imports::call_parameters_v3_to_v0;
 use crate::host_imports::call_parameters_v3_to_v1;
 use crate::host_imports::call_parameters_v3_to_v2;
 use crate::json_to_marine_err;
+use crate::msgpack_to_marine_err;
 
 use marine_wasm_backend_traits::WasmBackend;
 #[cfg(feature = "raw-module-api")]
 use marine_wasm_backend_traits::WasiState;
+use marine_wasm_backend_traits::MemoryDir;
+use marine_wasm_backend_traits::DeterministicEnv;
+use marine_wasm_backend_traits::OutputCapture;
 
 use marine_core::MError;
 use marine_core::generic::MarineCore;
 use marine_core::IFunctionArg;
 use marine_core::MarineCoreConfig;
 use marine_core::MRecordTypes;
+use marine_core::MarineCoreSnapshot;
+use marine_core::CallTrace;
+use marine_core::CapturedOutput;
 use marine_utils::SharedString;
 use marine_rs_sdk::CallParameters;
+use marine_module_info_parser::signature::VerifyingKey;
 
 use parking_lot::Mutex;
 use serde_json::Value as JValue;
@@ -52,6 +64,22 @@ use std::sync::Arc;
 type MFunctionSignature = (Arc<Vec<IFunctionArg>>, Arc<Vec<IType>>);
 type MModuleInterface = (Arc<Vec<IFunctionArg>>, Arc<Vec<IType>>, Arc<MRecordTypes>);
 
+/// Result of a call made with a fuel limit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuelMeteredResult<T> {
+    pub result: T,
+
+    /// Amount of fuel consumed by the call.
+    pub consumed_fuel: u64,
+}
+
+/// Stdout and stderr a module wrote during a call, see `ModuleOutput::Capture`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleCallOutput {
+    pub module_name: String,
+    pub output: CapturedOutput,
+}
+
 struct ModuleInterface {
     function_signatures: HashMap<SharedString, MFunctionSignature>,
     record_types: Arc<MRecordTypes>,
@@ -73,6 +101,24 @@ pub struct Marine<WB: WasmBackend> {
 
     /// Cached module interfaces by names.
     module_interfaces_cache: HashMap<String, ModuleInterface>,
+
+    /// If set, only modules signed with one of these keys could be loaded.
+    trusted_keys: Option<Vec<VerifyingKey>>,
+
+    /// Virtual time and randomness of modules in the deterministic mode, reset on each call.
+    deterministic_env: Option<DeterministicEnv>,
+
+    /// Buffers for stdout and stderr of modules that don't inherit them from the host.
+    output_captures: HashMap<String, ModuleOutputCapture>,
+
+    /// Output captured during the last call, sorted by module names.
+    last_call_output: Vec<ModuleCallOutput>,
+}
+
+struct ModuleOutputCapture {
+    capture: OutputCapture,
+    /// If set, the output is written to the log instead of `last_call_output`.
+    forward_to_log: bool,
 }
 
 impl<WB: WasmBackend> Marine<WB> {
@@ -94,18 +140,69 @@ impl<WB: WasmBackend> Marine<WB> {
         Self::with_module_names::<MarineConfig<WB>>(backend, &modules, config).await
     }
 
+    /// Creates Marine from config deserialized from TOML and restores the modules state
+    /// from the snapshot previously made by [`Marine::snapshot`].
+    pub async fn with_raw_config_from_snapshot<C>(
+        backend: WB,
+        config: C,
+        snapshot: &MarineCoreSnapshot,
+    ) -> MarineResult<Self>
+    where
+        C: TryInto<MarineConfig<WB>>,
+        MarineError: From<C::Error>,
+    {
+        let config = config.try_into()?;
+        let modules = config
+            .modules_config
+            .iter()
+            .map(|m| -> MarineResult<(String, PathBuf)> {
+                Ok((m.import_name.clone(), m.get_path(&config.modules_dir)?))
+            })
+            .collect::<MarineResult<HashMap<String, PathBuf>>>()?;
+        let modules = load_modules_from_fs(&modules)?;
+
+        Self::with_modules_(backend, modules, config, Some(snapshot)).await
+    }
+
     /// Creates Marine with given modules.
     pub async fn with_modules<C>(
         backend: WB,
-        mut modules: HashMap<String, Vec<u8>>,
+        modules: HashMap<String, Vec<u8>>,
         config: C,
     ) -> MarineResult<Self>
     where
         C: TryInto<MarineConfig<WB>>,
         MarineError: From<C::Error>,
     {
-        let config = config.try_into()?;
-        let core_config = MarineCoreConfig::new(backend, config.total_memory_limit);
+        Self::with_modules_(backend, modules, config.try_into()?, None).await
+    }
+
+    /// Creates Marine with given modules and restores their state from the snapshot
+    /// previously made by [`Marine::snapshot`].
+    pub async fn with_modules_from_snapshot<C>(
+        backend: WB,
+        modules: HashMap<String, Vec<u8>>,
+        config: C,
+        snapshot: &MarineCoreSnapshot,
+    ) -> MarineResult<Self>
+    where
+        C: TryInto<MarineConfig<WB>>,
+        MarineError: From<C::Error>,
+    {
+        Self::with_modules_(backend, modules, config.try_into()?, Some(snapshot)).await
+    }
+
+    async fn with_modules_(
+        backend: WB,
+        mut modules: HashMap<String, Vec<u8>>,
+        config: MarineConfig<WB>,
+        snapshot: Option<&MarineCoreSnapshot>,
+    ) -> MarineResult<Self> {
+        let mut core_config = MarineCoreConfig::new(backend, config.total_memory_limit);
+        // a restored service could be snapshotted again
+        if config.snapshots || snapshot.is_some() {
+            core_config = core_config.with_snapshots();
+        }
         let mut marine = MarineCore::new(core_config)?;
         let call_parameters_v0 = Arc::<Mutex<marine_call_parameters_v0::CallParameters>>::default();
         let call_parameters_v1 = Arc::<Mutex<marine_call_parameters_v1::CallParameters>>::default();
@@ -113,12 +210,18 @@ impl<WB: WasmBackend> Marine<WB> {
         let call_parameters_v3 = Arc::<Mutex<CallParameters>>::default();
 
         let modules_dir = config.modules_dir;
+        let trusted_keys = config.trusted_keys;
+        let deterministic_env = config.deterministic.then(DeterministicEnv::default);
 
         // LoggerFilter can be initialized with an empty string
         let wasm_log_env = std::env::var(WASM_LOG_ENV_NAME).unwrap_or_default();
         let logger_filter = LoggerFilter::from_env_string(&wasm_log_env);
 
-        for module in config.modules_config {
+        let modules_config = sort_by_imports(config.modules_config, &modules)?;
+
+        let mut output_captures = HashMap::new();
+        let mut restored_modules = HashMap::new();
+        for module in modules_config {
             let module_bytes = modules.remove(&module.import_name).ok_or_else(|| {
                 MarineError::InstantiationError {
                     module_import_name: module.import_name.clone(),
@@ -127,7 +230,11 @@ impl<WB: WasmBackend> Marine<WB> {
                 }
             })?;
 
-            let marine_module_config = crate::config::make_marine_config(
+            check_signature(&module.import_name, &module_bytes, trusted_keys.as_deref())?;
+            check_effects(&module.import_name, &module_bytes, Some(&module.config))?;
+
+            let output_capture = ModuleOutputCapture::new(module.config.output);
+            let mut marine_module_config = crate::config::make_marine_config(
                 module.import_name.clone(),
                 Some(module.config),
                 call_parameters_v0.clone(),
@@ -136,6 +243,18 @@ impl<WB: WasmBackend> Marine<WB> {
                 call_parameters_v3.clone(),
                 &logger_filter,
             )?;
+            marine_module_config.wasi_parameters.deterministic_env = deterministic_env.clone();
+            marine_module_config.wasi_parameters.output_capture =
+                output_capture.as_ref().map(|output| output.capture.clone());
+            if let Some(output_capture) = output_capture {
+                output_captures.insert(module.import_name.clone(), output_capture);
+            }
+
+            if snapshot.is_some() {
+                // modules are loaded by the snapshot order after all configs are prepared
+                restored_modules.insert(module.import_name, (module_bytes, marine_module_config));
+                continue;
+            }
 
             marine
                 .load_module(module.import_name, &module_bytes, marine_module_config)
@@ -143,14 +262,29 @@ impl<WB: WasmBackend> Marine<WB> {
                 .map_err(|e| check_for_oom_and_convert_error(&marine, e))?;
         }
 
-        Ok(Self {
+        if let Some(snapshot) = snapshot {
+            marine
+                .restore(snapshot, restored_modules)
+                .await
+                .map_err(|e| check_for_oom_and_convert_error(&marine, e))?;
+        }
+
+        let mut marine = Self {
             core: marine,
             call_parameters_v0,
             call_parameters_v1,
             call_parameters_v2,
             call_parameters_v3,
             module_interfaces_cache: HashMap::new(),
-        })
+            trusted_keys,
+            deterministic_env,
+            output_captures,
+            last_call_output: Vec::new(),
+        };
+        // output of module starts mustn't be attributed to the first call
+        marine.drain_output("start of modules");
+
+        Ok(marine)
     }
 
     /// Searches for modules in `config.modules_dir`, loads only those in the `names` set
@@ -177,19 +311,40 @@ impl<WB: WasmBackend> Marine<WB> {
         args: &[IValue],
         call_parameters: marine_rs_sdk::CallParameters,
     ) -> MarineResult<Vec<IValue>> {
+        self.last_call_output.clear();
         self.update_call_parameters(call_parameters);
 
-        let result = self
-            .core
-            .call_async(module_name, func_name, args)
-            .await
-            .map_err(|e| check_for_oom_and_convert_error(&self.core, e))?;
-
-        self.core.clear_allocation_stats();
+        let (result, _) = self
+            .call_core(module_name.as_ref(), func_name.as_ref(), args, None)
+            .await?;
 
         Ok(result)
     }
 
+    /// Call a specified function like `call_with_ivalues_async`, but fail with
+    /// `MarineError::FuelExhausted` if the call consumes more than `fuel` units.
+    /// Fuel metering must be enabled in the Wasm backend config.
+    pub async fn call_with_ivalues_and_fuel_async(
+        &mut self,
+        module_name: impl AsRef<str>,
+        func_name: impl AsRef<str>,
+        args: &[IValue],
+        call_parameters: marine_rs_sdk::CallParameters,
+        fuel: u64,
+    ) -> MarineResult<FuelMeteredResult<Vec<IValue>>> {
+        self.last_call_output.clear();
+        self.update_call_parameters(call_parameters);
+
+        let (result, consumed_fuel) = self
+            .call_core(module_name.as_ref(), func_name.as_ref(), args, Some(fuel))
+            .await?;
+
+        Ok(FuelMeteredResult {
+            result,
+            consumed_fuel,
+        })
+    }
+
     /// Call a specified function of loaded on a startup module by its name.
     pub async fn call_with_json_async(
         &mut self,
@@ -198,12 +353,59 @@ impl<WB: WasmBackend> Marine<WB> {
         json_args: JValue,
         call_parameters: marine_rs_sdk::CallParameters,
     ) -> MarineResult<JValue> {
+        let (result, _) = self
+            .call_with_json_(
+                module_name.as_ref(),
+                func_name.as_ref(),
+                json_args,
+                call_parameters,
+                None,
+            )
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Call a specified function like `call_with_json_async`, but fail with
+    /// `MarineError::FuelExhausted` if the call consumes more than `fuel` units.
+    /// Fuel metering must be enabled in the Wasm backend config.
+    pub async fn call_with_json_and_fuel_async(
+        &mut self,
+        module_name: impl AsRef<str>,
+        func_name: impl AsRef<str>,
+        json_args: JValue,
+        call_parameters: marine_rs_sdk::CallParameters,
+        fuel: u64,
+    ) -> MarineResult<FuelMeteredResult<JValue>> {
+        let (result, consumed_fuel) = self
+            .call_with_json_(
+                module_name.as_ref(),
+                func_name.as_ref(),
+                json_args,
+                call_parameters,
+                Some(fuel),
+            )
+            .await?;
+
+        Ok(FuelMeteredResult {
+            result,
+            consumed_fuel,
+        })
+    }
+
+    async fn call_with_json_(
+        &mut self,
+        module_name: &str,
+        func_name: &str,
+        json_args: JValue,
+        call_parameters: marine_rs_sdk::CallParameters,
+        fuel: Option<u64>,
+    ) -> MarineResult<(JValue, u64)> {
         use it_json_serde::json_to_ivalues;
         use it_json_serde::ivalues_to_json;
 
-        let module_name = module_name.as_ref();
-        let func_name = func_name.as_ref();
-
+        // output of the previous call mustn't be taken for output of a call failed before start
+        self.last_call_output.clear();
         let (func_signature, output_types, record_types) =
             self.lookup_module_interface(module_name, func_name)?;
         let iargs = json_to_marine_err!(
@@ -218,21 +420,97 @@ impl<WB: WasmBackend> Marine<WB> {
 
         self.update_call_parameters(call_parameters);
 
-        let result = self
-            .core
-            .call_async(module_name, func_name, &iargs)
-            .await
-            .map_err(|e| check_for_oom_and_convert_error(&self.core, e))?;
-
-        self.core.clear_allocation_stats();
+        let (result, consumed_fuel) = self.call_core(module_name, func_name, &iargs, fuel).await?;
 
-        json_to_marine_err!(
+        let result = json_to_marine_err!(
             ivalues_to_json(result, &output_types, &record_types),
             module_name.to_string(),
             func_name.to_string()
+        )?;
+
+        Ok((result, consumed_fuel))
+    }
+
+    /// Call a specified function of loaded on a startup module by its name
+    /// with arguments and results encoded in MessagePack.
+    pub async fn call_with_msgpack_async(
+        &mut self,
+        module_name: impl AsRef<str>,
+        func_name: impl AsRef<str>,
+        msgpack_args: &[u8],
+        call_parameters: marine_rs_sdk::CallParameters,
+    ) -> MarineResult<Vec<u8>> {
+        use it_msgpack_serde::msgpack_to_ivalues;
+        use it_msgpack_serde::ivalues_to_msgpack;
+
+        let module_name = module_name.as_ref();
+        let func_name = func_name.as_ref();
+
+        self.last_call_output.clear();
+        let (func_signature, output_types, record_types) =
+            self.lookup_module_interface(module_name, func_name)?;
+        let iargs = msgpack_to_marine_err!(
+            msgpack_to_ivalues(
+                msgpack_args,
+                func_signature.iter().map(|arg| (&arg.name, &arg.ty)),
+                &record_types,
+            ),
+            module_name.to_string(),
+            func_name.to_string()
+        )?;
+
+        self.update_call_parameters(call_parameters);
+
+        let (result, _) = self.call_core(module_name, func_name, &iargs, None).await?;
+
+        msgpack_to_marine_err!(
+            ivalues_to_msgpack(result, &output_types, &record_types),
+            module_name.to_string(),
+            func_name.to_string()
         )
     }
 
+    /// Calls a function of the core with an optional fuel limit,
+    /// returns its result and the consumed fuel (0 if there were no limit).
+    async fn call_core(
+        &mut self,
+        module_name: &str,
+        func_name: &str,
+        args: &[IValue],
+        fuel: Option<u64>,
+    ) -> MarineResult<(Vec<IValue>, u64)> {
+        let result = match fuel {
+            Some(fuel) => {
+                self.core
+                    .call_with_fuel_async(module_name, func_name, args, fuel)
+                    .await
+            }
+            None => self
+                .core
+                .call_async(module_name, func_name, args)
+                .await
+                .map(|result| (result, 0)),
+        };
+        // output is drained even if the call failed, it may explain the failure
+        let particle_id = self.call_parameters_v3.lock().particle.id.clone();
+        self.drain_output(&format!(
+            "{module_name}.{func_name}, particle {particle_id}"
+        ));
+
+        let result = result.map_err(|e| match e {
+            MError::FuelExhausted(fuel_limit) => MarineError::FuelExhausted {
+                module_name: module_name.to_string(),
+                function_name: func_name.to_string(),
+                fuel_limit,
+            },
+            e => check_for_oom_and_convert_error(&self.core, e),
+        })?;
+
+        self.core.clear_allocation_stats();
+
+        Ok(result)
+    }
+
     /// Return all export functions (name and signatures) of loaded modules.
     pub fn get_interface(&self) -> MarineInterface<'_> {
         let modules = self.core.interface().collect();
@@ -245,6 +523,99 @@ impl<WB: WasmBackend> Marine<WB> {
         self.core.module_memory_stats()
     }
 
+    /// Replace a loaded module with a new version without recreating the whole Marine instance.
+    /// Modules importing functions from the replaced one are relinked to the new module,
+    /// the replacement is refused if it doesn't provide compatible functions for them.
+    pub async fn replace_module<C, S>(
+        &mut self,
+        name: S,
+        wasm_bytes: &[u8],
+        config: Option<C>,
+    ) -> MarineResult<()>
+    where
+        S: Into<String>,
+        C: TryInto<crate::generic::MarineModuleConfig<WB>>,
+        MarineError: From<C::Error>,
+    {
+        let config = config.map(|c| c.try_into()).transpose()?;
+        let name = name.into();
+
+        // LoggerFilter can be initialized with an empty string
+        let wasm_log_env = std::env::var(WASM_LOG_ENV_NAME).unwrap_or_default();
+        let logger_filter = LoggerFilter::from_env_string(&wasm_log_env);
+
+        check_signature(&name, wasm_bytes, self.trusted_keys.as_deref())?;
+        check_effects(&name, wasm_bytes, config.as_ref())?;
+
+        let output = config
+            .as_ref()
+            .map(|config| config.output)
+            .unwrap_or_default();
+        let output_capture = ModuleOutputCapture::new(output);
+        let mut marine_module_config = crate::config::make_marine_config(
+            name.clone(),
+            config,
+            self.call_parameters_v0.clone(),
+            self.call_parameters_v1.clone(),
+            self.call_parameters_v2.clone(),
+            self.call_parameters_v3.clone(),
+            &logger_filter,
+        )?;
+        marine_module_config.wasi_parameters.deterministic_env = self.deterministic_env.clone();
+        marine_module_config.wasi_parameters.output_capture =
+            output_capture.as_ref().map(|output| output.capture.clone());
+
+        self.core
+            .replace_module(&name, wasm_bytes, marine_module_config)
+            .await
+            .map_err(|e| check_for_oom_and_convert_error(&self.core, e))?;
+        self.set_output_capture(name.clone(), output_capture);
+        // output of the module start mustn't be attributed to the next call
+        self.drain_output(&format!("start of {name}"));
+
+        // signatures of the replaced module could change
+        self.module_interfaces_cache.clear();
+
+        Ok(())
+    }
+
+    /// Make a snapshot of the state of all loaded modules. It could be saved to disk
+    /// and used later to create a new Marine instance with the same state.
+    /// Snapshots must be enabled in the config, unless Marine was restored from a snapshot.
+    pub fn snapshot(&mut self) -> MarineResult<MarineCoreSnapshot> {
+        self.core.snapshot().map_err(Into::into)
+    }
+
+    /// Enable or disable recording of calls between modules, it's disabled by default.
+    pub fn set_call_tracing(&mut self, enabled: bool) {
+        self.core.set_call_tracing(enabled)
+    }
+
+    /// Return the tree of calls made by the last call into Marine, if tracing is enabled.
+    /// It could be exported with [`CallTrace::to_chrome_trace`] for viewing in chrome://tracing.
+    pub fn last_call_trace(&self) -> Option<CallTrace> {
+        self.core.last_call_trace()
+    }
+
+    /// Return stdout and stderr written during the last call into Marine by modules
+    /// with `ModuleOutput::Capture`, modules that wrote nothing are omitted.
+    /// After a module load it holds output written by the module start.
+    pub fn last_call_output(&self) -> &[ModuleCallOutput] {
+        &self.last_call_output
+    }
+
+    /// Returns an in-memory directory mapped to the guest path of a module, it could be
+    /// exported with `MemoryDir::export_to_host_dir` or `MemoryDir::export_to_tar`.
+    pub fn module_memory_dir(
+        &self,
+        module_name: impl AsRef<str>,
+        guest_path: impl AsRef<str>,
+    ) -> MarineResult<MemoryDir> {
+        self.core
+            .module_memory_dir(module_name, guest_path)
+            .map_err(Into::into)
+    }
+
     /// At first, tries to find function signature and record types in module_interface_cache,
     /// if there is no them, tries to look
     fn lookup_module_interface(
@@ -298,7 +669,48 @@ impl<WB: WasmBackend> Marine<WB> {
         Ok((arg_types, output_types, record_types))
     }
 
+    fn set_output_capture(&mut self, module_name: String, capture: Option<ModuleOutputCapture>) {
+        match capture {
+            Some(capture) => self.output_captures.insert(module_name, capture),
+            None => self.output_captures.remove(&module_name),
+        };
+    }
+
+    /// Moves output written by modules during a call or a module load either to
+    /// `last_call_output` or to the log, where it's marked with `call`.
+    fn drain_output(&mut self, call: &str) {
+        self.last_call_output.clear();
+
+        for (name, output_capture) in &self.output_captures {
+            let output = output_capture.capture.take();
+            if output.is_empty() {
+                continue;
+            }
+
+            if output_capture.forward_to_log {
+                log_output(name, call, &output);
+                continue;
+            }
+
+            self.last_call_output.push(ModuleCallOutput {
+                module_name: name.clone(),
+                output,
+            });
+        }
+
+        self.last_call_output
+            .sort_by(|lhs, rhs| lhs.module_name.cmp(&rhs.module_name));
+    }
+
     fn update_call_parameters(&mut self, call_parameters: CallParameters) {
+        if let Some(deterministic_env) = &self.deterministic_env {
+            let particle = &call_parameters.particle;
+            // the particle timestamp is in milliseconds
+            let timestamp_ns = particle.timestamp.saturating_mul(1_000_000);
+            let random_seed = [particle.id.as_bytes(), &particle.timestamp.to_le_bytes()].concat();
+            deterministic_env.reset(timestamp_ns, random_seed);
+        }
+
         {
             // a separate code block to unlock the mutex ASAP and to avoid double locking
             let mut cp = self.call_parameters_v0.lock();
@@ -346,7 +758,15 @@ impl<WB: WasmBackend> Marine<WB> {
         let wasm_log_env = std::env::var(WASM_LOG_ENV_NAME).unwrap_or_default();
         let logger_filter = LoggerFilter::from_env_string(&wasm_log_env);
 
-        let marine_module_config = crate::config::make_marine_config(
+        check_signature(&name, wasm_bytes, self.trusted_keys.as_deref())?;
+        check_effects(&name, wasm_bytes, config.as_ref())?;
+
+        let output = config
+            .as_ref()
+            .map(|config| config.output)
+            .unwrap_or_default();
+        let output_capture = ModuleOutputCapture::new(output);
+        let mut marine_module_config = crate::config::make_marine_config(
             name.clone(),
             config,
             self.call_parameters_v0.clone(),
@@ -355,14 +775,44 @@ impl<WB: WasmBackend> Marine<WB> {
             self.call_parameters_v3.clone(),
             &logger_filter,
         )?;
+        marine_module_config.wasi_parameters.deterministic_env = self.deterministic_env.clone();
+        marine_module_config.wasi_parameters.output_capture =
+            output_capture.as_ref().map(|output| output.capture.clone());
         self.core
-            .load_module(name, wasm_bytes, marine_module_config)
+            .load_module(name.clone(), wasm_bytes, marine_module_config)
             .await
-            .map_err(|e| check_for_oom_and_convert_error(&self.core, e))
+            .map_err(|e| check_for_oom_and_convert_error(&self.core, e))?;
+        self.set_output_capture(name.clone(), output_capture);
+        // output of the module start mustn't be attributed to the next call
+        self.drain_output(&format!("start of {name}"));
+
+        Ok(())
     }
 
     pub fn unload_module(&mut self, module_name: impl AsRef<str>) -> MarineResult<()> {
-        self.core.unload_module(module_name).map_err(Into::into)
+        self.core.unload_module(module_name.as_ref())?;
+        self.output_captures.remove(module_name.as_ref());
+
+        Ok(())
+    }
+
+    /// Returns the linear memory size of a module in bytes.
+    pub fn module_memory_size(&self, module_name: impl AsRef<str>) -> MarineResult<usize> {
+        self.core
+            .module_memory_size(module_name)
+            .map_err(Into::into)
+    }
+
+    /// Returns `len` bytes of a module linear memory starting from `offset` without changing it.
+    pub fn read_module_memory(
+        &self,
+        module_name: impl AsRef<str>,
+        offset: usize,
+        len: usize,
+    ) -> MarineResult<Vec<u8>> {
+        self.core
+            .read_module_memory(module_name, offset, len)
+            .map_err(Into::into)
     }
 
     pub fn module_wasi_state(
@@ -377,6 +827,41 @@ impl<WB: WasmBackend> Marine<WB> {
     }
 }
 
+impl ModuleOutputCapture {
+    /// Returns `None` for modules inheriting stdout and stderr from the host.
+    fn new(output: ModuleOutput) -> Option<Self> {
+        let (max_size, forward_to_log) = match output {
+            ModuleOutput::Inherit => return None,
+            ModuleOutput::Capture { max_size } => (max_size, false),
+            ModuleOutput::Log { max_size } => (max_size, true),
+        };
+
+        Some(Self {
+            capture: OutputCapture::new(max_size),
+            forward_to_log,
+        })
+    }
+}
+
+/// Writes captured output line by line with the module name as the target,
+/// stderr lines are logged as warnings.
+fn log_output(module_name: &str, call: &str, output: &CapturedOutput) {
+    let streams = [
+        (log::Level::Info, &output.stdout),
+        (log::Level::Warn, &output.stderr),
+    ];
+
+    for (level, data) in streams {
+        for line in String::from_utf8_lossy(data).lines() {
+            log::log!(target: module_name, level, "[{}] {}", call, line);
+        }
+    }
+
+    if output.truncated {
+        log::warn!(target: module_name, "[{}] output exceeded the limit and was truncated", call);
+    }
+}
+
 fn check_for_oom_and_convert_error<WB: WasmBackend>(
     core: &MarineCore<WB>,
     error: MError,
@@ -394,6 +879,7 @@ fn check_for_oom_and_convert_error<WB: WasmBackend>(
         MError::ITInstructionError(_)
         | MError::HostImportError(_)
         | MError::WasmBackendError(_) => MarineError::HighProbabilityOOM {
+            module_name: allocation_stats.module_limit_exceeded.clone(),
             allocation_stats,
             original_error: error,
         },
@@ -18,8 +18,12 @@ use super::IRecordType;
 use super::itype_text_view;
 use crate::MarineModuleInterface;
 
+use marine_module_interface::json_schema;
+use marine_module_interface::json_schema::FunctionTypes;
+
 use itertools::Itertools;
 use serde::Serialize;
+use serde_json::Value as JValue;
 
 use std::fmt;
 use std::collections::HashMap;
@@ -29,6 +33,40 @@ pub struct MarineInterface<'a> {
     pub modules: HashMap<&'a str, MarineModuleInterface<'a>>,
 }
 
+impl<'a> MarineInterface<'a> {
+    /// Returns JSON Schema documents for arguments and results of functions of each module,
+    /// see `marine_module_interface::json_schema::module_schema` for the layout.
+    pub fn json_schema(&self) -> JValue {
+        let modules = self
+            .modules
+            .iter()
+            .map(|(name, module)| (name.to_string(), module_json_schema(name, module)))
+            .collect::<serde_json::Map<_, _>>();
+
+        JValue::Object(modules)
+    }
+
+    /// Returns JSON Schema document of the module with the supplied name.
+    pub fn module_json_schema(&self, module_name: &str) -> Option<JValue> {
+        self.modules
+            .get(module_name)
+            .map(|module| module_json_schema(module_name, module))
+    }
+}
+
+fn module_json_schema(module_name: &str, module: &MarineModuleInterface<'_>) -> JValue {
+    let functions = module
+        .function_signatures
+        .iter()
+        .map(|signature| FunctionTypes {
+            name: &signature.name,
+            arguments: &signature.arguments,
+            outputs: &signature.outputs,
+        });
+
+    json_schema::module_schema(module_name, functions, module.record_types)
+}
+
 impl<'a> fmt::Display for MarineInterface<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         print_record_types(self.modules.values(), f)?;
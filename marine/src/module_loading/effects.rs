@@ -0,0 +1,98 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::MarineError;
+use crate::MarineResult;
+use crate::config::MarineModuleConfig;
+
+use marine_module_info_parser::effects;
+use marine_module_info_parser::effects::WasmEffect;
+use marine_wasm_backend_traits::WasmBackend;
+
+use std::collections::HashSet;
+
+/// Checks that the module uses only the logger and host imports granted by its config,
+/// if the config enforces effects. Granted but unused capabilities are reported as warnings.
+pub(crate) fn check_effects<WB: WasmBackend>(
+    module_name: &str,
+    wasm_bytes: &[u8],
+    config: Option<&MarineModuleConfig<WB>>,
+) -> MarineResult<()> {
+    let config = match config {
+        Some(config) if config.enforce_effects => config,
+        _ => return Ok(()),
+    };
+
+    let module_effects = effects::extract_from_bytes(wasm_bytes).map_err(|error| {
+        MarineError::EffectsExtractionError {
+            module_name: module_name.to_string(),
+            error,
+        }
+    })?;
+
+    let granted_imports = config
+        .host_imports
+        .values()
+        .flat_map(|imports| imports.keys())
+        .map(String::as_str)
+        .collect::<HashSet<_>>();
+
+    let mut uses_logger = false;
+    let mut used_imports = HashSet::new();
+    let mut ungranted_effects = Vec::new();
+    for effect in module_effects.iter() {
+        match effect {
+            WasmEffect::Logger => {
+                uses_logger = true;
+                if !config.logger_enabled {
+                    ungranted_effects.push("logger".to_string());
+                }
+            }
+            WasmEffect::MountedBinary(name) => {
+                used_imports.insert(name.as_str());
+                if !granted_imports.contains(name.as_str()) {
+                    ungranted_effects.push(format!("host import `{}`", name));
+                }
+            }
+        }
+    }
+
+    if !ungranted_effects.is_empty() {
+        ungranted_effects.sort();
+        ungranted_effects.dedup();
+        return Err(MarineError::UngrantedEffects {
+            module_name: module_name.to_string(),
+            effects: ungranted_effects,
+        });
+    }
+
+    if config.logger_enabled && !uses_logger {
+        log::warn!(
+            r#"module "{}" is granted the logger, but doesn't use it"#,
+            module_name
+        );
+    }
+
+    for import_name in granted_imports.difference(&used_imports) {
+        log::warn!(
+            r#"module "{}" is granted host import `{}`, but doesn't use it"#,
+            module_name,
+            import_name
+        );
+    }
+
+    Ok(())
+}
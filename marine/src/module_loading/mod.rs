@@ -14,6 +14,17 @@
  * limitations under the License.
  */
 
+mod effects;
+mod ordering;
+mod signature;
+
+pub(crate) use effects::check_effects;
+pub(crate) use ordering::load_order;
+pub(crate) use ordering::sort_by_imports;
+pub(crate) use ordering::LoadOrder;
+pub use ordering::ModuleDependencyIssue;
+pub(crate) use signature::check_signature;
+
 use crate::MarineError;
 use crate::MarineResult;
 
@@ -0,0 +1,214 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::MarineError;
+use crate::MarineResult;
+use crate::config::ModuleDescriptor;
+
+use marine_it_parser::extract_import_namespaces;
+use marine_module_info_parser::effects::is_host_import;
+use marine_wasm_backend_traits::WasmBackend;
+use thiserror::Error as ThisError;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+/// A problem that prevents loading modules in an order satisfying their imports.
+#[derive(Debug, Clone, PartialEq, Eq, ThisError)]
+pub enum ModuleDependencyIssue {
+    #[error(
+        r#"module "{module}" imports functions from module "{provider}" which isn't provided"#
+    )]
+    MissingProvider { module: String, provider: String },
+
+    #[error("modules import functions from each other: {}", .0.join(" -> "))]
+    ImportCycle(Vec<String>),
+}
+
+pub(crate) struct LoadOrder {
+    /// Indices of modules that could be loaded, in the loading order.
+    pub(crate) order: Vec<usize>,
+    pub(crate) issues: Vec<ModuleDependencyIssue>,
+}
+
+/// Sorts modules so that each one is loaded after the modules it imports functions from,
+/// modules not depending on each other keep their relative order. Imports from absent modules
+/// are reported and ignored, modules in cycles and modules depending on them are left out.
+pub(crate) fn load_order(modules: &[(&str, Vec<String>)]) -> LoadOrder {
+    let mut index_by_name = HashMap::new();
+    for (index, (name, _)) in modules.iter().enumerate() {
+        index_by_name.entry(*name).or_insert(index);
+    }
+
+    let mut issues = Vec::new();
+    let dependencies = modules
+        .iter()
+        .map(|(name, namespaces)| {
+            namespaces
+                .iter()
+                .filter(|namespace| !is_host_import(namespace))
+                .filter_map(|namespace| match index_by_name.get(namespace.as_str()) {
+                    Some(index) => Some(*index),
+                    None => {
+                        issues.push(ModuleDependencyIssue::MissingProvider {
+                            module: name.to_string(),
+                            provider: namespace.clone(),
+                        });
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut loaded = vec![false; modules.len()];
+    let mut order = Vec::with_capacity(modules.len());
+    while let Some(next) = (0..modules.len())
+        .find(|&index| !loaded[index] && dependencies[index].iter().all(|&dep| loaded[dep]))
+    {
+        loaded[next] = true;
+        order.push(next);
+    }
+
+    // each of the remaining modules depends on at least one remaining module,
+    // so following these dependencies always leads to a cycle
+    let mut visited = HashSet::new();
+    for start in (0..modules.len()).filter(|&index| !loaded[index]) {
+        if visited.contains(&start) {
+            continue;
+        }
+
+        let mut path = vec![start];
+        loop {
+            let last = path[path.len() - 1];
+            let next = dependencies[last]
+                .iter()
+                .copied()
+                .find(|&dep| !loaded[dep])
+                .expect("a remaining module must have a remaining dependency");
+
+            if let Some(position) = path.iter().position(|&index| index == next) {
+                let cycle = &path[position..];
+                if !cycle.iter().any(|index| visited.contains(index)) {
+                    let names = cycle
+                        .iter()
+                        .chain(std::iter::once(&next))
+                        .map(|&index| modules[index].0.to_string())
+                        .collect();
+                    issues.push(ModuleDependencyIssue::ImportCycle(names));
+                }
+                break;
+            }
+
+            if visited.contains(&next) {
+                break;
+            }
+            path.push(next);
+        }
+
+        visited.extend(path);
+    }
+
+    LoadOrder { order, issues }
+}
+
+/// Reorders module descriptors so that every module is loaded after the modules it imports from.
+pub(crate) fn sort_by_imports<WB: WasmBackend>(
+    modules_config: Vec<ModuleDescriptor<WB>>,
+    modules: &HashMap<String, Vec<u8>>,
+) -> MarineResult<Vec<ModuleDescriptor<WB>>> {
+    let imports = modules_config
+        .iter()
+        .map(|module| {
+            // absent or malformed modules are reported by the loader with a more precise error
+            let namespaces = modules
+                .get(&module.import_name)
+                .and_then(|wasm_bytes| extract_import_namespaces(wasm_bytes).ok())
+                .unwrap_or_default();
+
+            (module.import_name.as_str(), namespaces)
+        })
+        .collect::<Vec<_>>();
+
+    let LoadOrder { order, issues } = load_order(&imports);
+    if !issues.is_empty() {
+        return Err(MarineError::UnresolvableModuleImports(issues));
+    }
+
+    let mut modules_config = modules_config.into_iter().map(Some).collect::<Vec<_>>();
+    let sorted = order
+        .into_iter()
+        .filter_map(|index| modules_config[index].take())
+        .collect();
+
+    Ok(sorted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order_of<'n>(modules: &[(&'n str, &[&str])]) -> (Vec<&'n str>, Vec<ModuleDependencyIssue>) {
+        let modules = modules
+            .iter()
+            .map(|(name, imports)| (*name, imports.iter().map(|i| i.to_string()).collect()))
+            .collect::<Vec<_>>();
+
+        let LoadOrder { order, issues } = load_order(&modules);
+        let order = order.into_iter().map(|index| modules[index].0).collect();
+
+        (order, issues)
+    }
+
+    #[test]
+    fn dependencies_are_loaded_first() {
+        let (order, issues) = order_of(&[
+            ("facade", &["b", "host"]),
+            ("b", &["a"]),
+            ("c", &[]),
+            ("a", &[]),
+        ]);
+
+        assert_eq!(order, vec!["c", "a", "b", "facade"]);
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn cycles_and_missing_providers_are_reported() {
+        let (order, issues) = order_of(&[
+            ("facade", &["a"]),
+            ("a", &["b"]),
+            ("b", &["a"]),
+            ("c", &["d"]),
+        ]);
+
+        assert_eq!(order, vec!["c"]);
+        assert_eq!(
+            issues,
+            vec![
+                ModuleDependencyIssue::MissingProvider {
+                    module: "c".to_string(),
+                    provider: "d".to_string(),
+                },
+                ModuleDependencyIssue::ImportCycle(vec![
+                    "a".to_string(),
+                    "b".to_string(),
+                    "a".to_string()
+                ]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,48 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::MarineError;
+use crate::MarineResult;
+
+use marine_module_info_parser::signature;
+use marine_module_info_parser::signature::VerifyingKey;
+
+/// Checks that the module is signed with one of the trusted keys, if they are set.
+pub(crate) fn check_signature(
+    module_name: &str,
+    wasm_bytes: &[u8],
+    trusted_keys: Option<&[VerifyingKey]>,
+) -> MarineResult<()> {
+    let trusted_keys = match trusted_keys {
+        Some(trusted_keys) => trusted_keys,
+        None => return Ok(()),
+    };
+
+    let signer = signature::verify_trusted(wasm_bytes, trusted_keys).map_err(|error| {
+        MarineError::UntrustedModule {
+            module_name: module_name.to_string(),
+            error,
+        }
+    })?;
+
+    log::debug!(
+        r#"module "{}" is signed with trusted key {}"#,
+        module_name,
+        signature::key_to_hex(&signer)
+    );
+
+    Ok(())
+}
@@ -18,6 +18,9 @@ extern crate core;
 
 use marine::Marine;
 use marine::TomlMarineConfig;
+use marine::MemoryDir;
+use marine::MemoryNode;
+use marine::MarineError;
 use marine_wasmtime_backend::WasmtimeWasmBackend;
 use marine_wasm_backend_traits::WasmBackend;
 
@@ -86,6 +89,261 @@ async fn wasi_mapped_dirs() {
     }
 }
 
+#[tokio::test]
+async fn wasi_memory_dirs() {
+    let config_path = "tests/wasm_tests/wasi/MemoryDir.toml";
+    let raw_config = TomlMarineConfig::load(config_path).expect("Config must be loaded");
+    let mut marine = Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), raw_config)
+        .await
+        .expect("Marine should load all modules");
+
+    let file_data = std::fs::read("tests/wasm_tests/wasi/some_dir/some_file")
+        .expect("file must exist for test to work");
+    let result = marine
+        .call_with_json_async(
+            "wasi_effector",
+            "read_from_mapped_dir",
+            json!([]),
+            <_>::default(),
+        )
+        .await
+        .expect("function should execute successfully");
+    assert_eq!(result, json!(file_data));
+
+    marine
+        .call_with_json_async(
+            "wasi_effector",
+            "write_to_mapped_dir",
+            json!(["result", [1, 2, 3]]),
+            <_>::default(),
+        )
+        .await
+        .expect("function should execute successfully");
+    assert!(!std::path::Path::new("tests/wasm_tests/wasi/some_dir/written").exists());
+
+    let dir = marine
+        .module_memory_dir("wasi_effector", "some_dir")
+        .expect("memory dir must be mapped");
+    let mut tar = Vec::new();
+    dir.export_to_tar(&mut tar).unwrap();
+    let exported = MemoryDir::from_tar(tar.as_slice()).unwrap();
+
+    let written = match exported.get("written") {
+        Some(MemoryNode::Dir(written)) => written,
+        _ => panic!("written dir must be exported"),
+    };
+    match written.get("result") {
+        Some(MemoryNode::File(file)) => assert_eq!(file.to_vec(), [1, 2, 3]),
+        _ => panic!("result file must be exported"),
+    }
+}
+
+#[tokio::test]
+async fn wasi_read_only_dirs() {
+    let config_path = "tests/wasm_tests/wasi/ReadOnlyDir.toml";
+    let raw_config = TomlMarineConfig::load(config_path).expect("Config must be loaded");
+    let mut marine = Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), raw_config)
+        .await
+        .expect("Marine should load all modules");
+
+    marine
+        .call_with_json_async(
+            "wasi_effector",
+            "read_from_mapped_dir",
+            json!([]),
+            <_>::default(),
+        )
+        .await
+        .expect("reading from a read-only dir should succeed");
+
+    let result = marine
+        .call_with_json_async(
+            "wasi_effector",
+            "write_to_mapped_dir",
+            json!(["result", [1, 2, 3]]),
+            <_>::default(),
+        )
+        .await;
+    assert!(result.is_err(), "writing to a read-only dir should fail");
+
+    let dir = marine
+        .module_memory_dir("wasi_effector", "some_dir")
+        .expect("memory dir must be mapped");
+    assert!(dir.get("written").is_none());
+}
+
+#[tokio::test]
+async fn wasi_dir_quotas() {
+    let config_path = "tests/wasm_tests/wasi/QuotaDir.toml";
+    let raw_config = TomlMarineConfig::load(config_path).expect("Config must be loaded");
+    let mut marine = Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), raw_config)
+        .await
+        .expect("Marine should load all modules");
+
+    // the seed takes 10 bytes of 16 and 1 file of 4, the written dir and file take 2 more
+    write_to_mapped_dir(&mut marine, "first", vec![1; 3])
+        .await
+        .expect("write within quotas should succeed");
+    assert!(write_to_mapped_dir(&mut marine, "second", vec![2; 10])
+        .await
+        .is_err());
+    assert!(write_to_mapped_dir(&mut marine, "third", vec![])
+        .await
+        .is_err());
+
+    let dir = marine
+        .module_memory_dir("wasi_effector", "some_dir")
+        .expect("memory dir must be mapped");
+    let written = match dir.get("written") {
+        Some(MemoryNode::Dir(written)) => written,
+        _ => panic!("written dir must exist"),
+    };
+    let names = written
+        .entries()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["first", "second"]);
+    assert_eq!(dir.usage().bytes, 13);
+}
+
+#[tokio::test]
+async fn wasi_dir_quotas_are_shared_by_modules() {
+    let shared_dir =
+        std::env::temp_dir().join(format!("marine-shared-quota-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&shared_dir);
+    std::fs::create_dir_all(shared_dir.join("data")).unwrap();
+
+    // both modules map the same host dir, so they are limited by its total size
+    let module = |name: &str| {
+        format!(
+            r#"
+            [[module]]
+                name = "{name}"
+                file_name = "wasi_effector.wasm"
+                [module.wasi]
+                    mapped_dirs = {{ "some_dir" = {{ path = {:?}, max_size = "16 B" }} }}
+            "#,
+            shared_dir.join("data").display().to_string(),
+        )
+    };
+    let config = format!(
+        "modules_dir = {:?}\ntotal_memory_limit = \"10 MiB\"\n{}{}",
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/wasm_tests/wasi/artifacts/"
+        ),
+        module("first_effector"),
+        module("second_effector"),
+    );
+    let config_path = shared_dir.join("Config.toml");
+    std::fs::write(&config_path, config).unwrap();
+
+    let raw_config = TomlMarineConfig::load(&config_path).expect("Config must be loaded");
+    let mut marine = Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), raw_config)
+        .await
+        .expect("Marine should load all modules");
+
+    let data = json!(["file", vec![1; 10]]);
+    let first = marine
+        .call_with_json_async(
+            "first_effector",
+            "write_to_mapped_dir",
+            data,
+            <_>::default(),
+        )
+        .await;
+    let data = json!(["another_file", vec![2; 10]]);
+    let second = marine
+        .call_with_json_async(
+            "second_effector",
+            "write_to_mapped_dir",
+            data,
+            <_>::default(),
+        )
+        .await;
+    std::fs::remove_dir_all(&shared_dir).unwrap();
+
+    first.expect("write within the quota should succeed");
+    assert!(
+        second.is_err(),
+        "the quota should count writes of both modules"
+    );
+}
+
+#[tokio::test]
+async fn wasi_captured_output() {
+    let config_path = "tests/wasm_tests/wasi/CapturedOutput.toml";
+    let raw_config = TomlMarineConfig::load(config_path).expect("Config must be loaded");
+    let mut marine = Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), raw_config)
+        .await
+        .expect("Marine should load all modules");
+
+    print_output(&mut marine, "hello", "oops").await;
+    let outputs = marine.last_call_output();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0].module_name, "wasi_effector");
+    assert_eq!(outputs[0].output.stdout, b"hello\n");
+    assert_eq!(outputs[0].output.stderr, b"oops\n");
+    assert!(!outputs[0].output.truncated);
+
+    // the output is drained after each call, even after failed ones
+    write_to_mapped_dir(&mut marine, "file", vec![])
+        .await
+        .expect_err("module has no mapped dirs");
+    let output = &marine.last_call_output()[0].output;
+    assert!(output.stdout.is_empty());
+    // the panic message of the module
+    assert!(!output.stderr.is_empty());
+
+    print_output(&mut marine, "a long line that doesn't fit", "").await;
+    let output = &marine.last_call_output()[0].output;
+    assert_eq!(output.stdout, b"a long line that");
+    assert!(output.truncated);
+
+    // output of the previous call isn't kept, if a call fails before the module is called
+    let result = marine
+        .call_with_json_async(
+            "wasi_effector",
+            "print_output",
+            json!([1, 2]),
+            <_>::default(),
+        )
+        .await;
+    assert!(
+        result.is_err(),
+        "arguments of wrong types should be refused"
+    );
+    assert!(marine.last_call_output().is_empty());
+}
+
+async fn print_output(marine: &mut Marine, stdout: &str, stderr: &str) {
+    marine
+        .call_with_json_async(
+            "wasi_effector",
+            "print_output",
+            json!([stdout, stderr]),
+            <_>::default(),
+        )
+        .await
+        .expect("call should succeed");
+}
+
+async fn write_to_mapped_dir(
+    marine: &mut Marine,
+    file_name: &str,
+    data: Vec<u8>,
+) -> Result<Value, MarineError> {
+    marine
+        .call_with_json_async(
+            "wasi_effector",
+            "write_to_mapped_dir",
+            json!([file_name, data]),
+            <_>::default(),
+        )
+        .await
+}
+
 #[tokio::test]
 async fn mapping_from_absolute_path_in_wasi_allowed() {
     let config_path = "tests/wasm_tests/wasi/MapFromAbsolutePath.toml";
@@ -94,3 +352,204 @@ async fn mapping_from_absolute_path_in_wasi_allowed() {
         .await
         .expect("Module should be loaded successfully");
 }
+
+#[test]
+fn validate_valid_config() {
+    let config_path = "tests/config_tests/ModulesDirConfig.toml";
+    let raw_config = TomlMarineConfig::load(config_path).expect("Config must be loaded");
+
+    assert_eq!(raw_config.validate(), vec![]);
+}
+
+#[test]
+fn validate_reports_all_issues() {
+    use marine::ConfigIssue;
+    use marine::ConfigIssueKind;
+
+    let raw_config = r#"
+        modules_dir = "renamed_records_passing"
+        total_memory_limit = "Infinity"
+        facade = "absent_facade"
+
+        [[module]]
+        name = "records_passing_pure"
+
+            [module.mounted_binaries]
+            missing = "/nonexistent/binary"
+            unused = "/bin/echo"
+
+        [[module]]
+        name = "records_passing_effector"
+        file_name = "records_passing_effector_renamed.wasm"
+
+        [[module]]
+        name = "records_passing_effector"
+
+        [[module]]
+        name = "absent_module"
+    "#;
+
+    let mut raw_config: TomlMarineConfig =
+        toml::from_str(raw_config).expect("Config must be parsed");
+    raw_config.base_path = std::path::PathBuf::from("tests/config_tests")
+        .canonicalize()
+        .unwrap();
+
+    let issues = raw_config.validate();
+    let issue = |location: &str, kind: ConfigIssueKind| ConfigIssue {
+        location: location.to_string(),
+        kind,
+    };
+
+    assert_eq!(
+        issues,
+        vec![
+            issue(
+                "facade",
+                ConfigIssueKind::UnknownFacade("absent_facade".to_string())
+            ),
+            issue(
+                r#"module "records_passing_pure", mounted_binaries.missing"#,
+                ConfigIssueKind::MountedBinaryNotFound("/nonexistent/binary".into())
+            ),
+            issue(
+                r#"module "records_passing_pure", mounted_binaries.missing"#,
+                ConfigIssueKind::UnusedMountedBinary
+            ),
+            issue(
+                r#"module "records_passing_pure", mounted_binaries.unused"#,
+                ConfigIssueKind::UnusedMountedBinary
+            ),
+            issue(
+                r#"module "records_passing_effector""#,
+                ConfigIssueKind::DuplicateModule
+            ),
+            issue(
+                r#"module "absent_module""#,
+                ConfigIssueKind::ModuleNotFound(
+                    raw_config
+                        .base_path
+                        .join("renamed_records_passing/absent_module.wasm")
+                )
+            ),
+        ]
+    );
+}
+
+#[test]
+fn validate_missing_mounted_binary() {
+    use marine::ConfigIssueKind;
+
+    let mut raw_config = TomlMarineConfig::load("tests/wasm_tests/mounted_binaries/Config.toml")
+        .expect("Config must be loaded");
+    raw_config.module[0]
+        .config
+        .mounted_binaries
+        .as_mut()
+        .unwrap()
+        .remove("sleep");
+
+    let issues = raw_config.validate();
+    assert_eq!(issues.len(), 1);
+    assert_eq!(
+        issues[0].kind,
+        ConfigIssueKind::MissingMountedBinary("sleep".to_string())
+    );
+    assert_eq!(issues[0].location, r#"module "mounted_binaries_effector""#);
+}
+
+#[tokio::test]
+async fn trusted_keys_refuse_unsigned_and_untrusted_modules() {
+    use marine::MarineError;
+    use marine_module_info_parser::signature;
+    use marine_module_info_parser::signature::SignatureError;
+    use marine_module_info_parser::signature::SigningKey;
+
+    use std::collections::HashMap;
+
+    let trusted_key = SigningKey::from_bytes(&[1; 32]);
+    let untrusted_key = SigningKey::from_bytes(&[2; 32]);
+
+    let mut raw_config = TomlMarineConfig::load("tests/config_tests/ModulesDirConfig.toml")
+        .expect("Config must be loaded");
+    raw_config.trusted_keys = Some(vec![signature::key_to_hex(&trusted_key.verifying_key())]);
+
+    let effector = std::fs::read(
+        "tests/config_tests/renamed_records_passing/records_passing_effector_renamed.wasm",
+    )
+    .unwrap();
+    let pure =
+        std::fs::read("tests/config_tests/renamed_records_passing/records_passing_pure.wasm")
+            .unwrap();
+
+    let load = |effector: Vec<u8>, pure: Vec<u8>| {
+        let modules = HashMap::from([
+            ("records_passing_effector".to_string(), effector),
+            ("records_passing_pure".to_string(), pure),
+        ]);
+        Marine::with_modules(
+            WasmtimeWasmBackend::new_async().unwrap(),
+            modules,
+            raw_config.clone(),
+        )
+    };
+
+    let signed_effector = signature::sign(&effector, &trusted_key).unwrap();
+    let signed_pure = signature::sign(&pure, &trusted_key).unwrap();
+    load(signed_effector.clone(), signed_pure)
+        .await
+        .expect("Marine should load modules signed with a trusted key");
+
+    let result = load(effector, pure.clone()).await;
+    assert!(matches!(
+        result,
+        Err(MarineError::UntrustedModule {
+            module_name,
+            error: SignatureError::Unsigned,
+        }) if module_name == "records_passing_effector"
+    ));
+
+    let wrongly_signed_pure = signature::sign(&pure, &untrusted_key).unwrap();
+    let result = load(signed_effector, wrongly_signed_pure).await;
+    assert!(matches!(
+        result,
+        Err(MarineError::UntrustedModule {
+            module_name,
+            error: SignatureError::UntrustedKey(_),
+        }) if module_name == "records_passing_pure"
+    ));
+}
+
+#[tokio::test]
+async fn modules_are_loaded_in_import_order() {
+    use marine::MarineError;
+    use marine::ModuleDependencyIssue;
+
+    let config_path = "tests/config_tests/ModulesDirConfig.toml";
+    let mut raw_config = TomlMarineConfig::load(config_path).expect("Config must be loaded");
+    // records_passing_pure imports functions from records_passing_effector
+    raw_config.module.reverse();
+
+    let _marine = Marine::with_raw_config(
+        WasmtimeWasmBackend::new_async().unwrap(),
+        raw_config.clone(),
+    )
+    .await
+    .expect("Marine should load modules in the order of their imports");
+
+    raw_config
+        .module
+        .retain(|module| module.name == "records_passing_pure");
+    let result =
+        Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), raw_config).await;
+
+    assert!(matches!(
+        result,
+        Err(MarineError::UnresolvableModuleImports(issues)) if issues == vec![
+            ModuleDependencyIssue::MissingProvider {
+                module: "records_passing_pure".to_string(),
+                provider: "records_passing_effector".to_string(),
+            }
+        ]
+    ));
+}
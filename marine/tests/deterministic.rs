@@ -0,0 +1,101 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine::Marine;
+use marine::MarineError;
+use marine_wasmtime_backend::WasmtimeConfig;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+use marine_wasm_backend_traits::WasmBackend;
+
+use once_cell::sync::Lazy;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+use serde_json::Value as JValue;
+
+static CONFIG: Lazy<marine::TomlMarineConfig> = Lazy::new(|| {
+    marine::TomlMarineConfig::load("./tests/wasm_tests/deterministic/Config.toml")
+        .expect("toml marine config should be created")
+});
+
+const PARTICLE_TIMESTAMP_MS: u64 = 1_700_000_000_000;
+
+async fn create_marine() -> Marine {
+    let mut backend_config = WasmtimeConfig::default();
+    backend_config.deterministic(true);
+    let backend = WasmtimeWasmBackend::new(backend_config).unwrap();
+
+    Marine::with_raw_config(backend, CONFIG.clone())
+        .await
+        .unwrap_or_else(|e| panic!("can't create Marine instance: {}", e))
+}
+
+fn call_parameters(particle_id: &str) -> marine_rs_sdk::CallParameters {
+    let particle = marine_rs_sdk::ParticleParameters {
+        id: particle_id.to_string(),
+        timestamp: PARTICLE_TIMESTAMP_MS,
+        ..<_>::default()
+    };
+
+    marine_rs_sdk::CallParameters {
+        particle,
+        ..<_>::default()
+    }
+}
+
+async fn observe_environment(marine: &mut Marine, particle_id: &str) -> JValue {
+    marine
+        .call_with_json_async(
+            "deterministic",
+            "observe_environment",
+            json!([0.0]),
+            call_parameters(particle_id),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke observe_environment: {:?}", e))
+}
+
+#[tokio::test]
+async fn same_call_gives_same_output() {
+    let mut first_marine = create_marine().await;
+    let mut second_marine = create_marine().await;
+
+    let first_result = observe_environment(&mut first_marine, "particle_id").await;
+    let second_result = observe_environment(&mut second_marine, "particle_id").await;
+    // the virtual environment is reset before each call
+    let repeated_result = observe_environment(&mut first_marine, "particle_id").await;
+
+    let first_bytes = serde_json::to_vec(&first_result).unwrap();
+    assert_eq!(first_bytes, serde_json::to_vec(&second_result).unwrap());
+    assert_eq!(first_bytes, serde_json::to_vec(&repeated_result).unwrap());
+
+    assert_eq!(
+        first_result["time_ns"],
+        json!(PARTICLE_TIMESTAMP_MS * 1_000_000)
+    );
+    assert_eq!(first_result["nan_bits"], json!(0x7fc00000u32));
+
+    let other_result = observe_environment(&mut first_marine, "other_particle_id").await;
+    assert_ne!(first_result["random"], other_result["random"]);
+    assert_eq!(first_result["time_ns"], other_result["time_ns"]);
+}
+
+#[tokio::test]
+async fn deterministic_config_requires_deterministic_backend() {
+    let backend = WasmtimeWasmBackend::new_async().unwrap();
+    let result = Marine::with_raw_config(backend, CONFIG.clone()).await;
+
+    assert!(matches!(result, Err(MarineError::EngineError(_))));
+}
@@ -0,0 +1,113 @@
+/*
+ * Copyright 2020 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine::Marine;
+use marine::MarineError;
+use marine::MError;
+use marine::IValue;
+use marine_wasmtime_backend::WasmtimeConfig;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+use marine_wasm_backend_traits::RuntimeError;
+use marine_wasm_backend_traits::WasmBackend;
+use marine_wasm_backend_traits::WasmBackendError;
+
+use pretty_assertions::assert_eq;
+
+use std::path::PathBuf;
+
+async fn create_greeting_marine(backend: WasmtimeWasmBackend) -> Marine {
+    let greeting_config_path = "../examples/greeting/Config.toml";
+
+    let greeting_config_raw = std::fs::read(greeting_config_path)
+        .expect("../examples/greeting/Config.toml should presence");
+
+    let mut greeting_config: marine::TomlMarineConfig =
+        toml::from_slice(&greeting_config_raw).expect("greeting config should be well-formed");
+    greeting_config.modules_dir = Some(PathBuf::from("../examples/greeting/artifacts"));
+
+    Marine::with_raw_config(backend, greeting_config)
+        .await
+        .unwrap_or_else(|e| panic!("can't create Marine instance: {}", e))
+}
+
+fn fuel_backend() -> WasmtimeWasmBackend {
+    let mut config = WasmtimeConfig::default();
+    config.consume_fuel(true);
+    WasmtimeWasmBackend::new(config).unwrap()
+}
+
+#[tokio::test]
+pub async fn call_with_enough_fuel() {
+    let args = [IValue::String(String::from("Fluence"))];
+
+    let mut marine = create_greeting_marine(fuel_backend()).await;
+    let first = marine
+        .call_with_ivalues_and_fuel_async("greeting", "greeting", &args, <_>::default(), 1_000_000)
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke greeting: {:?}", e));
+
+    let mut marine = create_greeting_marine(fuel_backend()).await;
+    let second = marine
+        .call_with_ivalues_and_fuel_async("greeting", "greeting", &args, <_>::default(), 1_000_000)
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke greeting: {:?}", e));
+
+    assert_eq!(
+        first.result,
+        vec![IValue::String(String::from("Hi, Fluence"))]
+    );
+    assert!(first.consumed_fuel > 0);
+    // the same call on the same state costs the same regardless of the host load
+    assert_eq!(first.consumed_fuel, second.consumed_fuel);
+}
+
+#[tokio::test]
+pub async fn fuel_exhausted() {
+    let mut marine = create_greeting_marine(fuel_backend()).await;
+
+    let args = [IValue::String(String::from("Fluence"))];
+    let result = marine
+        .call_with_ivalues_and_fuel_async("greeting", "greeting", &args, <_>::default(), 10)
+        .await;
+    assert!(matches!(
+        result,
+        Err(MarineError::FuelExhausted { fuel_limit: 10, .. })
+    ));
+
+    // calls without a fuel limit aren't affected by the previous one
+    let result = marine
+        .call_with_ivalues_async("greeting", "greeting", &args, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke greeting: {:?}", e));
+    assert_eq!(result, vec![IValue::String(String::from("Hi, Fluence"))]);
+}
+
+#[tokio::test]
+pub async fn fuel_not_enabled() {
+    let mut marine = create_greeting_marine(WasmtimeWasmBackend::new_async().unwrap()).await;
+
+    let args = [IValue::String(String::from("Fluence"))];
+    let result = marine
+        .call_with_ivalues_and_fuel_async("greeting", "greeting", &args, <_>::default(), 1_000_000)
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(MarineError::EngineError(MError::WasmBackendError(
+            WasmBackendError::RuntimeError(RuntimeError::FuelNotSupported)
+        )))
+    ));
+}
@@ -38,6 +38,10 @@ static LIMIT_64_MIB: Lazy<marine::TomlMarineConfig> = Lazy::new(|| {
     marine::TomlMarineConfig::load("./tests/wasm_tests/memory_limiting/64MiB_limit.toml")
         .expect("toml faas config should be created")
 });
+static EFFECTOR_LIMIT_8_MIB: Lazy<marine::TomlMarineConfig> = Lazy::new(|| {
+    marine::TomlMarineConfig::load("./tests/wasm_tests/memory_limiting/8MiB_effector_limit.toml")
+        .expect("toml faas config should be created")
+});
 const FACADE_MODULE: &str = "memory_limiting_pure";
 const WASM_PAGE_SIZE: u64 = 64 * KIB;
 
@@ -242,6 +246,64 @@ pub async fn triggered_by_large_allocation_single_module() {
     }
 }
 
+#[tokio::test]
+pub async fn triggered_by_module_limit() {
+    let mut faas = Marine::with_raw_config(
+        WasmtimeWasmBackend::new_async().unwrap(),
+        EFFECTOR_LIMIT_8_MIB.clone(),
+    )
+    .await
+    .unwrap_or_else(|e| panic!("can't create Fluence FaaS instance: {}", e));
+
+    // 12 MiB in each module exceeds only the effector limit
+    let result = faas
+        .call_with_ivalues_async(
+            FACADE_MODULE,
+            "allocate_two_modules_64KB_pieces",
+            &[IValue::U32(192)],
+            CallParameters::default(),
+        )
+        .await;
+
+    assert!(get_module_memory(&faas, "memory_limiting_effector") <= 8 * MIB);
+    match result {
+        Err(MarineError::HighProbabilityOOM {
+            allocation_stats,
+            module_name: Some(module_name),
+            ..
+        }) if allocation_stats.allocation_rejects > 0 => {
+            assert_eq!(module_name, "memory_limiting_effector")
+        }
+        Err(e) => panic!(
+            "Expected HighProbabilityOOM error, got different error: {:?}",
+            e
+        ),
+        Ok(_) => panic!("Expected HighProbabilityOOM error, got success"),
+    }
+
+    // the facade is still able to use the rest of the total limit
+    let result = faas
+        .call_with_ivalues_async(
+            FACADE_MODULE,
+            "allocate_single_module_64KB_pieces",
+            &[IValue::U32(512)],
+            CallParameters::default(),
+        )
+        .await;
+
+    assert!(result.is_ok(), "Expected success, got error: {:?}", result);
+    assert!(get_module_memory(&faas, FACADE_MODULE) > 32 * MIB);
+}
+
+fn get_module_memory(faas: &marine::Marine, module_name: &str) -> u64 {
+    faas.module_memory_stats()
+        .modules
+        .iter()
+        .find(|stats| stats.name == module_name)
+        .map(|stats| stats.memory_size as u64)
+        .unwrap_or_else(|| panic!("module {} should be loaded", module_name))
+}
+
 fn get_total_memory(faas: &marine::Marine) -> u64 {
     faas.module_memory_stats()
         .modules
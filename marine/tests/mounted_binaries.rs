@@ -0,0 +1,108 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use marine::Marine;
+use marine_wasmtime_backend::WasmtimeWasmBackend;
+use marine_wasm_backend_traits::WasmBackend;
+
+use once_cell::sync::Lazy;
+use pretty_assertions::assert_eq;
+use serde_json::json;
+
+use std::time::Duration;
+use std::time::Instant;
+
+static CONFIG: Lazy<marine::TomlMarineConfig> = Lazy::new(|| {
+    marine::TomlMarineConfig::load("./tests/wasm_tests/mounted_binaries/Config.toml")
+        .expect("toml marine config should be created")
+});
+
+async fn create_marine() -> Marine {
+    Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), CONFIG.clone())
+        .await
+        .unwrap_or_else(|e| panic!("can't create Marine instance: {}", e))
+}
+
+#[tokio::test]
+async fn mounted_binary_output() {
+    let mut marine = create_marine().await;
+
+    let result = marine
+        .call_with_json_async(
+            "mounted_binaries_effector",
+            "call_echo",
+            json!([["abc"]]),
+            <_>::default(),
+        )
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke mounted_binaries_effector: {}", e));
+
+    assert_eq!(result, json!("abc\n"));
+}
+
+#[tokio::test]
+async fn mounted_binary_does_not_block_executor() {
+    const SLEEP_DURATION: Duration = Duration::from_millis(500);
+
+    let mut marine = create_marine().await;
+
+    let start = Instant::now();
+    let call = marine.call_with_json_async(
+        "mounted_binaries_effector",
+        "call_sleep",
+        json!([SLEEP_DURATION.as_secs_f32().to_string()]),
+        <_>::default(),
+    );
+    // polled right after the call is suspended on the mounted binary
+    let concurrent_task = async { start.elapsed() };
+
+    let (result, concurrent_task_elapsed) = futures::join!(call, concurrent_task);
+
+    assert_eq!(result.unwrap(), json!(0));
+    assert!(start.elapsed() >= SLEEP_DURATION);
+    assert!(concurrent_task_elapsed < SLEEP_DURATION);
+}
+
+#[tokio::test]
+async fn enforced_effects() {
+    let mut config = CONFIG.clone();
+    config.module[0].config.enforce_effects = Some(true);
+
+    Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), config.clone())
+        .await
+        .unwrap_or_else(|e| panic!("module with granted effects should be loaded: {}", e));
+
+    config.module[0]
+        .config
+        .mounted_binaries
+        .as_mut()
+        .unwrap()
+        .remove("sleep");
+
+    let result = Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), config).await;
+
+    match result {
+        Err(marine::MarineError::UngrantedEffects {
+            module_name,
+            effects,
+        }) => {
+            assert_eq!(module_name, "mounted_binaries_effector");
+            assert_eq!(effects, vec!["host import `sleep`".to_string()]);
+        }
+        Err(e) => panic!("expected UngrantedEffects error, got {}", e),
+        Ok(_) => panic!("module using an ungranted mounted binary shouldn't be loaded"),
+    }
+}
@@ -168,6 +168,86 @@ pub async fn records() {
     assert_eq!(result5, expected_result);
 }
 
+#[tokio::test]
+pub async fn records_msgpack() {
+    use rmpv::Value as MValue;
+
+    let records_config_path = "../examples/records/Config.toml";
+
+    let records_config_raw = std::fs::read(records_config_path)
+        .expect("../examples/records/Config.toml should presence");
+
+    let mut records_config: marine::TomlMarineConfig =
+        toml::from_slice(&records_config_raw).expect("records config should be well-formed");
+    records_config.modules_dir = Some(PathBuf::from("../examples/records/artifacts/"));
+
+    let mut marine =
+        Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), records_config)
+            .await
+            .unwrap_or_else(|e| panic!("can't create Fluence FaaS instance: {}", e));
+
+    let record = MValue::Array(vec![
+        MValue::from(false),
+        MValue::from(0),
+        MValue::from(0),
+        MValue::from(0),
+        MValue::from(0),
+        MValue::from(0),
+        MValue::from(0),
+        MValue::from(0),
+        MValue::from(0),
+        MValue::from(0.0f32),
+        MValue::from(0.0f64),
+        MValue::from(""),
+        MValue::Binary(vec![1]),
+    ]);
+
+    let mut args = Vec::new();
+    rmpv::encode::write_value(&mut args, &MValue::Array(vec![record])).unwrap();
+
+    let result = marine
+        .call_with_msgpack_async("records_effector", "mutate_struct", &args, <_>::default())
+        .await
+        .unwrap_or_else(|e| panic!("can't invoke mutate_struct: {:?}", e));
+
+    let result = rmpv::decode::read_value(&mut result.as_slice()).unwrap();
+
+    let expected_result = MValue::Map(vec![
+        (MValue::from("field_0"), MValue::from(true)),
+        (MValue::from("field_1"), MValue::from(1)),
+        (MValue::from("field_2"), MValue::from(2)),
+        (MValue::from("field_3"), MValue::from(3)),
+        (MValue::from("field_4"), MValue::from(4)),
+        (MValue::from("field_5"), MValue::from(5)),
+        (MValue::from("field_6"), MValue::from(6)),
+        (MValue::from("field_7"), MValue::from(7)),
+        (MValue::from("field_8"), MValue::from(8)),
+        (MValue::from("field_9"), MValue::from(9.0f32)),
+        (MValue::from("field_10"), MValue::from(10.0f64)),
+        (MValue::from("field_11"), MValue::from("field_11")),
+        (MValue::from("field_12"), MValue::Binary(vec![0x13, 0x37])),
+    ]);
+
+    assert_eq!(result, expected_result);
+
+    let mut invalid_args = Vec::new();
+    rmpv::encode::write_value(&mut invalid_args, &MValue::from("not a record")).unwrap();
+
+    let result = marine
+        .call_with_msgpack_async(
+            "records_effector",
+            "mutate_struct",
+            &invalid_args,
+            <_>::default(),
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(marine::MarineError::MsgPackArgumentsDeserializationError { .. })
+    ));
+}
+
 #[tokio::test]
 async fn records_passing() {
     let inner_records_config_raw = std::fs::read("./tests/wasm_tests/records_passing/Config.toml")
@@ -473,3 +553,57 @@ async fn records_pass_frees() {
         }
     }
 }
+
+#[tokio::test]
+async fn records_json_schema() {
+    let records_config_path = "../examples/records/Config.toml";
+
+    let records_config_raw = std::fs::read(records_config_path)
+        .expect("../examples/records/Config.toml should presence");
+
+    let mut records_config: marine::TomlMarineConfig =
+        toml::from_slice(&records_config_raw).expect("records config should be well-formed");
+    records_config.modules_dir = Some(PathBuf::from("../examples/records/artifacts/"));
+
+    let marine = Marine::with_raw_config(WasmtimeWasmBackend::new_async().unwrap(), records_config)
+        .await
+        .unwrap_or_else(|e| panic!("can't create Fluence FaaS instance: {}", e));
+
+    let schema = marine.get_interface().json_schema();
+    let pure_schema = &schema["records_pure"];
+
+    assert_eq!(pure_schema["$id"], json!("urn:marine:records_pure"));
+    assert_eq!(
+        pure_schema["functions"]["invoke"]["result"],
+        json!({ "$ref": "#/$defs/TestRecord" })
+    );
+    assert_eq!(
+        pure_schema["functions"]["invoke"]["arguments"],
+        json!({ "type": "array", "prefixItems": [], "items": false, "minItems": 0 })
+    );
+
+    // only records used by the exported functions are defined
+    let definitions = pure_schema["$defs"].as_object().unwrap();
+    assert_eq!(definitions.keys().collect::<Vec<_>>(), vec!["TestRecord"]);
+
+    let test_record = &definitions["TestRecord"];
+    assert_eq!(test_record["type"], json!("object"));
+    assert_eq!(test_record["required"].as_array().unwrap().len(), 13);
+    assert_eq!(
+        test_record["properties"]["field_0"],
+        json!({ "type": "boolean" })
+    );
+    assert_eq!(
+        test_record["properties"]["field_8"],
+        json!({
+            "type": "integer",
+            "format": "uint64",
+            "minimum": 0,
+            "maximum": u64::MAX,
+        })
+    );
+    assert_eq!(
+        test_record["properties"]["field_12"]["items"],
+        json!({ "type": "integer", "minimum": 0, "maximum": 255 })
+    );
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![allow(clippy::all)]
+
+use marine_rs_sdk::marine;
+
+use std::time::SystemTime;
+
+fn main() {}
+
+#[link(wasm_import_module = "wasi_snapshot_preview1")]
+extern "C" {
+    fn random_get(buf: *mut u8, buf_len: usize) -> u16;
+}
+
+#[marine]
+pub struct Environment {
+    pub time_ns: u64,
+    pub random: Vec<u8>,
+    pub nan_bits: u32,
+}
+
+#[marine]
+pub fn observe_environment(zero: f32) -> Environment {
+    let time_ns = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+
+    let mut random = vec![0u8; 48];
+    let errno = unsafe { random_get(random.as_mut_ptr(), random.len()) };
+    assert_eq!(errno, 0);
+
+    Environment {
+        time_ns,
+        random,
+        nan_bits: (zero / zero).to_bits(),
+    }
+}
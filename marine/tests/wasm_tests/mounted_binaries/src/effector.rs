@@ -0,0 +1,42 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#![allow(improper_ctypes)]
+#![allow(clippy::all)]
+
+use marine_rs_sdk::marine;
+use marine_rs_sdk::MountedBinaryResult;
+
+fn main() {}
+
+#[marine]
+pub fn call_echo(args: Vec<String>) -> String {
+    let result = echo(args);
+    String::from_utf8(result.stdout).unwrap()
+}
+
+#[marine]
+pub fn call_sleep(duration: String) -> i32 {
+    sleep(vec![duration]).ret_code
+}
+
+#[marine]
+#[host_import]
+extern "C" {
+    fn echo(args: Vec<String>) -> MountedBinaryResult;
+
+    fn sleep(args: Vec<String>) -> MountedBinaryResult;
+}
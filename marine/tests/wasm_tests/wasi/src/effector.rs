@@ -24,3 +24,15 @@ fn main() {}
 pub fn read_from_mapped_dir() -> Vec<u8> {
     std::fs::read("/some_dir/some_file").unwrap()
 }
+
+#[marine]
+pub fn write_to_mapped_dir(file_name: String, data: Vec<u8>) {
+    std::fs::create_dir_all("/some_dir/written").unwrap();
+    std::fs::write(format!("/some_dir/written/{}", file_name), data).unwrap()
+}
+
+#[marine]
+pub fn print_output(stdout: String, stderr: String) {
+    println!("{}", stdout);
+    eprintln!("{}", stderr);
+}
@@ -23,12 +23,19 @@ pub const AUTHORS: &str = env!("CARGO_PKG_AUTHORS");
 pub const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
 
 pub const IN_WASM_PATH: &str = "in-wasm-path";
+pub const IN_PATH: &str = "in-path";
+pub const CONFIG_PATH: &str = "config-path";
+pub const OLD_PATH: &str = "old-path";
+pub const NEW_PATH: &str = "new-path";
+pub const JSON_OUTPUT: &str = "json";
 pub const IT_PATH: &str = "it-path";
 pub const OUT_WASM_PATH: &str = "out-wasm-path";
 pub const SERVICE_NAME: &str = "service-name";
 pub const PROJECT_NAME: &str = "generate-project-name";
 pub const SHOULD_INIT_OPTION: &str = "should-init";
 pub const SERVICE_ID: &str = "service-id";
+pub const KEY_PATH: &str = "key-path";
+pub const TRUSTED_KEY: &str = "trusted-key";
 
 pub const SDK_VERSION: &str = "sdk-version";
 
@@ -56,6 +63,48 @@ pub fn aqua<'a, 'b>() -> App<'a, 'b> {
         ])
 }
 
+pub fn schema<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("schema")
+        .about("Shows JSON Schema of functions of provided module or of all modules from provided config")
+        .args(&[Arg::with_name(IN_PATH)
+            .required(true)
+            .takes_value(true)
+            .index(1)
+            .help("a path to a Wasm file or to a Config.toml")])
+}
+
+pub fn diff<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("diff")
+        .about("Shows changes between two versions of a module or a service and classifies them as compatible or breaking, exits with code 1 if there are breaking changes")
+        .args(&[
+            Arg::with_name(OLD_PATH)
+                .required(true)
+                .takes_value(true)
+                .index(1)
+                .help("a path to an old Wasm file or Config.toml"),
+            Arg::with_name(NEW_PATH)
+                .required(true)
+                .takes_value(true)
+                .index(2)
+                .help("a path to a new Wasm file or Config.toml"),
+            Arg::with_name(JSON_OUTPUT)
+                .required(false)
+                .takes_value(false)
+                .long("json")
+                .help("print changes in JSON"),
+        ])
+}
+
+pub fn check<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("check")
+        .about("Checks provided service config and modules it refers to without instantiating them, exits with code 1 if there are problems")
+        .args(&[Arg::with_name(CONFIG_PATH)
+            .required(true)
+            .takes_value(true)
+            .index(1)
+            .help("a path to a Config.toml")])
+}
+
 pub fn build<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("build")
         .about("Builds provided Rust project to Wasm")
@@ -141,6 +190,50 @@ fn set_version<'a, 'b>() -> App<'a, 'b> {
         ])
 }
 
+pub fn sign<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("sign")
+        .about("Signs the provided Wasm file with an ed25519 key, the signature covers the whole module including its manifest")
+        .args(&[
+            Arg::with_name(IN_WASM_PATH)
+                .required(true)
+                .takes_value(true)
+                .short("i")
+                .long("input")
+                .help("a path to a Wasm file"),
+            Arg::with_name(KEY_PATH)
+                .required(true)
+                .takes_value(true)
+                .short("k")
+                .long("key")
+                .help("a path to a file with a hex-encoded 32-byte ed25519 secret key"),
+            Arg::with_name(OUT_WASM_PATH)
+                .takes_value(true)
+                .short("o")
+                .long("output")
+                .help("A path to the result signed Wasm file. If absent, modifies input file."),
+        ])
+}
+
+pub fn verify<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("verify")
+        .about("Verifies signature of the provided Wasm file and shows the key it was signed with")
+        .args(&[
+            Arg::with_name(IN_WASM_PATH)
+                .required(true)
+                .takes_value(true)
+                .index(1)
+                .help("a path to a Wasm file"),
+            Arg::with_name(TRUSTED_KEY)
+                .required(false)
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .short("k")
+                .long("key")
+                .help("a hex-encoded ed25519 public key the module should be signed with, could be specified several times"),
+        ])
+}
+
 pub fn show_wit<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("it")
         .about("Shows IT of the provided Wasm file")
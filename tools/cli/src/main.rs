@@ -28,6 +28,7 @@
 use marine_module_info_parser::manifest;
 use marine_module_info_parser::ModuleInfoError;
 use marine_module_info_parser::sdk_version;
+use marine_module_info_parser::signature;
 
 mod args;
 mod build;
@@ -44,9 +45,14 @@ pub fn main() -> Result<(), anyhow::Error> {
         .author(args::AUTHORS)
         .setting(clap::AppSettings::ArgRequiredElseHelp)
         .subcommand(args::aqua())
+        .subcommand(args::schema())
+        .subcommand(args::diff())
+        .subcommand(args::check())
         .subcommand(args::build())
         .subcommand(args::generate())
         .subcommand(args::set())
+        .subcommand(args::sign())
+        .subcommand(args::verify())
         .subcommand(args::show_manifest())
         .subcommand(args::show_wit())
         .subcommand(args::repl());
@@ -57,9 +63,23 @@ pub fn main() -> Result<(), anyhow::Error> {
             // avoid printing version
             return aqua(args);
         }
+        ("schema", Some(args)) => {
+            // avoid printing version
+            return schema(args);
+        }
+        ("diff", Some(args)) => {
+            // avoid printing version
+            return diff(args);
+        }
+        ("check", Some(args)) => {
+            // avoid printing version
+            return check(args);
+        }
         ("build", Some(args)) => build(args),
         ("generate", Some(args)) => generate(args),
         ("set", Some(args)) => set(args),
+        ("sign", Some(args)) => sign(args),
+        ("verify", Some(args)) => verify(args),
         ("it", Some(args)) => it(args),
         ("info", Some(args)) => info(args),
         ("repl", Some(args)) => repl(args),
@@ -125,6 +145,142 @@ fn aqua(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+fn schema(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
+    let in_path = std::path::Path::new(args.value_of(args::IN_PATH).unwrap());
+
+    let schema = match in_path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => {
+            let config = marine::TomlMarineConfig::load(in_path)?;
+            let modules = config
+                .module
+                .iter()
+                .map(|module| {
+                    let wasm_path = config.module_path(module)?;
+                    let schema = module_json_schema(&module.name, &wasm_path)?;
+                    Ok((module.name.clone(), schema))
+                })
+                .collect::<Result<serde_json::Map<_, _>, anyhow::Error>>()?;
+
+            serde_json::Value::Object(modules)
+        }
+        _ => {
+            let module_name = in_path
+                .file_stem()
+                .ok_or_else(|| anyhow::Error::msg("provided path isn't a path to a file"))?;
+
+            module_json_schema(&module_name.to_string_lossy(), in_path)?
+        }
+    };
+
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+
+    Ok(())
+}
+
+fn module_json_schema(
+    module_name: &str,
+    wasm_path: &std::path::Path,
+) -> Result<serde_json::Value, anyhow::Error> {
+    use marine_module_interface::json_schema;
+
+    let module_interface = marine_it_parser::module_it_interface(wasm_path)?;
+    let functions = module_interface
+        .function_signatures
+        .iter()
+        .map(|signature| json_schema::FunctionTypes {
+            name: &signature.name,
+            arguments: &signature.arguments,
+            outputs: &signature.outputs,
+        });
+
+    Ok(json_schema::module_schema(
+        module_name,
+        functions,
+        &module_interface.record_types,
+    ))
+}
+
+fn diff(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
+    use marine_module_interface::diff;
+
+    let old_path = std::path::Path::new(args.value_of(args::OLD_PATH).unwrap());
+    let new_path = std::path::Path::new(args.value_of(args::NEW_PATH).unwrap());
+
+    let diff = match (is_config(old_path), is_config(new_path)) {
+        (true, true) => diff::diff_services(&service_api(old_path)?, &service_api(new_path)?),
+        (false, false) => diff::diff_modules(
+            &marine_it_parser::module_api(old_path)?,
+            &marine_it_parser::module_api(new_path)?,
+        ),
+        _ => {
+            return Err(anyhow::Error::msg(
+                "both paths should point either to Wasm files or to Config.toml files",
+            ))
+        }
+    };
+
+    if args.is_present(args::JSON_OUTPUT) {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else if diff.changes.is_empty() {
+        println!("no changes found");
+    } else {
+        print!("{}", diff);
+    }
+
+    if diff.is_breaking() {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn check(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
+    let config_path = args.value_of(args::CONFIG_PATH).unwrap();
+
+    let config = marine::TomlMarineConfig::load(config_path)?;
+    let issues = config.validate();
+    if issues.is_empty() {
+        println!("no problems found");
+        return Ok(());
+    }
+
+    for issue in issues.iter() {
+        println!("{}", issue);
+    }
+    println!("{} problem(s) found", issues.len());
+
+    std::process::exit(1);
+}
+
+fn is_config(path: &std::path::Path) -> bool {
+    path.extension().and_then(|extension| extension.to_str()) == Some("toml")
+}
+
+fn service_api(
+    config_path: &std::path::Path,
+) -> Result<Vec<(String, marine_module_interface::diff::ModuleApi)>, anyhow::Error> {
+    let config = marine::TomlMarineConfig::load(config_path)?;
+    let mut modules = config
+        .module
+        .iter()
+        .map(|module| {
+            let wasm_path = config.module_path(module)?;
+            let api = marine_it_parser::module_api(wasm_path)?;
+            Ok((module.name.clone(), api))
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+
+    // diff expects the facade to be the last module
+    if let Some(facade) = &config.facade {
+        if let Some(position) = modules.iter().position(|(name, _)| name == facade) {
+            let facade = modules.remove(position);
+            modules.push(facade);
+        }
+    }
+
+    Ok(modules)
+}
+
 fn build(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
     let trailing_args: Vec<&str> = args.values_of("optional").unwrap_or_default().collect();
 
@@ -182,6 +338,57 @@ fn set_version(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+fn sign(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
+    let in_wasm_path = args.value_of(args::IN_WASM_PATH).unwrap();
+    let key_path = args.value_of(args::KEY_PATH).unwrap();
+    let out_wasm_path = match args.value_of(args::OUT_WASM_PATH) {
+        Some(path) => path,
+        None => in_wasm_path,
+    };
+
+    let key = std::fs::read_to_string(key_path)?;
+    let signing_key = signature::parse_signing_key(&key)?;
+
+    let wasm_bytes = std::fs::read(in_wasm_path)?;
+    let signed_bytes = signature::sign(&wasm_bytes, &signing_key)?;
+    std::fs::write(out_wasm_path, signed_bytes)?;
+
+    println!(
+        "the module was successfully signed with key {}",
+        signature::key_to_hex(&signing_key.verifying_key())
+    );
+
+    Ok(())
+}
+
+fn verify(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
+    let wasm_path = args.value_of(args::IN_WASM_PATH).unwrap();
+    let trusted_keys = args
+        .values_of(args::TRUSTED_KEY)
+        .map(|keys| {
+            keys.map(signature::parse_verifying_key)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+
+    let wasm_bytes = std::fs::read(wasm_path)?;
+    let signer = match trusted_keys {
+        Some(trusted_keys) => signature::verify_trusted(&wasm_bytes, &trusted_keys)?,
+        None => signature::verify(&wasm_bytes)?,
+    };
+
+    println!("signed by:   {}", signature::key_to_hex(&signer));
+    match manifest::extract_from_bytes(&wasm_bytes) {
+        Ok(manifest) => println!("{}", manifest),
+        Err(ModuleInfoError::NoCustomSection(_)) => {
+            println!("module doesn't contain module manifest")
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(())
+}
+
 fn it(args: &clap::ArgMatches<'_>) -> Result<(), anyhow::Error> {
     let wasm_path = args.value_of(args::IN_WASM_PATH).unwrap();
 
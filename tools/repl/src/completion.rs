@@ -0,0 +1,276 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use fluence_app_service::IFunctionArg;
+use fluence_app_service::IRecordType;
+use fluence_app_service::IType;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Names of loaded modules and their functions with argument skeletons used for completion.
+#[derive(Default, Debug)]
+pub(super) struct CompletionData {
+    modules: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl CompletionData {
+    pub(super) fn add_function(
+        &mut self,
+        module_name: &str,
+        function_name: &str,
+        arguments: &[IFunctionArg],
+        record_types: &HashMap<u64, Arc<IRecordType>>,
+    ) {
+        let skeleton = arguments_skeleton(arguments, record_types);
+        self.modules
+            .entry(module_name.to_string())
+            .or_default()
+            .insert(function_name.to_string(), skeleton);
+    }
+
+    pub(super) fn module_names(&self) -> impl Iterator<Item = &str> {
+        self.modules.keys().map(String::as_str)
+    }
+
+    pub(super) fn function_names(&self, module_name: &str) -> impl Iterator<Item = &str> {
+        self.modules
+            .get(module_name)
+            .into_iter()
+            .flat_map(|functions| functions.keys().map(String::as_str))
+    }
+
+    /// Returns JSON arguments of the function with default values, e.g. `{"name": ""}`.
+    pub(super) fn arguments_skeleton(
+        &self,
+        module_name: &str,
+        function_name: &str,
+    ) -> Option<&str> {
+        self.modules
+            .get(module_name)?
+            .get(function_name)
+            .map(String::as_str)
+    }
+}
+
+fn arguments_skeleton(
+    arguments: &[IFunctionArg],
+    record_types: &HashMap<u64, Arc<IRecordType>>,
+) -> String {
+    let fields = arguments
+        .iter()
+        .map(|argument| (argument.name.as_str(), &argument.ty));
+
+    object_skeleton(fields, record_types, &mut HashSet::new())
+}
+
+// fields are written in the declaration order, serde_json would sort them by names
+fn object_skeleton<'f>(
+    fields: impl Iterator<Item = (&'f str, &'f IType)>,
+    record_types: &HashMap<u64, Arc<IRecordType>>,
+    expanded_records: &mut HashSet<u64>,
+) -> String {
+    let fields = fields
+        .map(|(name, ty)| {
+            let value = value_skeleton(ty, record_types, expanded_records);
+            format!("\"{}\": {}", name, value)
+        })
+        .collect::<Vec<_>>();
+
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// `expanded_records` are ids of records being expanded, a record referring to one of them
+/// is left empty, so recursive records don't expand infinitely.
+fn value_skeleton(
+    ty: &IType,
+    record_types: &HashMap<u64, Arc<IRecordType>>,
+    expanded_records: &mut HashSet<u64>,
+) -> String {
+    match ty {
+        IType::Boolean => String::from("false"),
+        IType::S8
+        | IType::S16
+        | IType::S32
+        | IType::S64
+        | IType::U8
+        | IType::U16
+        | IType::U32
+        | IType::U64
+        | IType::I32
+        | IType::I64 => String::from("0"),
+        IType::F32 | IType::F64 => String::from("0.0"),
+        IType::String => String::from("\"\""),
+        IType::ByteArray => String::from("[]"),
+        // an element is shown only for records, it's the hardest part to type by hand
+        IType::Array(element_ty) => match element_ty.as_ref() {
+            IType::Record(record_type_id) if !expanded_records.contains(record_type_id) => {
+                let element = value_skeleton(element_ty, record_types, expanded_records);
+                format!("[{}]", element)
+            }
+            _ => String::from("[]"),
+        },
+        IType::Record(record_type_id) => match record_types.get(record_type_id) {
+            Some(record_type) if expanded_records.insert(*record_type_id) => {
+                let fields = record_type
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.as_str(), &field.ty));
+                let skeleton = object_skeleton(fields, record_types, expanded_records);
+                expanded_records.remove(record_type_id);
+                skeleton
+            }
+            _ => String::from("{}"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompletionData;
+
+    use fluence_app_service::IFunctionArg;
+    use fluence_app_service::IRecordType;
+    use fluence_app_service::IType;
+    use wasmer_it::IRecordFieldType;
+    use wasmer_it::NEVec;
+
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn argument(name: &str, ty: IType) -> IFunctionArg {
+        IFunctionArg {
+            name: name.to_string(),
+            ty,
+        }
+    }
+
+    fn record(name: &str, fields: Vec<(&str, IType)>) -> Arc<IRecordType> {
+        let fields = fields
+            .into_iter()
+            .map(|(name, ty)| IRecordFieldType {
+                name: name.to_string(),
+                ty,
+            })
+            .collect();
+
+        Arc::new(IRecordType {
+            name: name.to_string(),
+            fields: NEVec::new(fields).unwrap(),
+        })
+    }
+
+    #[test]
+    fn scalar_arguments() {
+        let mut data = CompletionData::default();
+        let arguments = vec![
+            argument("flag", IType::Boolean),
+            argument("count", IType::U32),
+            argument("ratio", IType::F64),
+            argument("name", IType::String),
+            argument("bytes", IType::ByteArray),
+            argument("names", IType::Array(Box::new(IType::String))),
+        ];
+        data.add_function("module", "function", &arguments, &HashMap::new());
+
+        // fields keep the declaration order
+        assert_eq!(
+            data.arguments_skeleton("module", "function"),
+            Some(
+                r#"{"flag": false, "count": 0, "ratio": 0.0, "name": "", "bytes": [], "names": []}"#
+            )
+        );
+    }
+
+    #[test]
+    fn nested_records_and_arrays_of_records() {
+        let record_types = HashMap::from([
+            (0, record("Address", vec![("city", IType::String)])),
+            (
+                1,
+                record(
+                    "User",
+                    vec![
+                        ("name", IType::String),
+                        ("address", IType::Record(0)),
+                        ("previous", IType::Array(Box::new(IType::Record(0)))),
+                    ],
+                ),
+            ),
+        ]);
+
+        let mut data = CompletionData::default();
+        let arguments = vec![
+            argument("user", IType::Record(1)),
+            argument("unknown", IType::Record(42)),
+        ];
+        data.add_function("module", "function", &arguments, &record_types);
+
+        assert_eq!(
+            data.arguments_skeleton("module", "function"),
+            Some(
+                r#"{"user": {"name": "", "address": {"city": ""}, "previous": [{"city": ""}]}, "unknown": {}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn recursive_records() {
+        let record_types = HashMap::from([
+            (
+                0,
+                record(
+                    "Node",
+                    vec![
+                        ("value", IType::U32),
+                        ("children", IType::Array(Box::new(IType::Record(0)))),
+                        ("parent", IType::Record(1)),
+                    ],
+                ),
+            ),
+            (1, record("Parent", vec![("node", IType::Record(0))])),
+        ]);
+
+        let mut data = CompletionData::default();
+        let arguments = vec![
+            argument("node", IType::Record(0)),
+            argument("other", IType::Record(0)),
+        ];
+        data.add_function("module", "function", &arguments, &record_types);
+
+        // a record isn't expanded inside itself, but is expanded again in sibling fields
+        assert_eq!(
+            data.arguments_skeleton("module", "function"),
+            Some(
+                r#"{"node": {"value": 0, "children": [], "parent": {"node": {}}}, "other": {"value": 0, "children": [], "parent": {"node": {}}}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn unknown_modules_and_functions() {
+        let mut data = CompletionData::default();
+        data.add_function("module", "function", &[], &HashMap::new());
+
+        assert_eq!(data.arguments_skeleton("module", "function"), Some("{}"));
+        assert_eq!(data.arguments_skeleton("module", "absent"), None);
+        assert_eq!(data.arguments_skeleton("absent", "function"), None);
+        assert_eq!(data.function_names("absent").count(), 0);
+        assert_eq!(data.module_names().collect::<Vec<_>>(), vec!["module"]);
+    }
+}
@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use crate::completion::CompletionData;
 use crate::ReplResult;
 
 use rustyline::completion::Completer;
@@ -54,6 +55,7 @@ pub(super) fn init_editor() -> ReplResult<Editor<REPLHelper>> {
     };
     let repl_helper = REPLHelper {
         completer: FilenameCompleter::new(),
+        completion_data: CompletionData::default(),
         highlighter: MatchingBracketHighlighter::new(),
         hinter: repl_hinter,
         colored_prompt: "".to_owned(),
@@ -76,6 +78,7 @@ pub(super) fn init_editor() -> ReplResult<Editor<REPLHelper>> {
 #[derive(Helper)]
 pub(super) struct REPLHelper {
     completer: FilenameCompleter,
+    completion_data: CompletionData,
     highlighter: MatchingBracketHighlighter,
     validator: MatchingBracketValidator,
     hinter: REPLHinter,
@@ -86,6 +89,56 @@ impl REPLHelper {
     pub(super) fn set_prompt_color(&mut self, color: String) {
         self.colored_prompt = color;
     }
+
+    pub(super) fn set_completion_data(&mut self, completion_data: CompletionData) {
+        self.completion_data = completion_data;
+    }
+
+    /// Completes commands, module and function names, and arguments of `call`,
+    /// returns None if the word should be completed as a file path.
+    fn complete_interface(&self, line: &str) -> Option<(usize, Vec<Pair>)> {
+        let mut words = line.split_whitespace().collect::<Vec<_>>();
+        // the word under the cursor is empty right after a whitespace
+        if line.is_empty() || line.ends_with(char::is_whitespace) {
+            words.push("");
+        }
+
+        let (word, previous_words) = words.split_last()?;
+        let start = line.len() - word.len();
+        let candidates = |names: &mut dyn Iterator<Item = &str>| {
+            names
+                .filter(|name| name.starts_with(word))
+                .map(|name| Pair {
+                    display: name.to_string(),
+                    replacement: name.to_string(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let candidates = match previous_words {
+            [] => candidates(&mut COMMANDS.iter().copied()),
+            ["c" | "call" | "u" | "unload" | "e" | "envs" | "f" | "fs" | "m" | "mem"
+            | "mem-find" | "mem-dump"] => candidates(&mut self.completion_data.module_names()),
+            ["c" | "call", module_name] => {
+                candidates(&mut self.completion_data.function_names(module_name))
+            }
+            ["c" | "call", module_name, function_name]
+            | ["c" | "call", module_name, function_name, "-nr"]
+                if word.is_empty() =>
+            {
+                let skeleton = self
+                    .completion_data
+                    .arguments_skeleton(module_name, function_name)?;
+                vec![Pair {
+                    display: skeleton.to_string(),
+                    replacement: skeleton.to_string(),
+                }]
+            }
+            _ => return None,
+        };
+
+        Some((start, candidates))
+    }
 }
 
 /// Tries to find hint from history if its failed from supported command list.
@@ -103,7 +156,10 @@ impl Completer for REPLHelper {
         pos: usize,
         ctx: &Context<'_>,
     ) -> std::result::Result<(usize, Vec<Pair>), ReadlineError> {
-        self.completer.complete(line, pos, ctx)
+        match self.complete_interface(&line[..pos]) {
+            Some(candidates) => Ok(candidates),
+            None => self.completer.complete(line, pos, ctx),
+        }
     }
 }
 
@@ -174,15 +230,109 @@ impl Validator for REPLHelper {
     }
 }
 
+const COMMANDS: &[&str] = &[
+    "new",
+    "load",
+    "unload",
+    "call",
+    "interface",
+    "stats",
+    "mem",
+    "mem-find",
+    "mem-dump",
+    "envs",
+    "fs",
+    "help",
+    "quit",
+];
+
 fn commands_hints() -> HashSet<String> {
-    let mut set = HashSet::new();
-    set.insert(String::from("load"));
-    set.insert(String::from("unload"));
-    set.insert(String::from("call"));
-    set.insert(String::from("envs"));
-    set.insert(String::from("fs"));
-    set.insert(String::from("interface"));
-    set.insert(String::from("heap"));
-    set.insert(String::from("help"));
-    set
+    COMMANDS.iter().map(|command| command.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fluence_app_service::IFunctionArg;
+    use fluence_app_service::IType;
+
+    fn helper() -> REPLHelper {
+        let mut completion_data = CompletionData::default();
+        let arguments = vec![IFunctionArg {
+            name: "name".to_string(),
+            ty: IType::String,
+        }];
+        completion_data.add_function("greeting", "greeting", &arguments, &<_>::default());
+        completion_data.add_function("greeting", "greet_all", &[], &<_>::default());
+
+        REPLHelper {
+            completer: FilenameCompleter::new(),
+            completion_data,
+            highlighter: MatchingBracketHighlighter::new(),
+            validator: MatchingBracketValidator::new(),
+            hinter: REPLHinter {
+                commands_hints: commands_hints(),
+                history_hinter: HistoryHinter {},
+            },
+            colored_prompt: String::new(),
+        }
+    }
+
+    fn complete(line: &str) -> Option<(usize, Vec<String>)> {
+        helper()
+            .complete_interface(line)
+            .map(|(start, candidates)| {
+                let candidates = candidates
+                    .into_iter()
+                    .map(|pair| pair.replacement)
+                    .collect();
+                (start, candidates)
+            })
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(
+            complete("me"),
+            Some((
+                0,
+                vec![
+                    "mem".to_string(),
+                    "mem-find".to_string(),
+                    "mem-dump".to_string()
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn module_and_function_names() {
+        assert_eq!(complete("call gr"), Some((5, vec!["greeting".to_string()])));
+        assert_eq!(
+            complete("call greeting gree"),
+            Some((14, vec!["greet_all".to_string(), "greeting".to_string()]))
+        );
+        assert_eq!(complete("call absent "), Some((12, vec![])));
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(
+            complete("call greeting greeting "),
+            Some((23, vec![r#"{"name": ""}"#.to_string()]))
+        );
+        assert_eq!(
+            complete("call greeting greeting -nr "),
+            Some((27, vec![r#"{"name": ""}"#.to_string()]))
+        );
+        // arguments of unknown functions are completed as file paths
+        assert_eq!(complete("call greeting absent "), None);
+        assert_eq!(complete("call absent greeting "), None);
+    }
+
+    #[test]
+    fn file_paths() {
+        assert_eq!(complete("load module ./"), None);
+    }
 }
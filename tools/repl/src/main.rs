@@ -26,9 +26,11 @@
 
 /// Command-line tool intended to test Fluence App services.
 
+mod completion;
 mod editor;
 mod logger;
 mod repl;
+mod script;
 
 use logger::init_logger;
 use editor::init_editor;
@@ -51,9 +53,20 @@ async fn main() -> ReplResult<()> {
         param config_file_path: Option<String>, desc: "Path to a service config";
         opt quiet: bool=false, desc: "Suppress unnecessary welcome message";
         opt working_dir: Option<String>, desc: "Set working dir for service, default = \".\"";
+        opt script: Option<String>, desc: "Execute commands from a file with `expect` checks of call results, exit with a non-zero code on the first failure";
     }
     .parse_or_exit();
 
+    if let Some(script_path) = args.script {
+        let mut repl = REPL::new(args.config_file_path, args.working_dir, true).await?;
+        if let Err(e) = script::run_script(&mut repl, script_path.as_ref()).await {
+            eprintln!("\nscript failed: {}", e);
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
     let mut rl = init_editor()?;
     let _ = rl.load_history(HISTORY_FILE_PATH);
 
@@ -66,9 +79,10 @@ async fn main() -> ReplResult<()> {
     let mut count = 1;
     loop {
         let p = format!("\n{}> ", count);
-        rl.helper_mut()
-            .expect("No helper")
-            .set_prompt_color(format!("\x1b[1;32m{}\x1b[0m", p));
+        let helper = rl.helper_mut().expect("No helper");
+        helper.set_prompt_color(format!("\x1b[1;32m{}\x1b[0m", p));
+        // modules could be loaded or unloaded by the previous command
+        helper.set_completion_data(repl.completion_data());
         let readline = rl.readline(&p);
         match readline {
             Ok(line) => {
@@ -14,10 +14,13 @@
  * limitations under the License.
  */
 
+mod memory;
 mod print_state;
 
+use print_state::call_output_view;
 use print_state::print_envs;
 use print_state::print_fs_state;
+use crate::completion::CompletionData;
 use crate::ReplResult;
 
 use fluence_app_service::WasmtimeConfig;
@@ -27,6 +30,7 @@ use fluence_app_service::CallParameters;
 use fluence_app_service::ParticleParameters;
 use fluence_app_service::SecurityTetraplet;
 use fluence_app_service::MarineModuleConfig;
+use fluence_app_service::ModuleOutput;
 use fluence_app_service::TomlAppServiceConfig;
 
 use anyhow::anyhow;
@@ -38,18 +42,6 @@ use std::fs;
 use std::path::PathBuf;
 use std::time::Instant;
 
-macro_rules! next_argument {
-    ($arg_name:ident, $args:ident, $error_msg:expr) => {
-        let $arg_name = match $args.next() {
-            Some($arg_name) => $arg_name,
-            None => {
-                println!($error_msg);
-                return;
-            }
-        };
-    };
-}
-
 macro_rules! next_argument_or_result {
     ($arg_name:ident, $args:ident, $error_msg:expr) => {
         let $arg_name = match $args.next() {
@@ -68,12 +60,17 @@ struct CallModuleArguments<'args> {
 }
 
 const DEFAULT_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
+/// Result of a command: an error message if it failed.
+pub(super) type CommandResult = Result<(), String>;
+
 #[allow(clippy::upper_case_acronyms)]
 pub(super) struct REPL {
     app_service: AppService,
     service_working_dir: Option<String>,
     app_service_factory: AppServiceFactory,
     timeout: std::time::Duration,
+    /// Result of the last command if it's a successful call, it's checked by `expect` lines of scripts.
+    last_call_result: Option<JValue>,
 }
 
 impl REPL {
@@ -100,55 +97,104 @@ impl REPL {
             service_working_dir: working_dir,
             app_service_factory,
             timeout: DEFAULT_TIMEOUT,
+            last_call_result: None,
         })
     }
 
     /// Returns true, it should be the last executed command.
-    pub async fn execute<'args>(&mut self, mut args: impl Iterator<Item = &'args str>) -> bool {
-        // Explicit statements on "h"/"help" options is more convenient, as we have such commands.
-        #[allow(clippy::wildcard_in_or_patterns)]
+    pub async fn execute<'args>(&mut self, args: impl Iterator<Item = &'args str>) -> bool {
+        match self.execute_command(args).await {
+            Ok(should_continue) => should_continue,
+            Err(message) => {
+                println!("{}", message);
+                true
+            }
+        }
+    }
+
+    /// Executes a command without printing its error, returns false if it's the last one.
+    pub async fn execute_command<'args>(
+        &mut self,
+        mut args: impl Iterator<Item = &'args str>,
+    ) -> Result<bool, String> {
+        // expectations of scripts relate only to the command right before them
+        self.last_call_result = None;
+
         match args.next() {
-            Some("n") | Some("new") => self.new_service(args).await,
-            Some("l") | Some("load") => self.load_module(args).await,
-            Some("u") | Some("unload") => self.unload_module(args),
-            Some("c") | Some("call") => self.call_module(args).await,
-            Some("e") | Some("envs") => self.show_envs(args),
-            Some("f") | Some("fs") => self.show_fs(args),
+            Some("n") | Some("new") => self.new_service(args).await?,
+            Some("l") | Some("load") => self.load_module(args).await?,
+            Some("u") | Some("unload") => self.unload_module(args)?,
+            Some("c") | Some("call") => self.call_module(args).await?,
+            Some("e") | Some("envs") => self.show_envs(args)?,
+            Some("f") | Some("fs") => self.show_fs(args)?,
             Some("i") | Some("interface") => self.show_interface(),
             Some("s") | Some("stats") => self.show_memory_stats(),
+            Some("m") | Some("mem") => self.show_memory(args)?,
+            Some("mem-find") => self.find_in_memory(args)?,
+            Some("mem-dump") => self.dump_memory(args)?,
             Some("q") | Some("quit") => {
-                return false;
+                return Ok(false);
+            }
+            Some("h") | Some("help") | None => print_help(),
+            Some(command) => {
+                print_help();
+                return Err(format!("unknown command: {}", command));
             }
+        }
+
+        Ok(true)
+    }
+
+    /// Returns the result of the last command if it's a successful call.
+    pub fn last_call_result(&self) -> Option<&JValue> {
+        self.last_call_result.as_ref()
+    }
+
+    /// Collects names and argument skeletons of exported functions of all loaded modules.
+    pub(super) fn completion_data(&self) -> CompletionData {
+        let interface = self.app_service.get_full_interface();
 
-            Some("h") | Some("help") | _ => print_help(),
+        let mut completion_data = CompletionData::default();
+        for (module_name, module_interface) in interface.modules {
+            for signature in module_interface.function_signatures {
+                completion_data.add_function(
+                    module_name,
+                    &signature.name,
+                    &signature.arguments,
+                    module_interface.record_types,
+                );
+            }
         }
 
-        true
+        completion_data
     }
 
-    async fn new_service<'args>(&mut self, mut args: impl Iterator<Item = &'args str>) {
-        match Self::create_app_service(
+    async fn new_service<'args>(
+        &mut self,
+        mut args: impl Iterator<Item = &'args str>,
+    ) -> CommandResult {
+        let service = Self::create_app_service(
             &self.app_service_factory,
             args.next(),
             self.service_working_dir.clone(),
             false,
         )
         .await
-        {
-            Ok(service) => self.app_service = service,
-            Err(e) => println!("failed to create a new application service: {}", e),
-        };
+        .map_err(|e| format!("failed to create a new application service: {}", e))?;
+
+        self.app_service = service;
+        Ok(())
     }
 
-    async fn load_module<'args>(&mut self, mut args: impl Iterator<Item = &'args str>) {
-        next_argument!(module_name, args, "Module name should be specified");
-        next_argument!(module_path, args, "Module path should be specified");
+    async fn load_module<'args>(
+        &mut self,
+        mut args: impl Iterator<Item = &'args str>,
+    ) -> CommandResult {
+        next_argument_or_result!(module_name, args, "Module name should be specified");
+        next_argument_or_result!(module_path, args, "Module path should be specified");
 
-        let wasm_bytes = fs::read(module_path);
-        if let Err(e) = wasm_bytes {
-            println!("failed to read wasm module: {}", e);
-            return;
-        }
+        let wasm_bytes =
+            fs::read(module_path).map_err(|e| format!("failed to read wasm module: {}", e))?;
 
         let start = Instant::now();
         let config = MarineModuleConfig {
@@ -156,103 +202,126 @@ impl REPL {
             host_imports: Default::default(),
             wasi: Default::default(),
             logging_mask: Default::default(),
+            max_memory: None,
+            enforce_effects: false,
+            // so the output is shown under call results instead of interleaving with the REPL one
+            output: ModuleOutput::Capture {
+                max_size: ModuleOutput::DEFAULT_MAX_SIZE,
+            },
         };
-        let result_msg = match self
-            .app_service
+        self.app_service
             .load_module::<MarineModuleConfig, String>(
                 module_name.into(),
-                &wasm_bytes.unwrap(),
+                &wasm_bytes,
                 Some(config),
             )
             .await
-        {
-            Ok(_) => {
-                let elapsed_time = start.elapsed();
-                format!(
-                    "module successfully loaded into App service\nelapsed time: {:?}",
-                    elapsed_time
-                )
-            }
-            Err(e) => format!("loading failed with: {}", e),
-        };
-        println!("{}", result_msg);
+            .map_err(|e| format!("loading failed with: {}", e))?;
+
+        let elapsed_time = start.elapsed();
+        println!(
+            "module successfully loaded into App service\nelapsed time: {:?}",
+            elapsed_time
+        );
+        Ok(())
     }
 
-    fn unload_module<'args>(&mut self, mut args: impl Iterator<Item = &'args str>) {
-        next_argument!(module_name, args, "Module name should be specified");
+    fn unload_module<'args>(
+        &mut self,
+        mut args: impl Iterator<Item = &'args str>,
+    ) -> CommandResult {
+        next_argument_or_result!(module_name, args, "Module name should be specified");
 
         let start = Instant::now();
-        let result_msg = match self.app_service.unload_module(module_name) {
-            Ok(_) => {
-                let elapsed_time = start.elapsed();
-                format!(
-                    "module successfully unloaded from App service\nelapsed time: {:?}",
-                    elapsed_time
-                )
-            }
-            Err(e) => format!("unloading failed with: {}", e),
-        };
-        println!("{}", result_msg);
+        self.app_service
+            .unload_module(module_name)
+            .map_err(|e| format!("unloading failed with: {}", e))?;
+
+        let elapsed_time = start.elapsed();
+        println!(
+            "module successfully unloaded from App service\nelapsed time: {:?}",
+            elapsed_time
+        );
+        Ok(())
     }
 
-    async fn call_module<'args>(&mut self, args: impl Iterator<Item = &'args str>) {
+    async fn call_module<'args>(
+        &mut self,
+        args: impl Iterator<Item = &'args str>,
+    ) -> CommandResult {
         let CallModuleArguments {
             module_name,
             func_name,
             show_result_arg,
             args,
             call_parameters,
-        } = match parse_call_module_arguments(args) {
-            Ok(call_module_arguments) => call_module_arguments,
-            Err(message) => {
-                println!("{}", message);
-                return;
-            }
-        };
+        } = parse_call_module_arguments(args)?;
 
         let start = Instant::now();
         let call_future =
             self.app_service
                 .call_module(module_name, func_name, args, call_parameters);
         let result = match tokio::time::timeout(self.timeout, call_future).await {
-            Ok(Ok(result)) if show_result_arg => {
-                let elapsed_time = start.elapsed();
-
-                let result_string = match serde_json::to_string_pretty(&result) {
-                    Ok(pretty_printed) => pretty_printed,
-                    Err(_) => format!("{:?}", result),
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => {
+                let output = call_output_view(self.app_service.last_call_output());
+                return match output.is_empty() {
+                    true => Err(format!("call failed with: {}", e)),
+                    false => Err(format!("call failed with: {}\n{}", e, output)),
                 };
-
-                format!(
-                    "result: {}\n elapsed time: {:?}",
-                    result_string, elapsed_time
-                )
             }
-            Ok(Ok(_)) => {
-                let elapsed_time = start.elapsed();
-                format!("call succeeded, elapsed time: {:?}", elapsed_time)
+            Err(elapsed) => {
+                return Err(format!(
+                    "call interrupted: {} ({:#?})",
+                    elapsed, self.timeout
+                ))
             }
-            Ok(Err(e)) => format!("call failed with: {}", e),
-            Err(elapsed) => format!("call interrupted: {} ({:#?})", elapsed, self.timeout),
         };
 
-        println!("{}", result);
+        let elapsed_time = start.elapsed();
+        if show_result_arg {
+            let result_string = match serde_json::to_string_pretty(&result) {
+                Ok(pretty_printed) => pretty_printed,
+                Err(_) => format!("{:?}", result),
+            };
+
+            println!(
+                "result: {}\n elapsed time: {:?}",
+                result_string, elapsed_time
+            );
+        } else {
+            println!("call succeeded, elapsed time: {:?}", elapsed_time);
+        }
+
+        let output = call_output_view(self.app_service.last_call_output());
+        if !output.is_empty() {
+            println!("{}", output);
+        }
+
+        self.last_call_result = Some(result);
+        Ok(())
     }
 
-    fn show_envs<'args>(&mut self, mut args: impl Iterator<Item = &'args str>) {
-        next_argument!(module_name, args, "Module name should be specified");
-        match self.app_service.get_wasi_state(module_name) {
-            Ok(wasi_state) => print_envs(module_name, wasi_state.as_ref()),
-            Err(e) => println!("{}", e),
-        };
+    fn show_envs<'args>(&mut self, mut args: impl Iterator<Item = &'args str>) -> CommandResult {
+        next_argument_or_result!(module_name, args, "Module name should be specified");
+        let wasi_state = self
+            .app_service
+            .get_wasi_state(module_name)
+            .map_err(|e| e.to_string())?;
+
+        print_envs(module_name, wasi_state.as_ref());
+        Ok(())
     }
 
-    fn show_fs<'args>(&mut self, mut args: impl Iterator<Item = &'args str>) {
-        next_argument!(module_name, args, "Module name should be specified");
-        match self.app_service.get_wasi_state(module_name) {
-            Ok(wasi_state) => print_fs_state(wasi_state.as_ref()),
-            Err(e) => println!("{}", e),
-        };
+    fn show_fs<'args>(&mut self, mut args: impl Iterator<Item = &'args str>) -> CommandResult {
+        next_argument_or_result!(module_name, args, "Module name should be specified");
+        let wasi_state = self
+            .app_service
+            .get_wasi_state(module_name)
+            .map_err(|e| e.to_string())?;
+
+        print_fs_state(module_name, wasi_state.as_ref());
+        Ok(())
     }
 
     fn show_interface(&mut self) {
@@ -267,6 +336,64 @@ impl REPL {
         print!("Loaded modules heap sizes:\n{}", statistic);
     }
 
+    fn show_memory<'args>(&self, mut args: impl Iterator<Item = &'args str>) -> CommandResult {
+        next_argument_or_result!(module_name, args, "Module name should be specified");
+        next_argument_or_result!(offset, args, "Offset should be specified");
+        next_argument_or_result!(len, args, "Length should be specified");
+        let offset = memory::parse_number(offset)?;
+        let len = memory::parse_number(len)?;
+
+        let bytes = self
+            .app_service
+            .read_module_memory(module_name, offset, len)
+            .map_err(|e| e.to_string())?;
+
+        memory::print_hexdump(offset, &bytes);
+        Ok(())
+    }
+
+    fn find_in_memory<'args>(&self, mut args: impl Iterator<Item = &'args str>) -> CommandResult {
+        use itertools::Itertools;
+
+        next_argument_or_result!(module_name, args, "Module name should be specified");
+        // strings could contain spaces, they are split by the command parser
+        let pattern = memory::parse_pattern(&args.join(" "))?;
+
+        let memory = self.read_whole_memory(module_name)?;
+        let matches = memory::find_all(&memory, &pattern);
+
+        memory::print_matches(module_name, &matches);
+        Ok(())
+    }
+
+    fn dump_memory<'args>(&self, mut args: impl Iterator<Item = &'args str>) -> CommandResult {
+        next_argument_or_result!(module_name, args, "Module name should be specified");
+        next_argument_or_result!(file_path, args, "File path should be specified");
+
+        let memory = self.read_whole_memory(module_name)?;
+        fs::write(file_path, &memory)
+            .map_err(|e| format!("failed to write memory to {}: {}", file_path, e))?;
+
+        println!(
+            "{} bytes of {} memory are written to {}",
+            memory.len(),
+            module_name,
+            file_path
+        );
+        Ok(())
+    }
+
+    fn read_whole_memory(&self, module_name: &str) -> Result<Vec<u8>, String> {
+        let memory_size = self
+            .app_service
+            .module_memory_size(module_name)
+            .map_err(|e| e.to_string())?;
+
+        self.app_service
+            .read_module_memory(module_name, 0, memory_size)
+            .map_err(|e| e.to_string())
+    }
+
     async fn create_app_service<S: Into<PathBuf>>(
         app_service_factory: &AppServiceFactory,
         config_file_path: Option<S>,
@@ -480,6 +607,9 @@ fn print_help() {
             s/stats                                               print memory size of all loaded modules\n\
             e/envs <module_name>                                  print environment variables of a module\n\
             f/fs <module_name>                                    print filesystem state of a module\n\
+            m/mem <module_name> <offset> <len>                    print a hexdump of a module memory range\n\
+            mem-find <module_name> <0xbytes|string>               print offsets of bytes or a string in a module memory\n\
+            mem-dump <module_name> <file_path>                    write a whole module memory to a file\n\
             s/stats                                               print consumed memory size of each module\n\
             h/help                                                print this message\n\
             q/quit/Ctrl-C                                         exit\n\
@@ -0,0 +1,94 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+const HEXDUMP_LINE_WIDTH: usize = 16;
+const MAX_PRINTED_MATCHES: usize = 64;
+
+/// Parses a decimal or `0x`-prefixed hexadecimal number.
+pub(super) fn parse_number(number: &str) -> Result<usize, String> {
+    let result = match number.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => number.parse::<usize>(),
+    };
+
+    result.map_err(|e| format!("invalid number {}: {}", number, e))
+}
+
+/// Parses a search pattern, it's either `0x`-prefixed hex bytes or a string.
+pub(super) fn parse_pattern(pattern: &str) -> Result<Vec<u8>, String> {
+    let bytes = match pattern.strip_prefix("0x") {
+        Some(hex) => hex::decode(hex).map_err(|e| format!("invalid hex bytes {}: {}", hex, e))?,
+        None => pattern.as_bytes().to_vec(),
+    };
+
+    if bytes.is_empty() {
+        return Err(String::from("search pattern shouldn't be empty"));
+    }
+
+    Ok(bytes)
+}
+
+/// Returns offsets of all, possibly overlapping, occurrences of the pattern.
+pub(super) fn find_all(memory: &[u8], pattern: &[u8]) -> Vec<usize> {
+    memory
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| *window == pattern)
+        .map(|(offset, _)| offset)
+        .collect()
+}
+
+pub(super) fn print_matches(module_name: &str, matches: &[usize]) {
+    if matches.is_empty() {
+        println!("pattern isn't found in {} memory", module_name);
+        return;
+    }
+
+    println!("found {} occurrence(s) at:", matches.len());
+    for offset in matches.iter().take(MAX_PRINTED_MATCHES) {
+        println!("  {:#010x}", offset);
+    }
+
+    if matches.len() > MAX_PRINTED_MATCHES {
+        println!("  ... and {} more", matches.len() - MAX_PRINTED_MATCHES);
+    }
+}
+
+/// Prints bytes in the `hexdump -C` format, `offset` is the address of the first byte.
+pub(super) fn print_hexdump(offset: usize, bytes: &[u8]) {
+    for (line_id, line) in bytes.chunks(HEXDUMP_LINE_WIDTH).enumerate() {
+        let hex = line
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = line
+            .iter()
+            .map(|&byte| match byte {
+                0x20..=0x7e => byte as char,
+                _ => '.',
+            })
+            .collect::<String>();
+
+        println!(
+            "{:08x}  {:<width$}  |{}|",
+            offset + line_id * HEXDUMP_LINE_WIDTH,
+            hex,
+            ascii,
+            width = HEXDUMP_LINE_WIDTH * 3 - 1
+        );
+    }
+}
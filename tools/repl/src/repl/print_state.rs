@@ -14,6 +14,9 @@
  * limitations under the License.
  */
 
+use fluence_app_service::ModuleCallOutput;
+use marine_wasm_backend_traits::OpenFdKind;
+use marine_wasm_backend_traits::PreopenedDir;
 use marine_wasm_backend_traits::WasiState;
 
 pub(super) fn print_envs(module_name: &str, wasi_state: &dyn WasiState) {
@@ -32,6 +35,95 @@ pub(super) fn print_envs(module_name: &str, wasi_state: &dyn WasiState) {
     }
 }
 
-pub(super) fn print_fs_state(_wasi_state: &dyn WasiState) {
-    println!("Printing WASI filesystem state is not supported now.");
+pub(super) fn print_fs_state(module_name: &str, wasi_state: &dyn WasiState) {
+    let args = wasi_state.args();
+    if !args.is_empty() {
+        println!("Arguments: {}", args.join(" "));
+    }
+
+    let preopened_dirs = wasi_state.preopened_dirs();
+    if preopened_dirs.is_empty() {
+        println!("{} don't have mapped directories", module_name);
+    } else {
+        println!("Mapped directories:");
+        for dir in preopened_dirs {
+            let host_path = match &dir.host_path {
+                Some(host_path) => host_path.display().to_string(),
+                None => String::from("<memory>"),
+            };
+            println!(
+                "  fd {}: {} -> {}{}",
+                dir.fd,
+                dir.guest_path,
+                host_path,
+                dir_policy_view(dir)
+            );
+        }
+    }
+
+    let open_fds = wasi_state.open_fds();
+    if open_fds.is_empty() {
+        // the backend doesn't provide file descriptors, there are always stdio ones otherwise
+        return;
+    }
+
+    println!("Open file descriptors:");
+    for fd in open_fds {
+        let kind = match fd.kind {
+            OpenFdKind::Stdio => "stdio",
+            OpenFdKind::PreopenedDir => "mapped directory",
+            OpenFdKind::Opened => "opened by module",
+        };
+        println!("  {}: {}", fd.fd, kind);
+    }
+}
+
+fn dir_policy_view(dir: &PreopenedDir) -> String {
+    let mut restrictions = Vec::new();
+    if dir.policy.read_only {
+        restrictions.push(String::from("read-only"));
+    }
+
+    let usage = dir.usage.unwrap_or_default();
+    if let Some(max_bytes) = dir.policy.max_bytes {
+        restrictions.push(format!("{} of {} bytes", usage.bytes, max_bytes));
+    }
+    if let Some(max_files) = dir.policy.max_files {
+        restrictions.push(format!("{} of {} files", usage.files, max_files));
+    }
+
+    match restrictions.is_empty() {
+        true => String::new(),
+        false => format!(" ({})", restrictions.join(", ")),
+    }
+}
+
+/// Returns stdout and stderr captured during a call, indented under the module names.
+pub(super) fn call_output_view(outputs: &[ModuleCallOutput]) -> String {
+    let mut lines = Vec::new();
+    for ModuleCallOutput {
+        module_name,
+        output,
+    } in outputs
+    {
+        for (stream, data) in [("stdout", &output.stdout), ("stderr", &output.stderr)] {
+            if data.is_empty() {
+                continue;
+            }
+
+            lines.push(format!("{} {}:", module_name, stream));
+            for line in String::from_utf8_lossy(data).lines() {
+                lines.push(format!("    {}", line));
+            }
+        }
+
+        if output.truncated {
+            lines.push(format!(
+                "{} output exceeded the limit and was truncated",
+                module_name
+            ));
+        }
+    }
+
+    lines.join("\n")
 }
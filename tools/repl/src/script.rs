@@ -0,0 +1,256 @@
+/*
+ * Copyright 2023 Fluence Labs Limited
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::repl::REPL;
+use crate::ReplResult;
+
+use anyhow::anyhow;
+use serde_json::Value as JValue;
+
+use std::path::Path;
+
+/*
+An example of a script:
+
+# lines starting with # are comments
+call greeting greeting ["user"]
+expect "Hi, user"
+
+call records_passing_pure test_record {...}
+# only the listed fields are compared
+expect subset {"test_record_0": {"field_0": 0}}
+
+call greeting unknown_function []
+# the previous command must fail with an error containing the text
+expect error unknown_function
+ */
+
+const EXPECT_COMMAND: &str = "expect";
+
+enum Expectation {
+    /// The last call result must be equal to the value.
+    Exact(JValue),
+    /// The last call result must contain all fields and elements of the value.
+    Subset(JValue),
+    /// The last command must fail with an error containing the text.
+    Error(String),
+}
+
+/// Executes commands from the script line by line, stops on the first failed command
+/// or unmet expectation and returns it as an error.
+pub(crate) async fn run_script(repl: &mut REPL, script_path: &Path) -> ReplResult<()> {
+    let script = std::fs::read_to_string(script_path)
+        .map_err(|e| anyhow!("failed to read {}: {}", script_path.display(), e))?;
+
+    let mut lines = script
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    let fail = |line_number: usize, message: String| {
+        anyhow!("{}:{}: {}", script_path.display(), line_number, message)
+    };
+
+    let mut expectations_count = 0;
+    while let Some((line_number, line)) = lines.next() {
+        println!("\n> {}", line);
+
+        if let Some(expectation) = parse_expectation(line) {
+            let expectation = expectation.map_err(|e| fail(line_number, e))?;
+            if let Expectation::Error(_) = expectation {
+                return Err(fail(
+                    line_number,
+                    String::from("the previous command succeeded, but an error is expected"),
+                ));
+            }
+
+            check_call_result(repl.last_call_result(), &expectation)
+                .map_err(|e| fail(line_number, e))?;
+            expectations_count += 1;
+            continue;
+        }
+
+        let error = match repl.execute_command(line.split_whitespace()).await {
+            Ok(true) => continue,
+            Ok(false) => break,
+            Err(error) => error,
+        };
+
+        println!("{}", error);
+        let expected_error = match lines.peek() {
+            Some((_, next_line)) => match parse_expectation(next_line) {
+                Some(Ok(Expectation::Error(text))) => text,
+                _ => return Err(fail(line_number, error)),
+            },
+            None => return Err(fail(line_number, error)),
+        };
+
+        let (expectation_line_number, expectation_line) = lines.next().unwrap();
+        println!("\n> {}", expectation_line);
+        if !error.contains(&expected_error) {
+            return Err(fail(
+                expectation_line_number,
+                format!("the error doesn't contain \"{}\"", expected_error),
+            ));
+        }
+        expectations_count += 1;
+    }
+
+    println!(
+        "\nscript {} passed, {} expectation(s) met",
+        script_path.display(),
+        expectations_count
+    );
+
+    Ok(())
+}
+
+/// Returns None if the line isn't an expectation.
+fn parse_expectation(line: &str) -> Option<Result<Expectation, String>> {
+    let rest = line.strip_prefix(EXPECT_COMMAND)?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rest = rest.trim_start();
+
+    let (kind, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let expectation = match kind {
+        "error" => Ok(Expectation::Error(value.trim().to_string())),
+        "subset" => parse_json(value).map(Expectation::Subset),
+        _ => parse_json(rest).map(Expectation::Exact),
+    };
+
+    Some(expectation)
+}
+
+fn parse_json(value: &str) -> Result<JValue, String> {
+    serde_json::from_str(value).map_err(|e| format!("invalid expected value: {}", e))
+}
+
+fn check_call_result(actual: Option<&JValue>, expectation: &Expectation) -> Result<(), String> {
+    let actual = actual.ok_or_else(|| String::from("there is no call result to check"))?;
+
+    let (expected, is_met) = match expectation {
+        Expectation::Exact(expected) => (expected, actual == expected),
+        Expectation::Subset(expected) => (expected, is_subset(expected, actual)),
+        Expectation::Error(_) => unreachable!("errors are checked after failed commands"),
+    };
+
+    if is_met {
+        return Ok(());
+    }
+
+    Err(format!(
+        "unexpected call result:\n  expected: {}\n    actual: {}",
+        expected, actual
+    ))
+}
+
+/// Checks that all fields of expected objects are present in actual ones with matching values,
+/// arrays should have the same length and matching elements.
+fn is_subset(expected: &JValue, actual: &JValue) -> bool {
+    match (expected, actual) {
+        (JValue::Object(expected), JValue::Object(actual)) => {
+            expected.iter().all(|(key, expected)| {
+                actual
+                    .get(key)
+                    .map_or(false, |actual| is_subset(expected, actual))
+            })
+        }
+        (JValue::Array(expected), JValue::Array(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual.iter())
+                    .all(|(expected, actual)| is_subset(expected, actual))
+        }
+        (expected, actual) => expected == actual,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_subset;
+    use super::parse_expectation;
+    use super::Expectation;
+
+    use serde_json::json;
+
+    #[test]
+    fn subset_of_objects() {
+        let actual = json!({"name": "user", "age": 42, "address": {"city": "Paris", "zip": 1}});
+
+        assert!(is_subset(&json!({}), &actual));
+        assert!(is_subset(&json!({"name": "user"}), &actual));
+        assert!(is_subset(&json!({"address": {"city": "Paris"}}), &actual));
+        assert!(!is_subset(&json!({"name": "other"}), &actual));
+        assert!(!is_subset(&json!({"absent": null}), &actual));
+        assert!(!is_subset(&json!({"address": {"city": "Rome"}}), &actual));
+    }
+
+    #[test]
+    fn subset_of_arrays() {
+        let actual = json!([{"a": 1, "b": 2}, {"a": 3, "b": 4}]);
+
+        assert!(is_subset(&json!([{"a": 1}, {"b": 4}]), &actual));
+        // arrays must have the same length
+        assert!(!is_subset(&json!([{"a": 1}]), &actual));
+        assert!(!is_subset(&json!([{"a": 3}, {"a": 1}]), &actual));
+    }
+
+    #[test]
+    fn subset_of_scalars() {
+        assert!(is_subset(&json!(1), &json!(1)));
+        assert!(is_subset(&json!("text"), &json!("text")));
+        assert!(!is_subset(&json!(1), &json!("1")));
+        assert!(!is_subset(&json!({"a": 1}), &json!([1])));
+    }
+
+    #[test]
+    fn parse_expectations() {
+        assert!(std::matches!(
+            parse_expectation(r#"expect "Hi, user""#),
+            Some(Ok(Expectation::Exact(value))) if value == json!("Hi, user")
+        ));
+        assert!(std::matches!(
+            parse_expectation(r#"expect subset {"a": 1}"#),
+            Some(Ok(Expectation::Subset(value))) if value == json!({"a": 1})
+        ));
+        assert!(std::matches!(
+            parse_expectation("expect error unknown_function "),
+            Some(Ok(Expectation::Error(text))) if text == "unknown_function"
+        ));
+    }
+
+    #[test]
+    fn parse_not_expectations() {
+        assert!(parse_expectation("call greeting greeting []").is_none());
+        // the command must be followed by a whitespace
+        assert!(parse_expectation("expected 1").is_none());
+    }
+
+    #[test]
+    fn parse_invalid_expectations() {
+        assert!(std::matches!(parse_expectation("expect"), Some(Err(_))));
+        assert!(std::matches!(parse_expectation("expect {"), Some(Err(_))));
+        assert!(std::matches!(
+            parse_expectation("expect subset"),
+            Some(Err(_))
+        ));
+    }
+}